@@ -0,0 +1,5 @@
+CREATE TABLE if NOT EXISTS archived_swaps
+(
+    swap_id     TEXT    PRIMARY KEY NOT NULL,
+    archived_at TEXT                NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE if NOT EXISTS sellers
+(
+    peer_id     TEXT    PRIMARY KEY NOT NULL,
+    name        TEXT
+);
+
+INSERT OR IGNORE INTO sellers (peer_id) SELECT DISTINCT peer_id FROM peers;
@@ -0,0 +1 @@
+ALTER TABLE sellers ADD COLUMN agent_version TEXT;
@@ -0,0 +1 @@
+ALTER TABLE sellers ADD COLUMN removed BOOLEAN NOT NULL DEFAULT FALSE;
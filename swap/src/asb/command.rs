@@ -1,9 +1,9 @@
 use crate::asb::config::GetDefaults;
-use crate::bitcoin::Amount;
+use crate::bitcoin::{parse_fee_rate, Amount, Recipient, Withdrawal};
 use crate::env;
-use crate::env::GetConfig;
 use anyhow::{bail, Result};
-use bitcoin::Address;
+use bitcoin::{Address, OutPoint, Txid};
+use rust_decimal::Decimal;
 use serde::Serialize;
 use std::ffi::OsString;
 use std::path::PathBuf;
@@ -20,71 +20,98 @@ where
 
     let json = args.json;
     let disable_timestamp = args.disable_timestamp;
-    let testnet = args.testnet;
+    let network = env::Network::from_flags(args.testnet, args.signet, args.regtest);
     let config = args.config;
     let command: RawCommand = args.cmd;
 
     let arguments = match command {
         RawCommand::Start { resume_only } => Arguments {
-            testnet,
+            network,
             json,
             disable_timestamp,
-            config_path: config_path(config, testnet)?,
-            env_config: env_config(testnet),
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
             cmd: Command::Start { resume_only },
         },
         RawCommand::History => Arguments {
-            testnet,
+            network,
             json,
             disable_timestamp,
-            config_path: config_path(config, testnet)?,
-            env_config: env_config(testnet),
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
             cmd: Command::History,
         },
-        RawCommand::WithdrawBtc { amount, address } => Arguments {
-            testnet,
+        RawCommand::WithdrawBtc {
+            withdraw,
+            psbt_only,
+        } => Arguments {
+            network,
             json,
             disable_timestamp,
-            config_path: config_path(config, testnet)?,
-            env_config: env_config(testnet),
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
             cmd: Command::WithdrawBtc {
-                amount,
-                address: bitcoin_address(address, testnet)?,
+                withdrawal: withdraw.apply(network)?,
+                psbt_only,
             },
         },
         RawCommand::Balance => Arguments {
-            testnet,
+            network,
             json,
             disable_timestamp,
-            config_path: config_path(config, testnet)?,
-            env_config: env_config(testnet),
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
             cmd: Command::Balance,
         },
         RawCommand::Config => Arguments {
-            testnet,
+            network,
             json,
             disable_timestamp,
-            config_path: config_path(config, testnet)?,
-            env_config: env_config(testnet),
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
             cmd: Command::Config,
         },
+        RawCommand::BumpFee { txid, fee_rate } => Arguments {
+            network,
+            json,
+            disable_timestamp,
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
+            cmd: Command::BumpFee { txid, fee_rate },
+        },
         RawCommand::ExportBitcoinWallet => Arguments {
-            testnet,
+            network,
             json,
             disable_timestamp,
-            config_path: config_path(config, testnet)?,
-            env_config: env_config(testnet),
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
             cmd: Command::ExportBitcoinWallet,
         },
+        RawCommand::EncryptSeed => Arguments {
+            network,
+            json,
+            disable_timestamp,
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
+            cmd: Command::EncryptSeed,
+        },
+        RawCommand::ChangeSeedPassphrase => Arguments {
+            network,
+            json,
+            disable_timestamp,
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
+            cmd: Command::ChangeSeedPassphrase,
+        },
         RawCommand::ManualRecovery(ManualRecovery::Redeem {
             redeem_params: RecoverCommandParams { swap_id },
             do_not_await_finality,
         }) => Arguments {
-            testnet,
+            network,
             json,
             disable_timestamp,
-            config_path: config_path(config, testnet)?,
-            env_config: env_config(testnet),
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
             cmd: Command::Redeem {
                 swap_id,
 
@@ -94,39 +121,39 @@ where
         RawCommand::ManualRecovery(ManualRecovery::Cancel {
             cancel_params: RecoverCommandParams { swap_id },
         }) => Arguments {
-            testnet,
+            network,
             json,
             disable_timestamp,
-            config_path: config_path(config, testnet)?,
-            env_config: env_config(testnet),
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
             cmd: Command::Cancel { swap_id },
         },
         RawCommand::ManualRecovery(ManualRecovery::Refund {
             refund_params: RecoverCommandParams { swap_id },
         }) => Arguments {
-            testnet,
+            network,
             json,
             disable_timestamp,
-            config_path: config_path(config, testnet)?,
-            env_config: env_config(testnet),
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
             cmd: Command::Refund { swap_id },
         },
         RawCommand::ManualRecovery(ManualRecovery::Punish {
             punish_params: RecoverCommandParams { swap_id },
         }) => Arguments {
-            testnet,
+            network,
             json,
             disable_timestamp,
-            config_path: config_path(config, testnet)?,
-            env_config: env_config(testnet),
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
             cmd: Command::Punish { swap_id },
         },
         RawCommand::ManualRecovery(ManualRecovery::SafelyAbort { swap_id }) => Arguments {
-            testnet,
+            network,
             json,
             disable_timestamp,
-            config_path: config_path(config, testnet)?,
-            env_config: env_config(testnet),
+            config_path: config_path(config, network)?,
+            env_config: network.get_config(),
             cmd: Command::SafelyAbort { swap_id },
         },
     };
@@ -134,14 +161,85 @@ where
     Ok(arguments)
 }
 
-fn bitcoin_address(address: Address, is_testnet: bool) -> Result<Address> {
-    let network = if is_testnet {
-        bitcoin::Network::Testnet
-    } else {
-        bitcoin::Network::Bitcoin
-    };
+#[derive(structopt::StructOpt, Debug)]
+struct Withdraw {
+    #[structopt(
+        long = "amount",
+        help = "Optionally specify the amount of Bitcoin to be withdrawn. If not specified, everything that is left after paying the outputs is sent to the address, without outputs the wallet will be drained. Amount must be specified in quotes with denomination, e.g `--amount '0.1 BTC'`",
+        requires = "address"
+    )]
+    amount: Option<Amount>,
+
+    #[structopt(
+        long = "address",
+        help = "The address to receive the Bitcoin.",
+        required_unless = "outputs"
+    )]
+    address: Option<Address>,
+
+    #[structopt(
+        long = "output",
+        help = "An additional payment given as address and amount with denomination, e.g `--output '<address>:0.1 BTC'`. Can be given multiple times.",
+        number_of_values = 1
+    )]
+    outputs: Vec<Recipient>,
+
+    #[structopt(
+        long = "fee-rate",
+        help = "The fee rate in sat/vB. Estimated from the configured confirmation target if not specified.",
+        parse(try_from_str = parse_fee_rate)
+    )]
+    fee_rate: Option<Decimal>,
+
+    #[structopt(
+        long = "utxo",
+        help = "Spend only this output of the wallet, given as `<txid>:<vout>`. Can be given multiple times.",
+        number_of_values = 1
+    )]
+    utxos: Vec<OutPoint>,
+
+    #[structopt(
+        long = "rbf",
+        help = "Signal that the transaction can be replaced by one paying a higher fee (BIP-125)"
+    )]
+    rbf: bool,
+}
+
+impl Withdraw {
+    fn apply(self, network: env::Network) -> Result<Withdrawal> {
+        let address = self
+            .address
+            .map(|address| bitcoin_address(address, network))
+            .transpose()?;
+        let outputs = self
+            .outputs
+            .into_iter()
+            .map(|output| {
+                Ok(Recipient {
+                    address: bitcoin_address(output.address, network)?,
+                    amount: output.amount,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Withdrawal::new(
+            address,
+            self.amount,
+            outputs,
+            self.fee_rate,
+            self.utxos,
+            self.rbf,
+        )
+    }
+}
 
-    if address.network != network {
+/// Signet addresses are parsed as testnet addresses because both use the same
+/// encoding, which is why the network is checked with
+/// [`Address::is_valid_for_network`].
+fn bitcoin_address(address: Address, network: env::Network) -> Result<Address> {
+    let network = network.get_config().bitcoin_network;
+
+    if !address.is_valid_for_network(network) {
         bail!(BitcoinAddressNetworkMismatch {
             expected: network,
             actual: address.network
@@ -151,26 +249,18 @@ fn bitcoin_address(address: Address, is_testnet: bool) -> Result<Address> {
     Ok(address)
 }
 
-fn config_path(config: Option<PathBuf>, is_testnet: bool) -> Result<PathBuf> {
-    let config_path = if let Some(config_path) = config {
-        config_path
-    } else if is_testnet {
-        env::Testnet::getConfigFileDefaults()?.config_path
-    } else {
-        env::Mainnet::getConfigFileDefaults()?.config_path
+fn config_path(config: Option<PathBuf>, network: env::Network) -> Result<PathBuf> {
+    let config_path = match (config, network) {
+        (Some(config_path), _) => config_path,
+        (None, env::Network::Mainnet) => env::Mainnet::getConfigFileDefaults()?.config_path,
+        (None, env::Network::Testnet) => env::Testnet::getConfigFileDefaults()?.config_path,
+        (None, env::Network::Signet) => env::Signet::getConfigFileDefaults()?.config_path,
+        (None, env::Network::Regtest) => env::Regtest::getConfigFileDefaults()?.config_path,
     };
 
     Ok(config_path)
 }
 
-fn env_config(is_testnet: bool) -> env::Config {
-    if is_testnet {
-        env::Testnet::get_config()
-    } else {
-        env::Mainnet::get_config()
-    }
-}
-
 #[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[error("Invalid Bitcoin address provided, expected address on network {expected:?}  but address provided is on {actual:?}")]
 pub struct BitcoinAddressNetworkMismatch {
@@ -182,7 +272,7 @@ pub struct BitcoinAddressNetworkMismatch {
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Arguments {
-    pub testnet: bool,
+    pub network: env::Network,
     pub json: bool,
     pub disable_timestamp: bool,
     pub config_path: PathBuf,
@@ -198,8 +288,8 @@ pub enum Command {
     History,
     Config,
     WithdrawBtc {
-        amount: Option<Amount>,
-        address: Address,
+        withdrawal: Withdrawal,
+        psbt_only: bool,
     },
     Balance,
     Redeem {
@@ -218,7 +308,13 @@ pub enum Command {
     SafelyAbort {
         swap_id: Uuid,
     },
+    BumpFee {
+        txid: Txid,
+        fee_rate: Option<Decimal>,
+    },
     ExportBitcoinWallet,
+    EncryptSeed,
+    ChangeSeedPassphrase,
 }
 
 #[derive(structopt::StructOpt, Debug)]
@@ -232,6 +328,20 @@ pub struct RawArguments {
     #[structopt(long, help = "Swap on testnet")]
     pub testnet: bool,
 
+    #[structopt(
+        long,
+        help = "Swap on Bitcoin signet and Monero stagenet",
+        conflicts_with = "testnet"
+    )]
+    pub signet: bool,
+
+    #[structopt(
+        long,
+        help = "Swap on local Bitcoin and Monero regtest nodes",
+        conflicts_with_all = &["testnet", "signet"]
+    )]
+    pub regtest: bool,
+
     #[structopt(
         short,
         long = "json",
@@ -274,20 +384,43 @@ pub enum RawCommand {
     Config,
     #[structopt(about = "Allows withdrawing BTC from the internal Bitcoin wallet.")]
     WithdrawBtc {
+        #[structopt(flatten)]
+        withdraw: Withdraw,
+
         #[structopt(
-            long = "amount",
-            help = "Optionally specify the amount of Bitcoin to be withdrawn. If not specified the wallet will be drained. Amount must be specified in quotes with denomination, e.g `--amount '0.1 BTC'`"
+            long = "psbt-only",
+            help = "Print the unsigned transaction as base64 encoded PSBT instead of signing and broadcasting it"
         )]
-        amount: Option<Amount>,
-        #[structopt(long = "address", help = "The address to receive the Bitcoin.")]
-        address: Address,
+        psbt_only: bool,
     },
     #[structopt(
         about = "Prints the Bitcoin and Monero balance. Requires the monero-wallet-rpc to be running."
     )]
     Balance,
+    #[structopt(
+        about = "Speed up an unconfirmed transaction of the internal Bitcoin wallet, or a swap transaction paying to it, by paying a higher fee."
+    )]
+    BumpFee {
+        #[structopt(long = "txid", help = "The id of the transaction to speed up")]
+        txid: Txid,
+
+        #[structopt(
+            long = "fee-rate",
+            help = "The new fee rate in sat/vB. Estimated from the configured confirmation target if not specified.",
+            parse(try_from_str = parse_fee_rate)
+        )]
+        fee_rate: Option<Decimal>,
+    },
     #[structopt(about = "Print the internal bitcoin wallet descriptor.")]
     ExportBitcoinWallet,
+    #[structopt(
+        about = "Encrypts an unencrypted seed file with a passphrase. The passphrase is read from SWAP_SEED_NEW_PASSPHRASE or asked for interactively."
+    )]
+    EncryptSeed,
+    #[structopt(
+        about = "Changes the passphrase of an encrypted seed file. The current passphrase is read from SWAP_SEED_PASSPHRASE (or the file descriptor in SWAP_SEED_PASSPHRASE_FD) and the new one from SWAP_SEED_NEW_PASSPHRASE, otherwise both are asked for interactively."
+    )]
+    ChangeSeedPassphrase,
     #[structopt(about = "Contains sub-commands for recovering a swap manually.")]
     ManualRecovery(ManualRecovery),
 }
@@ -350,6 +483,7 @@ pub struct RecoverCommandParams {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::env::GetConfig;
     use std::str::FromStr;
 
     const BINARY_NAME: &str = "asb";
@@ -364,7 +498,7 @@ mod tests {
 
         let raw_ars = vec![BINARY_NAME, "start"];
         let expected_args = Arguments {
-            testnet: false,
+            network: env::Network::Mainnet,
             json: false,
             disable_timestamp: false,
             config_path: default_mainnet_conf_path,
@@ -382,7 +516,7 @@ mod tests {
 
         let raw_ars = vec![BINARY_NAME, "history"];
         let expected_args = Arguments {
-            testnet: false,
+            network: env::Network::Mainnet,
             json: false,
             disable_timestamp: false,
             config_path: default_mainnet_conf_path,
@@ -400,7 +534,7 @@ mod tests {
 
         let raw_ars = vec![BINARY_NAME, "balance"];
         let expected_args = Arguments {
-            testnet: false,
+            network: env::Network::Mainnet,
             json: false,
             disable_timestamp: false,
             config_path: default_mainnet_conf_path,
@@ -422,14 +556,45 @@ mod tests {
             BITCOIN_MAINNET_ADDRESS,
         ];
         let expected_args = Arguments {
-            testnet: false,
+            network: env::Network::Mainnet,
             json: false,
             disable_timestamp: false,
             config_path: default_mainnet_conf_path,
             env_config: mainnet_env_config,
             cmd: Command::WithdrawBtc {
-                amount: None,
-                address: Address::from_str(BITCOIN_MAINNET_ADDRESS).unwrap(),
+                withdrawal: Withdrawal {
+                    drain_to: Some(Address::from_str(BITCOIN_MAINNET_ADDRESS).unwrap()),
+                    ..Withdrawal::default()
+                },
+                psbt_only: false,
+            },
+        };
+        let args = parse_args(raw_ars).unwrap();
+        assert_eq!(expected_args, args);
+    }
+
+    #[test]
+    fn ensure_bump_fee_command_mapping_mainnet() {
+        let default_mainnet_conf_path = env::Mainnet::getConfigFileDefaults().unwrap().config_path;
+        let mainnet_env_config = env::Mainnet::get_config();
+        let txid = "ea030832ea030832ea030832ea030832ea030832ea030832ea030832ea030832";
+        let raw_ars = vec![
+            BINARY_NAME,
+            "bump-fee",
+            "--txid",
+            txid,
+            "--fee-rate",
+            "12.5",
+        ];
+        let expected_args = Arguments {
+            network: env::Network::Mainnet,
+            json: false,
+            disable_timestamp: false,
+            config_path: default_mainnet_conf_path,
+            env_config: mainnet_env_config,
+            cmd: Command::BumpFee {
+                txid: txid.parse().unwrap(),
+                fee_rate: Some(Decimal::new(125, 1)),
             },
         };
         let args = parse_args(raw_ars).unwrap();
@@ -449,7 +614,7 @@ mod tests {
             SWAP_ID,
         ];
         let expected_args = Arguments {
-            testnet: false,
+            network: env::Network::Mainnet,
             json: false,
             disable_timestamp: false,
             config_path: default_mainnet_conf_path,
@@ -475,7 +640,7 @@ mod tests {
             SWAP_ID,
         ];
         let expected_args = Arguments {
-            testnet: false,
+            network: env::Network::Mainnet,
             json: false,
             disable_timestamp: false,
             config_path: default_mainnet_conf_path,
@@ -501,7 +666,7 @@ mod tests {
             SWAP_ID,
         ];
         let expected_args = Arguments {
-            testnet: false,
+            network: env::Network::Mainnet,
             json: false,
             disable_timestamp: false,
             config_path: default_mainnet_conf_path,
@@ -527,7 +692,7 @@ mod tests {
             SWAP_ID,
         ];
         let expected_args = Arguments {
-            testnet: false,
+            network: env::Network::Mainnet,
             json: false,
             disable_timestamp: false,
             config_path: default_mainnet_conf_path,
@@ -540,6 +705,42 @@ mod tests {
         assert_eq!(expected_args, args);
     }
 
+    #[test]
+    fn ensure_encrypt_seed_command_mapping_mainnet() {
+        let default_mainnet_conf_path = env::Mainnet::getConfigFileDefaults().unwrap().config_path;
+        let mainnet_env_config = env::Mainnet::get_config();
+
+        let raw_ars = vec![BINARY_NAME, "encrypt-seed"];
+        let expected_args = Arguments {
+            network: env::Network::Mainnet,
+            json: false,
+            disable_timestamp: false,
+            config_path: default_mainnet_conf_path,
+            env_config: mainnet_env_config,
+            cmd: Command::EncryptSeed,
+        };
+        let args = parse_args(raw_ars).unwrap();
+        assert_eq!(expected_args, args);
+    }
+
+    #[test]
+    fn ensure_change_seed_passphrase_command_mapping_mainnet() {
+        let default_mainnet_conf_path = env::Mainnet::getConfigFileDefaults().unwrap().config_path;
+        let mainnet_env_config = env::Mainnet::get_config();
+
+        let raw_ars = vec![BINARY_NAME, "change-seed-passphrase"];
+        let expected_args = Arguments {
+            network: env::Network::Mainnet,
+            json: false,
+            disable_timestamp: false,
+            config_path: default_mainnet_conf_path,
+            env_config: mainnet_env_config,
+            cmd: Command::ChangeSeedPassphrase,
+        };
+        let args = parse_args(raw_ars).unwrap();
+        assert_eq!(expected_args, args);
+    }
+
     #[test]
     fn ensure_start_command_mapping_for_testnet() {
         let default_testnet_conf_path = env::Testnet::getConfigFileDefaults().unwrap().config_path;
@@ -547,7 +748,7 @@ mod tests {
 
         let raw_ars = vec![BINARY_NAME, "--testnet", "start"];
         let expected_args = Arguments {
-            testnet: true,
+            network: env::Network::Testnet,
             json: false,
             disable_timestamp: false,
             config_path: default_testnet_conf_path,
@@ -565,7 +766,7 @@ mod tests {
 
         let raw_ars = vec![BINARY_NAME, "--testnet", "history"];
         let expected_args = Arguments {
-            testnet: true,
+            network: env::Network::Testnet,
             json: false,
             disable_timestamp: false,
             config_path: default_testnet_conf_path,
@@ -583,7 +784,7 @@ mod tests {
 
         let raw_ars = vec![BINARY_NAME, "--testnet", "balance"];
         let expected_args = Arguments {
-            testnet: true,
+            network: env::Network::Testnet,
             json: false,
             disable_timestamp: false,
             config_path: default_testnet_conf_path,
@@ -607,14 +808,17 @@ mod tests {
             BITCOIN_TESTNET_ADDRESS,
         ];
         let expected_args = Arguments {
-            testnet: true,
+            network: env::Network::Testnet,
             json: false,
             disable_timestamp: false,
             config_path: default_testnet_conf_path,
             env_config: testnet_env_config,
             cmd: Command::WithdrawBtc {
-                amount: None,
-                address: Address::from_str(BITCOIN_TESTNET_ADDRESS).unwrap(),
+                withdrawal: Withdrawal {
+                    drain_to: Some(Address::from_str(BITCOIN_TESTNET_ADDRESS).unwrap()),
+                    ..Withdrawal::default()
+                },
+                psbt_only: false,
             },
         };
         let args = parse_args(raw_ars).unwrap();
@@ -634,7 +838,7 @@ mod tests {
             SWAP_ID,
         ];
         let expected_args = Arguments {
-            testnet: true,
+            network: env::Network::Testnet,
             json: false,
             disable_timestamp: false,
             config_path: default_testnet_conf_path,
@@ -661,7 +865,7 @@ mod tests {
             SWAP_ID,
         ];
         let expected_args = Arguments {
-            testnet: true,
+            network: env::Network::Testnet,
             json: false,
             disable_timestamp: false,
             config_path: default_testnet_conf_path,
@@ -688,7 +892,7 @@ mod tests {
             SWAP_ID,
         ];
         let expected_args = Arguments {
-            testnet: true,
+            network: env::Network::Testnet,
             json: false,
             disable_timestamp: false,
             config_path: default_testnet_conf_path,
@@ -715,7 +919,7 @@ mod tests {
             SWAP_ID,
         ];
         let expected_args = Arguments {
-            testnet: true,
+            network: env::Network::Testnet,
             json: false,
             disable_timestamp: false,
             config_path: default_testnet_conf_path,
@@ -735,7 +939,7 @@ mod tests {
 
         let raw_ars = vec![BINARY_NAME, "--disable-timestamp", "start"];
         let expected_args = Arguments {
-            testnet: false,
+            network: env::Network::Mainnet,
             json: false,
             disable_timestamp: true,
             config_path: default_mainnet_conf_path,
@@ -750,17 +954,20 @@ mod tests {
     fn given_user_provides_config_path_then_no_default_config_path_returned() {
         let cp = PathBuf::from_str("/some/config/path").unwrap();
 
-        let expected = config_path(Some(cp.clone()), true).unwrap();
+        let expected = config_path(Some(cp.clone()), env::Network::Testnet).unwrap();
         assert_eq!(expected, cp);
 
-        let expected = config_path(Some(cp.clone()), false).unwrap();
+        let expected = config_path(Some(cp.clone()), env::Network::Mainnet).unwrap();
         assert_eq!(expected, cp)
     }
 
     #[test]
     fn given_bitcoin_address_network_mismatch_then_error() {
-        let error =
-            bitcoin_address(Address::from_str(BITCOIN_MAINNET_ADDRESS).unwrap(), true).unwrap_err();
+        let error = bitcoin_address(
+            Address::from_str(BITCOIN_MAINNET_ADDRESS).unwrap(),
+            env::Network::Testnet,
+        )
+        .unwrap_err();
 
         assert_eq!(
             error
@@ -772,8 +979,11 @@ mod tests {
             }
         );
 
-        let error = bitcoin_address(Address::from_str(BITCOIN_TESTNET_ADDRESS).unwrap(), false)
-            .unwrap_err();
+        let error = bitcoin_address(
+            Address::from_str(BITCOIN_TESTNET_ADDRESS).unwrap(),
+            env::Network::Mainnet,
+        )
+        .unwrap_err();
 
         assert_eq!(
             error
@@ -785,4 +995,47 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn ensure_start_command_mapping_for_signet() {
+        let raw_ars = vec![BINARY_NAME, "--signet", "start"];
+        let expected_args = Arguments {
+            network: env::Network::Signet,
+            json: false,
+            disable_timestamp: false,
+            config_path: env::Signet::getConfigFileDefaults().unwrap().config_path,
+            env_config: env::Signet::get_config(),
+            cmd: Command::Start { resume_only: false },
+        };
+        let args = parse_args(raw_ars).unwrap();
+        assert_eq!(expected_args, args);
+    }
+
+    #[test]
+    fn ensure_start_command_mapping_for_regtest() {
+        let raw_ars = vec![BINARY_NAME, "--regtest", "start"];
+        let expected_args = Arguments {
+            network: env::Network::Regtest,
+            json: false,
+            disable_timestamp: false,
+            config_path: env::Regtest::getConfigFileDefaults().unwrap().config_path,
+            env_config: env::Regtest::get_config(),
+            cmd: Command::Start { resume_only: false },
+        };
+        let args = parse_args(raw_ars).unwrap();
+        assert_eq!(expected_args, args);
+
+        let raw_ars = vec![BINARY_NAME, "--testnet", "--regtest", "start"];
+        assert!(parse_args(raw_ars).is_err());
+    }
+
+    #[test]
+    fn given_signet_then_accepts_testnet_encoded_addresses() {
+        let address = Address::from_str(BITCOIN_TESTNET_ADDRESS).unwrap();
+
+        assert_eq!(
+            bitcoin_address(address.clone(), env::Network::Signet).unwrap(),
+            address
+        );
+    }
 }
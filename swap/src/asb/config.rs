@@ -1,4 +1,4 @@
-use crate::env::{Mainnet, Testnet};
+use crate::env::{self, Mainnet, Regtest, Signet, Testnet};
 use crate::fs::{ensure_directory_exists, system_config_dir, system_data_dir};
 use crate::tor::{DEFAULT_CONTROL_PORT, DEFAULT_SOCKS5_PORT};
 use anyhow::{bail, Context, Result};
@@ -49,6 +49,42 @@ impl GetDefaults for Testnet {
     }
 }
 
+impl GetDefaults for Signet {
+    fn getConfigFileDefaults() -> Result<Defaults> {
+        let defaults = Defaults {
+            config_path: default_asb_config_dir()?.join("signet").join("config.toml"),
+            data_dir: default_asb_data_dir()?.join("signet"),
+            listen_address_tcp: Multiaddr::from_str("/ip4/0.0.0.0/tcp/9939")?,
+            listen_address_ws: Multiaddr::from_str("/ip4/0.0.0.0/tcp/9940/ws")?,
+            electrum_rpc_url: Url::parse("ssl://mempool.space:60602")?,
+            monero_wallet_rpc_url: Url::parse("http://127.0.0.1:38083/json_rpc")?,
+            price_ticker_ws_url: Url::parse("wss://ws.kraken.com")?,
+            bitcoin_confirmation_target: 1,
+        };
+
+        Ok(defaults)
+    }
+}
+
+impl GetDefaults for Regtest {
+    fn getConfigFileDefaults() -> Result<Defaults> {
+        let defaults = Defaults {
+            config_path: default_asb_config_dir()?
+                .join("regtest")
+                .join("config.toml"),
+            data_dir: default_asb_data_dir()?.join("regtest"),
+            listen_address_tcp: Multiaddr::from_str("/ip4/0.0.0.0/tcp/9939")?,
+            listen_address_ws: Multiaddr::from_str("/ip4/0.0.0.0/tcp/9940/ws")?,
+            electrum_rpc_url: Url::parse("tcp://127.0.0.1:60401")?,
+            monero_wallet_rpc_url: Url::parse("http://127.0.0.1:18083/json_rpc")?,
+            price_ticker_ws_url: Url::parse("wss://ws.kraken.com")?,
+            bitcoin_confirmation_target: 1,
+        };
+
+        Ok(defaults)
+    }
+}
+
 impl GetDefaults for Mainnet {
     fn getConfigFileDefaults() -> Result<Defaults> {
         let defaults = Defaults {
@@ -141,14 +177,17 @@ pub struct Network {
 }
 
 mod addr_list {
-    use libp2p::Multiaddr;
     use serde::de::Unexpected;
     use serde::{de, Deserialize, Deserializer};
     use serde_json::Value;
+    use std::fmt::Display;
+    use std::str::FromStr;
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Multiaddr>, D::Error>
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
     where
         D: Deserializer<'de>,
+        T: FromStr,
+        T::Err: Display,
     {
         let s = Value::deserialize(deserializer)?;
         return match s {
@@ -184,11 +223,55 @@ mod addr_list {
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Bitcoin {
-    pub electrum_rpc_url: Url,
+    /// One or more Electrum servers, given as array or comma separated string.
+    /// The others are used if the first one fails.
+    #[serde(deserialize_with = "addr_list::deserialize")]
+    pub electrum_rpc_url: Vec<Url>,
+    /// Compare the block height of all Electrum servers to detect servers that
+    /// are lagging behind or lying.
+    #[serde(default)]
+    pub electrum_cross_check_block_height: bool,
+    /// Use Bitcoin Core instead of the Electrum server if given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitcoind_rpc: Option<crate::bitcoin::BitcoindRpc>,
+    /// Use Esplora instead of the Electrum server if given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub esplora: Option<crate::bitcoin::EsploraConfig>,
     pub target_block: usize,
     pub finality_confirmations: Option<u32>,
     #[serde(with = "crate::bitcoin::network")]
     pub network: bitcoin::Network,
+    /// Bounds on the fees of each kind of swap transaction.
+    #[serde(
+        default,
+        skip_serializing_if = "crate::bitcoin::FeePolicies::is_default"
+    )]
+    pub fees: crate::bitcoin::FeePolicies,
+    /// Where fee rate estimates come from and how they are combined.
+    #[serde(
+        default,
+        skip_serializing_if = "crate::bitcoin::FeeEstimation::is_default"
+    )]
+    pub fee_estimation: crate::bitcoin::FeeEstimation,
+}
+
+impl Bitcoin {
+    pub fn backend(&self) -> Result<crate::bitcoin::Backend> {
+        Ok(match (&self.bitcoind_rpc, &self.esplora) {
+            (Some(_), Some(_)) => {
+                bail!("Only one of `bitcoind_rpc` and `esplora` can be configured")
+            }
+            (Some(rpc), None) => crate::bitcoin::Backend::Bitcoind(rpc.clone()),
+            (None, Some(esplora)) => crate::bitcoin::Backend::Esplora(esplora.clone()),
+            (None, None) if self.electrum_rpc_url.is_empty() => {
+                bail!("At least one `electrum_rpc_url` has to be configured")
+            }
+            (None, None) => crate::bitcoin::Backend::Electrum(crate::bitcoin::ElectrumConfig {
+                urls: self.electrum_rpc_url.clone(),
+                cross_check_block_height: self.electrum_cross_check_block_height,
+            }),
+        })
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
@@ -261,22 +344,17 @@ pub fn initial_setup(config_path: PathBuf, config: Config) -> Result<()> {
     Ok(())
 }
 
-pub fn query_user_for_initial_config(testnet: bool) -> Result<Config> {
-    let (bitcoin_network, monero_network, defaults) = if testnet {
-        tracing::info!("Running initial setup for testnet");
-
-        let bitcoin_network = bitcoin::Network::Testnet;
-        let monero_network = monero::Network::Stagenet;
-        let defaults = Testnet::getConfigFileDefaults()?;
-
-        (bitcoin_network, monero_network, defaults)
-    } else {
-        tracing::info!("Running initial setup for mainnet");
-        let bitcoin_network = bitcoin::Network::Bitcoin;
-        let monero_network = monero::Network::Mainnet;
-        let defaults = Mainnet::getConfigFileDefaults()?;
-
-        (bitcoin_network, monero_network, defaults)
+pub fn query_user_for_initial_config(network: env::Network) -> Result<Config> {
+    tracing::info!("Running initial setup for {:?}", network);
+
+    let env_config = network.get_config();
+    let bitcoin_network = env_config.bitcoin_network;
+    let monero_network = env_config.monero_network;
+    let defaults = match network {
+        env::Network::Mainnet => Mainnet::getConfigFileDefaults()?,
+        env::Network::Testnet => Testnet::getConfigFileDefaults()?,
+        env::Network::Signet => Signet::getConfigFileDefaults()?,
+        env::Network::Regtest => Regtest::getConfigFileDefaults()?,
     };
 
     println!();
@@ -307,9 +385,13 @@ pub fn query_user_for_initial_config(testnet: bool) -> Result<Config> {
         .collect::<Result<Vec<Multiaddr>, _>>()?;
 
     let electrum_rpc_url = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Enter Electrum RPC URL or hit return to use default")
-        .default(defaults.electrum_rpc_url)
+        .with_prompt("Enter Electrum RPC URLs (comma separated), the others are used if the first one fails, or hit return to use default")
+        .default(defaults.electrum_rpc_url.to_string())
         .interact_text()?;
+    let electrum_rpc_url = electrum_rpc_url
+        .split(',')
+        .map(|str| str.trim().parse())
+        .collect::<Result<Vec<Url>, _>>()?;
 
     let monero_wallet_rpc_url = Input::with_theme(&ColorfulTheme::default())
         .with_prompt("Enter Monero Wallet RPC URL or hit enter to use default")
@@ -380,9 +462,14 @@ pub fn query_user_for_initial_config(testnet: bool) -> Result<Config> {
         },
         bitcoin: Bitcoin {
             electrum_rpc_url,
+            electrum_cross_check_block_height: false,
+            bitcoind_rpc: None,
+            esplora: None,
             target_block,
             finality_confirmations: None,
             network: bitcoin_network,
+            fees: crate::bitcoin::FeePolicies::default(),
+            fee_estimation: crate::bitcoin::FeeEstimation::default(),
         },
         monero: Monero {
             wallet_rpc_url: monero_wallet_rpc_url,
@@ -423,10 +510,15 @@ mod tests {
                 dir: Default::default(),
             },
             bitcoin: Bitcoin {
-                electrum_rpc_url: defaults.electrum_rpc_url,
+                electrum_rpc_url: vec![defaults.electrum_rpc_url],
+                electrum_cross_check_block_height: false,
+                bitcoind_rpc: None,
+                esplora: None,
                 target_block: defaults.bitcoin_confirmation_target,
                 finality_confirmations: None,
                 network: bitcoin::Network::Testnet,
+                fees: crate::bitcoin::FeePolicies::default(),
+                fee_estimation: crate::bitcoin::FeeEstimation::default(),
             },
             network: Network {
                 listen: vec![defaults.listen_address_tcp, defaults.listen_address_ws],
@@ -467,10 +559,68 @@ mod tests {
                 dir: Default::default(),
             },
             bitcoin: Bitcoin {
-                electrum_rpc_url: defaults.electrum_rpc_url,
+                electrum_rpc_url: vec![defaults.electrum_rpc_url],
+                electrum_cross_check_block_height: false,
+                bitcoind_rpc: None,
+                esplora: None,
+                target_block: defaults.bitcoin_confirmation_target,
+                finality_confirmations: None,
+                network: bitcoin::Network::Bitcoin,
+                fees: crate::bitcoin::FeePolicies::default(),
+                fee_estimation: crate::bitcoin::FeeEstimation::default(),
+            },
+            network: Network {
+                listen: vec![defaults.listen_address_tcp, defaults.listen_address_ws],
+                rendezvous_point: vec![],
+                external_addresses: vec![],
+            },
+            monero: Monero {
+                wallet_rpc_url: defaults.monero_wallet_rpc_url,
+                finality_confirmations: None,
+                network: monero::Network::Mainnet,
+            },
+            tor: Default::default(),
+            maker: Maker {
+                min_buy_btc: bitcoin::Amount::from_btc(DEFAULT_MIN_BUY_AMOUNT).unwrap(),
+                max_buy_btc: bitcoin::Amount::from_btc(DEFAULT_MAX_BUY_AMOUNT).unwrap(),
+                ask_spread: Decimal::from_f64(DEFAULT_SPREAD).unwrap(),
+                price_ticker_ws_url: defaults.price_ticker_ws_url,
+                external_bitcoin_redeem_address: None,
+            },
+        };
+
+        initial_setup(config_path.clone(), expected.clone()).unwrap();
+        let actual = read_config(config_path).unwrap().unwrap();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    #[serial]
+    fn bitcoind_rpc_takes_precedence_over_electrum() {
+        let temp_dir = tempdir().unwrap().path().to_path_buf();
+        let config_path = Path::join(&temp_dir, "config.toml");
+
+        let defaults = Mainnet::getConfigFileDefaults().unwrap();
+        let bitcoind_rpc = crate::bitcoin::BitcoindRpc {
+            url: Url::parse("http://127.0.0.1:8332").unwrap(),
+            cookie_file: Some(PathBuf::from("/home/bitcoin/.bitcoin/.cookie")),
+        };
+
+        let mut expected = Config {
+            data: Data {
+                dir: Default::default(),
+            },
+            bitcoin: Bitcoin {
+                electrum_rpc_url: vec![defaults.electrum_rpc_url.clone()],
+                electrum_cross_check_block_height: false,
+                bitcoind_rpc: None,
+                esplora: None,
                 target_block: defaults.bitcoin_confirmation_target,
                 finality_confirmations: None,
                 network: bitcoin::Network::Bitcoin,
+                fees: crate::bitcoin::FeePolicies::default(),
+                fee_estimation: crate::bitcoin::FeeEstimation::default(),
             },
             network: Network {
                 listen: vec![defaults.listen_address_tcp, defaults.listen_address_ws],
@@ -491,11 +641,115 @@ mod tests {
                 external_bitcoin_redeem_address: None,
             },
         };
+        assert_eq!(
+            expected.bitcoin.backend().unwrap(),
+            crate::bitcoin::Backend::Electrum(defaults.electrum_rpc_url.into())
+        );
 
+        expected.bitcoin.bitcoind_rpc = Some(bitcoind_rpc.clone());
         initial_setup(config_path.clone(), expected.clone()).unwrap();
         let actual = read_config(config_path).unwrap().unwrap();
 
         assert_eq!(expected, actual);
+        assert_eq!(
+            actual.bitcoin.backend().unwrap(),
+            crate::bitcoin::Backend::Bitcoind(bitcoind_rpc)
+        );
+
+        let mut both = actual;
+        both.bitcoin.esplora = Some(crate::bitcoin::EsploraConfig {
+            url: Url::parse("http://127.0.0.1:3002").unwrap(),
+            socks5_proxy: None,
+            min_relay_fee_sat_per_kvb: None,
+        });
+        assert!(both.bitcoin.backend().is_err());
+    }
+
+    #[test]
+    fn reads_fee_policy_per_transaction() {
+        let config = toml::from_str::<Bitcoin>(
+            r#"
+            electrum_rpc_url = "ssl://blockstream.info:700"
+            target_block = 3
+            network = "Mainnet"
+
+            [fees.redeem]
+            max_sat_per_vb = 300
+            max_relative_fee = 0.1
+            max_absolute_fee_sat = 1000000
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.fees.redeem,
+            crate::bitcoin::FeePolicy {
+                max_sat_per_vb: Some(Decimal::from(300)),
+                max_relative_fee: Decimal::from_str("0.1").unwrap(),
+                max_absolute_fee: bitcoin::Amount::from_sat(1_000_000),
+            }
+        );
+        assert_eq!(config.fees.refund, crate::bitcoin::FeePolicy::default());
+
+        let roundtrip = toml::from_str::<Bitcoin>(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(config, roundtrip);
+    }
+
+    #[test]
+    fn reads_fee_estimation_sources() {
+        let config = toml::from_str::<Bitcoin>(
+            r#"
+            electrum_rpc_url = "ssl://blockstream.info:700"
+            target_block = 3
+            network = "Mainnet"
+
+            [fee_estimation]
+            combine = "median"
+            floor_sat_per_vb = 2
+
+            [[fee_estimation.sources]]
+            type = "backend"
+
+            [[fee_estimation.sources]]
+            type = "http"
+            url = "https://mempool.space/api/v1/fees/recommended"
+            socks5_proxy = "127.0.0.1:9050"
+
+            [[fee_estimation.sources]]
+            type = "command"
+            program = "/usr/local/bin/fee-estimate"
+            args = ["--conservative", "--mode", "economical fallback"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.fee_estimation,
+            crate::bitcoin::FeeEstimation {
+                combine: crate::bitcoin::Combine::Median,
+                floor_sat_per_vb: Some(Decimal::from(2)),
+                sources: vec![
+                    crate::bitcoin::FeeSource::Backend,
+                    crate::bitcoin::FeeSource::Http {
+                        url: "https://mempool.space/api/v1/fees/recommended"
+                            .parse()
+                            .unwrap(),
+                        socks5_proxy: Some("127.0.0.1:9050".to_owned()),
+                    },
+                    crate::bitcoin::FeeSource::Command {
+                        program: "/usr/local/bin/fee-estimate".into(),
+                        args: vec![
+                            "--conservative".to_owned(),
+                            "--mode".to_owned(),
+                            "economical fallback".to_owned()
+                        ],
+                    },
+                ],
+            }
+        );
+
+        let roundtrip = toml::from_str::<Bitcoin>(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(config, roundtrip);
     }
 
     #[test]
@@ -517,14 +771,25 @@ mod tests {
             format!("{},{}", addr1, addr2),
         );
         std::env::set_var("ASB__NETWORK__LISTEN", format!("{},{}", addr1, addr2));
+        let electrum1 = "ssl://electrum.blockstream.info:50002";
+        let electrum2 = "tcp://127.0.0.1:50001";
+        std::env::set_var(
+            "ASB__BITCOIN__ELECTRUM_RPC_URL",
+            format!("{},{}", electrum1, electrum2),
+        );
 
         let expected = Config {
             data: Data { dir },
             bitcoin: Bitcoin {
-                electrum_rpc_url: defaults.electrum_rpc_url,
+                electrum_rpc_url: vec![electrum1.parse().unwrap(), electrum2.parse().unwrap()],
+                electrum_cross_check_block_height: false,
+                bitcoind_rpc: None,
+                esplora: None,
                 target_block: defaults.bitcoin_confirmation_target,
                 finality_confirmations: None,
                 network: bitcoin::Network::Bitcoin,
+                fees: crate::bitcoin::FeePolicies::default(),
+                fee_estimation: crate::bitcoin::FeeEstimation::default(),
             },
             network: Network {
                 listen,
@@ -553,5 +818,6 @@ mod tests {
         std::env::remove_var("ASB__DATA__DIR");
         std::env::remove_var("ASB__NETWORK__EXTERNAL_ADDRESSES");
         std::env::remove_var("ASB__NETWORK__LISTEN");
+        std::env::remove_var("ASB__BITCOIN__ELECTRUM_RPC_URL");
     }
 }
@@ -34,7 +34,7 @@ use swap::database::open_db;
 use swap::network::rendezvous::XmrBtcNamespace;
 use swap::network::swarm;
 use swap::protocol::alice::{run, AliceState};
-use swap::seed::Seed;
+use swap::seed::{Passphrase, Seed};
 use swap::tor::AuthenticatedClient;
 use swap::{asb, bitcoin, kraken, monero, tor};
 use tracing_subscriber::filter::LevelFilter;
@@ -44,7 +44,7 @@ const DEFAULT_WALLET_NAME: &str = "asb-wallet";
 #[tokio::main]
 async fn main() -> Result<()> {
     let Arguments {
-        testnet,
+        network,
         json,
         disable_timestamp,
         config_path,
@@ -77,7 +77,7 @@ async fn main() -> Result<()> {
     let config = match read_config(config_path.clone())? {
         Ok(config) => config,
         Err(ConfigNotInitialized {}) => {
-            initial_setup(config_path.clone(), query_user_for_initial_config(testnet)?)?;
+            initial_setup(config_path.clone(), query_user_for_initial_config(network)?)?;
             read_config(config_path)?.expect("after initial setup config can be read")
         }
     };
@@ -97,11 +97,16 @@ async fn main() -> Result<()> {
 
     let db = open_db(config.data.dir.join("sqlite")).await?;
 
-    let seed =
-        Seed::from_file_or_generate(&config.data.dir).expect("Could not retrieve/initialize seed");
+    // Only read when needed, so the seed commands do not generate one as a side
+    // effect
+    let seed = || {
+        Seed::from_file_or_generate(&config.data.dir).context("Could not retrieve/initialize seed")
+    };
 
     match cmd {
         Command::Start { resume_only } => {
+            let seed = seed()?;
+
             // check and warn for duplicate rendezvous points
             let mut rendezvous_addrs = config.network.rendezvous_point.clone();
             let prev_len = rendezvous_addrs.len();
@@ -164,7 +169,7 @@ async fn main() -> Result<()> {
             };
 
             let kraken_rate = KrakenRate::new(config.maker.ask_spread, kraken_price_updates);
-            let namespace = XmrBtcNamespace::from_is_testnet(testnet);
+            let namespace = XmrBtcNamespace::from_network(network);
 
             let mut swarm = swarm::asb(
                 &seed,
@@ -240,44 +245,52 @@ async fn main() -> Result<()> {
             let config_json = serde_json::to_string_pretty(&config)?;
             println!("{}", config_json);
         }
-        Command::WithdrawBtc { amount, address } => {
-            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed, env_config).await?;
+        Command::WithdrawBtc {
+            withdrawal,
+            psbt_only,
+        } => {
+            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed()?, env_config).await?;
 
-            let amount = match amount {
-                Some(amount) => amount,
-                None => {
-                    bitcoin_wallet
-                        .max_giveable(address.script_pubkey().len())
-                        .await?
-                }
-            };
+            let psbt = bitcoin_wallet.build_withdrawal(&withdrawal).await?;
+
+            if psbt_only {
+                tracing::info!(txid = %psbt.unsigned_tx.txid(), "Built unsigned withdrawal transaction, sign it with a wallet holding the keys of the internal wallet");
+                println!("{}", bitcoin::psbt_to_base64(&psbt));
+                return Ok(());
+            }
 
-            let psbt = bitcoin_wallet
-                .send_to_address(address, amount, None)
-                .await?;
             let signed_tx = bitcoin_wallet.sign_and_finalize(psbt).await?;
 
             bitcoin_wallet.broadcast(signed_tx, "withdraw").await?;
         }
+        Command::BumpFee { txid, fee_rate } => {
+            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed()?, env_config).await?;
+
+            let (fee_bump, psbt) = bitcoin_wallet.bump_fee(txid, fee_rate).await?;
+            let signed_tx = bitcoin_wallet.sign_and_finalize(psbt).await?;
+
+            let (new_txid, _) = bitcoin_wallet.broadcast(signed_tx, "bump-fee").await?;
+            tracing::info!(%txid, %new_txid, method = %fee_bump, "Bumped fee of transaction");
+        }
         Command::Balance => {
             let monero_wallet = init_monero_wallet(&config, env_config).await?;
             let monero_balance = monero_wallet.get_balance().await?;
             tracing::info!(%monero_balance);
 
-            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed, env_config).await?;
+            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed()?, env_config).await?;
             let bitcoin_balance = bitcoin_wallet.balance().await?;
             tracing::info!(%bitcoin_balance);
             tracing::info!(%bitcoin_balance, %monero_balance, "Current balance");
         }
         Command::Cancel { swap_id } => {
-            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed, env_config).await?;
+            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed()?, env_config).await?;
 
             let (txid, _) = cancel(swap_id, Arc::new(bitcoin_wallet), db).await?;
 
             tracing::info!("Cancel transaction successfully published with id {}", txid);
         }
         Command::Refund { swap_id } => {
-            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed, env_config).await?;
+            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed()?, env_config).await?;
             let monero_wallet = init_monero_wallet(&config, env_config).await?;
 
             refund(
@@ -291,7 +304,7 @@ async fn main() -> Result<()> {
             tracing::info!("Monero successfully refunded");
         }
         Command::Punish { swap_id } => {
-            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed, env_config).await?;
+            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed()?, env_config).await?;
 
             let (txid, _) = punish(swap_id, Arc::new(bitcoin_wallet), db).await?;
 
@@ -306,7 +319,7 @@ async fn main() -> Result<()> {
             swap_id,
             do_not_await_finality,
         } => {
-            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed, env_config).await?;
+            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed()?, env_config).await?;
 
             let (txid, _) = redeem(
                 swap_id,
@@ -319,10 +332,34 @@ async fn main() -> Result<()> {
             tracing::info!("Redeem transaction successfully published with id {}", txid);
         }
         Command::ExportBitcoinWallet => {
-            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed, env_config).await?;
+            let bitcoin_wallet = init_bitcoin_wallet(&config, &seed()?, env_config).await?;
             let wallet_export = bitcoin_wallet.wallet_export("asb").await?;
             println!("{}", wallet_export.to_string())
         }
+        Command::EncryptSeed => {
+            if Seed::is_encrypted(&config.data.dir).context("Failed to read in seed file")? {
+                bail!("Seed file is already encrypted, use `change-seed-passphrase` to change its passphrase")
+            }
+
+            let seed = seed()?;
+            let passphrase = Passphrase::new_from_env_or_prompt()?;
+            seed.encrypt_file(&config.data.dir, &passphrase)
+                .context("Failed to encrypt seed file")?;
+
+            tracing::info!("Seed file successfully encrypted");
+        }
+        Command::ChangeSeedPassphrase => {
+            if !Seed::is_encrypted(&config.data.dir).context("Failed to read in seed file")? {
+                bail!("Seed file is not encrypted, use `encrypt-seed` to encrypt it")
+            }
+
+            let seed = seed()?;
+            let passphrase = Passphrase::new_from_env_or_prompt()?;
+            seed.encrypt_file(&config.data.dir, &passphrase)
+                .context("Failed to re-encrypt seed file")?;
+
+            tracing::info!("Seed file passphrase successfully changed");
+        }
     }
 
     Ok(())
@@ -335,12 +372,19 @@ async fn init_bitcoin_wallet(
 ) -> Result<bitcoin::Wallet> {
     tracing::debug!("Opening Bitcoin wallet");
     let data_dir = &config.data.dir;
+    config
+        .bitcoin
+        .fees
+        .ensure_only(&bitcoin::TxKind::ASB)
+        .context("Invalid fee policy in [bitcoin.fees]")?;
     let wallet = bitcoin::Wallet::new(
-        config.bitcoin.electrum_rpc_url.clone(),
+        config.bitcoin.backend()?,
         data_dir,
         seed.derive_extended_private_key(env_config.bitcoin_network)?,
         env_config,
         config.bitcoin.target_block,
+        config.bitcoin.fees,
+        config.bitcoin.fee_estimation.clone(),
     )
     .await
     .context("Failed to initialize Bitcoin wallet")?;
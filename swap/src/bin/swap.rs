@@ -14,29 +14,45 @@
 
 use anyhow::{bail, Context, Result};
 use comfy_table::Table;
+use libp2p::{Multiaddr, PeerId};
 use qrcode::render::unicode;
 use qrcode::QrCode;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use std::cmp::min;
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::env;
 use std::future::Future;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use swap::bitcoin::TxLock;
-use swap::cli::command::{parse_args_and_apply_defaults, Arguments, Command, ParseResult};
-use swap::cli::{list_sellers, EventLoop, SellerStatus};
+use swap::bitcoin::{TxKind, TxLock};
+use swap::cli::address_book::{add_seller, known_sellers};
+use swap::cli::cancel_and_refund::TxSummary;
+use swap::cli::command::{
+    parse_args_and_apply_defaults, Arguments, Command, ParseResult, SellerSelection,
+};
+use swap::cli::config::PinnedSeller;
+use swap::cli::external_funding::PsbtPrompt;
+use swap::cli::lock_confirmation::LockConfirmation;
+use swap::cli::seller_identity;
+use swap::cli::seller_identity::Identity;
+use swap::cli::{list_sellers, EventLoop, EventLoopController, SellerStatus};
 use swap::common::check_latest_version;
 use swap::database::open_db;
 use swap::env::Config;
 use swap::libp2p_ext::MultiAddrExt;
 use swap::network::quote::{BidQuote, ZeroQuoteReceived};
+use swap::network::rendezvous::XmrBtcNamespace;
+use swap::network::swap_setup::bob::Error as SwapSetupError;
 use swap::network::swarm;
 use swap::protocol::bob;
 use swap::protocol::bob::{BobState, Swap};
-use swap::seed::Seed;
-use swap::{bitcoin, cli, monero};
-use url::Url;
+use swap::protocol::Database;
+use swap::seed::{Passphrase, Seed};
+use swap::{bitcoin, cli, monero, tor};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
 #[tokio::main]
@@ -62,68 +78,198 @@ async fn main() -> Result<()> {
     match cmd {
         Command::BuyXmr {
             seller,
-            bitcoin_electrum_rpc_url,
+            bitcoin_backend,
             bitcoin_target_block,
+            bitcoin_fee_policies,
+            bitcoin_fee_estimation,
             bitcoin_change_address,
             monero_receive_address,
             monero_daemon_address,
             tor_socks5_port,
             namespace,
+            xmr_amount,
+            max_price,
+            max_slippage,
+            reference_price,
+            force,
+            yes,
+            external_funding,
+            psbt_file,
         } => {
             let swap_id = Uuid::new_v4();
 
             cli::tracing::init(debug, json, data_dir.join("logs"), Some(swap_id))?;
 
             let db = open_db(data_dir.join("sqlite")).await?;
+            let config = cli::config::Config::read(&data_dir)?;
             let seed = Seed::from_file_or_generate(data_dir.as_path())
                 .context("Failed to read in seed file")?;
 
             let bitcoin_wallet = init_bitcoin_wallet(
-                bitcoin_electrum_rpc_url,
+                bitcoin_backend,
                 &seed,
                 data_dir.clone(),
                 env_config,
                 bitcoin_target_block,
+                bitcoin_fee_policies,
+                bitcoin_fee_estimation,
             )
             .await?;
             let (monero_wallet, _process) =
                 init_monero_wallet(data_dir, monero_daemon_address, env_config).await?;
             let bitcoin_wallet = Arc::new(bitcoin_wallet);
-            let seller_peer_id = seller
-                .extract_peer_id()
-                .context("Seller address must contain peer ID")?;
-            db.insert_address(seller_peer_id, seller.clone()).await?;
+            let monero_wallet = Arc::new(monero_wallet);
 
-            let behaviour = cli::Behaviour::new(
-                seller_peer_id,
-                env_config,
+            let (mut event_loop, controller) = init_event_loop(
+                &seed,
                 bitcoin_wallet.clone(),
-                (seed.derive_libp2p_identity(), namespace),
-            );
-            let mut swarm =
-                swarm::cli(seed.derive_libp2p_identity(), tor_socks5_port, behaviour).await?;
-            swarm.behaviour_mut().add_address(seller_peer_id, seller);
+                env_config,
+                namespace,
+                tor_socks5_port,
+                db.clone(),
+            )
+            .await?;
 
-            tracing::debug!(peer_id = %swarm.local_peer_id(), "Network layer initialized");
+            // A given seller is asked for a quote right away and the handle is
+            // kept for the swap. Discovered sellers are only connected to once they
+            // are picked.
+            let (mut sellers, mut connection) = match seller {
+                SellerSelection::Seller(seller) => {
+                    let (seller_peer_id, mut event_loop_handle) = connect_to_seller(
+                        seller.clone(),
+                        swap_id,
+                        &db,
+                        &config.pinned_sellers,
+                        &controller,
+                    )
+                    .await?;
+                    let bid_quote = event_loop_handle.request_quote().await?;
+                    let sellers = vec![cli::Seller {
+                        status: SellerStatus::Online(bid_quote),
+                        multiaddr: seller,
+                    }];
+
+                    (sellers, Some((seller_peer_id, event_loop_handle)))
+                }
+                SellerSelection::RendezvousPoint(rendezvous_point) => {
+                    let rendezvous_node_peer_id = rendezvous_point
+                        .extract_peer_id()
+                        .context("Rendezvous node address must contain peer ID")?;
+
+                    let mut sellers = list_sellers(
+                        rendezvous_node_peer_id,
+                        rendezvous_point,
+                        namespace,
+                        tor_socks5_port,
+                        seed.derive_libp2p_identity(),
+                    )
+                    .await?;
+
+                    let known_addresses = db.get_all_addresses().await?;
+                    sellers.retain(|seller| {
+                        let peer_id = match seller.multiaddr.extract_peer_id() {
+                            Some(peer_id) => peer_id,
+                            None => return false,
+                        };
+
+                        match seller_identity::identify(
+                            peer_id,
+                            &seller.multiaddr,
+                            &config.pinned_sellers,
+                            &known_addresses,
+                        ) {
+                            Ok(_) => true,
+                            Err(error) => {
+                                tracing::warn!("Ignoring discovered seller: {}", error);
+                                false
+                            }
+                        }
+                    });
+
+                    (sellers, None)
+                }
+            };
 
-            let (event_loop, mut event_loop_handle) =
-                EventLoop::new(swap_id, swarm, seller_peer_id)?;
-            let event_loop = tokio::spawn(event_loop.run());
+            if let Some(max_price) = max_price {
+                sellers.retain(|seller| {
+                    matches!(seller.status, SellerStatus::Online(quote) if quote.price <= max_price)
+                });
+                if sellers.is_empty() {
+                    bail!(
+                        "None of the sellers offers a price of at most {} per XMR",
+                        max_price
+                    )
+                }
+            }
+            if let Some(reference_price) = &reference_price {
+                let tor_socks5_port = tor_socks5_port_if_running(tor_socks5_port).await;
+                match reference_price.market_price(tor_socks5_port).await {
+                    Ok(market_price) => {
+                        sellers = reference_price
+                            .filter_sellers(sellers, market_price, force)
+                            .context("Refusing to swap above the maximum premium over the market price, use --force to swap anyway")?;
+                    }
+                    Err(error) if force => {
+                        tracing::warn!("Failed to fetch the market price, proceeding without comparing prices because of --force: {:#}", error);
+                    }
+                    Err(error) => {
+                        return Err(error.context(
+                            "Failed to fetch the market price, use --force to swap without comparing prices",
+                        ))
+                    }
+                }
+            }
+            let bid_quote = cli::combined_quote(&sellers)
+                .context("None of the discovered sellers is online")?;
 
             let max_givable = || bitcoin_wallet.max_giveable(TxLock::script_size());
-            let estimate_fee = |amount| bitcoin_wallet.estimate_fee(TxLock::weight(), amount);
+            let estimate_fee =
+                |amount| bitcoin_wallet.estimate_fee(TxKind::Lock, TxLock::weight(), amount);
+
+            let determine_amount = async {
+                if external_funding {
+                    let funding = request_external_funding(
+                        async { Ok(bid_quote) },
+                        bitcoin_wallet.new_address(),
+                    )
+                    .await?;
+                    let amount = funding.amount()?;
+                    tracing::info!(%amount, "Determined swap amount from external funding");
+
+                    Ok::<_, anyhow::Error>((amount, Some(funding)))
+                } else if let Some(xmr_amount) = xmr_amount {
+                    let (amount, fees) = determine_btc_for_xmr(
+                        json,
+                        xmr_amount,
+                        async { Ok(bid_quote) },
+                        bitcoin_wallet.new_address(),
+                        || bitcoin_wallet.balance(),
+                        max_givable,
+                        || bitcoin_wallet.sync(),
+                        estimate_fee,
+                    )
+                    .await?;
+                    tracing::info!(%amount, %fees, %xmr_amount, "Determined swap amount");
+
+                    Ok((amount, None))
+                } else {
+                    let (amount, fees) = determine_btc_to_swap(
+                        json,
+                        async { Ok(bid_quote) },
+                        bitcoin_wallet.new_address(),
+                        || bitcoin_wallet.balance(),
+                        max_givable,
+                        || bitcoin_wallet.sync(),
+                        estimate_fee,
+                    )
+                    .await?;
+                    tracing::info!(%amount, %fees,  "Determined swap amount");
+
+                    Ok((amount, None))
+                }
+            };
 
-            let (amount, fees) = match determine_btc_to_swap(
-                json,
-                event_loop_handle.request_quote(),
-                bitcoin_wallet.new_address(),
-                || bitcoin_wallet.balance(),
-                max_givable,
-                || bitcoin_wallet.sync(),
-                estimate_fee,
-            )
-            .await
-            {
+            let (amount, external_funding) = match determine_amount.await {
                 Ok(val) => val,
                 Err(error) => match error.downcast::<ZeroQuoteReceived>() {
                     Ok(_) => {
@@ -133,53 +279,119 @@ async fn main() -> Result<()> {
                 },
             };
 
-            tracing::info!(%amount, %fees,  "Determined swap amount");
+            let mut candidates = cli::select_sellers(sellers, amount, xmr_amount)
+                .into_iter()
+                .peekable();
+            if candidates.peek().is_none() {
+                bail!("None of the sellers accepts to buy {}", amount)
+            }
 
-            db.insert_peer_id(swap_id, seller_peer_id).await?;
             db.insert_monero_address(swap_id, monero_receive_address)
                 .await?;
 
-            let swap = Swap::new(
-                db,
-                swap_id,
-                bitcoin_wallet,
-                Arc::new(monero_wallet),
-                env_config,
-                event_loop_handle,
-                monero_receive_address,
-                bitcoin_change_address,
-                amount,
-            );
-
-            tokio::select! {
-                result = event_loop => {
-                    result
-                        .context("EventLoop panicked")?;
-                },
-                result = bob::run(swap) => {
-                    result.context("Failed to complete swap")?;
+            while let Some((seller, quote)) = candidates.next() {
+                let (seller_peer_id, event_loop_handle) = match connection.take() {
+                    Some(connection) => connection,
+                    None => match connect_to_seller(
+                        seller.clone(),
+                        swap_id,
+                        &db,
+                        &config.pinned_sellers,
+                        &controller,
+                    )
+                    .await
+                    {
+                        Ok(connection) => connection,
+                        Err(error) if candidates.peek().is_some() => {
+                            tracing::warn!(%seller, "Failed to connect to seller, trying the next seller: {:#}", error);
+                            continue;
+                        }
+                        Err(error) => return Err(error),
+                    },
+                };
+
+                let price_limit = price_limit(quote.price, max_price, max_slippage)?;
+                tracing::info!(%seller, price = %quote.price, "Swapping with seller");
+                db.insert_peer_id(swap_id, seller_peer_id).await?;
+
+                let swap = Swap::new(
+                    db.clone(),
+                    swap_id,
+                    bitcoin_wallet.clone(),
+                    monero_wallet.clone(),
+                    env_config,
+                    event_loop_handle,
+                    monero_receive_address,
+                    bitcoin_change_address.clone(),
+                    amount,
+                    external_funding.clone(),
+                    xmr_amount,
+                )
+                .with_external_signer(Arc::new(PsbtPrompt {
+                    lock_psbt_file: psbt_file.clone(),
+                }))
+                .with_max_price(price_limit)
+                .with_lock_confirmation(LockConfirmation {
+                    seller: seller_peer_id,
+                    bitcoin_avg_block_time: env_config.bitcoin_avg_block_time,
+                    json,
+                    assume_yes: yes,
+                });
+
+                tokio::select! {
+                    result = &mut event_loop => {
+                        result
+                            .context("EventLoop panicked")?;
+                    },
+                    result = bob::run(swap) => {
+                        match result {
+                            Err(error) if candidates.peek().is_some() && is_declined_by_seller(&error) => {
+                                tracing::warn!(%seller, "Seller declined the swap, trying the next seller: {:#}", error);
+                                continue;
+                            }
+                            result => {
+                                result.context("Failed to complete swap")?;
+                            }
+                        }
+                    }
                 }
+
+                break;
             }
         }
-        Command::History => {
+        Command::History { all } => {
             cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
 
             let db = open_db(data_dir.join("sqlite")).await?;
-            let swaps = db.all().await?;
+            let archived = db.archived().await?;
+            let swaps = db
+                .all()
+                .await?
+                .into_iter()
+                .map(|(swap_id, state)| (swap_id, state, archived.contains(&swap_id)))
+                .filter(|(_, _, archived)| all || !archived);
 
             if json {
-                for (swap_id, state) in swaps {
+                for (swap_id, state, archived) in swaps {
                     let state: BobState = state.try_into()?;
-                    tracing::info!(swap_id=%swap_id.to_string(), state=%state.to_string(), "Read swap state from database");
+                    tracing::info!(swap_id=%swap_id.to_string(), state=%state.to_string(), archived, "Read swap state from database");
                 }
             } else {
                 let mut table = Table::new();
 
-                table.set_header(vec!["SWAP ID", "STATE"]);
+                if all {
+                    table.set_header(vec!["SWAP ID", "STATE", "ARCHIVED"]);
+                } else {
+                    table.set_header(vec!["SWAP ID", "STATE"]);
+                }
 
-                for (swap_id, state) in swaps {
+                for (swap_id, state, archived) in swaps {
                     let state: BobState = state.try_into()?;
-                    table.add_row(vec![swap_id.to_string(), state.to_string()]);
+                    let mut row = vec![swap_id.to_string(), state.to_string()];
+                    if all {
+                        row.push(if archived { "yes" } else { "no" }.to_owned());
+                    }
+                    table.add_row(row);
                 }
 
                 println!("{}", table);
@@ -194,57 +406,89 @@ async fn main() -> Result<()> {
             tracing::info!(path=%format!("{}/seed.pem", data_dir.display()), "Seed file location");
             tracing::info!(path=%format!("{}/monero", data_dir.display()), "Monero-wallet-rpc directory");
             tracing::info!(path=%format!("{}/wallet", data_dir.display()), "Internal bitcoin wallet directory");
+            tracing::info!(path=%cli::config::Config::path(&data_dir).display(), "Config file location");
         }
         Command::WithdrawBtc {
-            bitcoin_electrum_rpc_url,
+            bitcoin_backend,
             bitcoin_target_block,
-            amount,
-            address,
+            bitcoin_fee_estimation,
+            withdrawal,
+            psbt_only,
         } => {
             cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
 
             let seed = Seed::from_file_or_generate(data_dir.as_path())
                 .context("Failed to read in seed file")?;
             let bitcoin_wallet = init_bitcoin_wallet(
-                bitcoin_electrum_rpc_url,
+                bitcoin_backend,
                 &seed,
                 data_dir.clone(),
                 env_config,
                 bitcoin_target_block,
+                bitcoin::FeePolicies::default(),
+                bitcoin_fee_estimation,
             )
             .await?;
 
-            let amount = match amount {
-                Some(amount) => amount,
-                None => {
-                    bitcoin_wallet
-                        .max_giveable(address.script_pubkey().len())
-                        .await?
-                }
-            };
+            let psbt = bitcoin_wallet.build_withdrawal(&withdrawal).await?;
+
+            if psbt_only {
+                tracing::info!(txid = %psbt.unsigned_tx.txid(), "Built unsigned withdrawal transaction, sign it with a wallet holding the keys of the internal wallet");
+                println!("{}", bitcoin::psbt_to_base64(&psbt));
+                return Ok(());
+            }
 
-            let psbt = bitcoin_wallet
-                .send_to_address(address, amount, None)
-                .await?;
             let signed_tx = bitcoin_wallet.sign_and_finalize(psbt).await?;
 
             bitcoin_wallet.broadcast(signed_tx, "withdraw").await?;
         }
 
+        Command::BumpFee {
+            bitcoin_backend,
+            bitcoin_target_block,
+            bitcoin_fee_estimation,
+            txid,
+            fee_rate,
+        } => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
+
+            let seed = Seed::from_file_or_generate(data_dir.as_path())
+                .context("Failed to read in seed file")?;
+            let bitcoin_wallet = init_bitcoin_wallet(
+                bitcoin_backend,
+                &seed,
+                data_dir.clone(),
+                env_config,
+                bitcoin_target_block,
+                bitcoin::FeePolicies::default(),
+                bitcoin_fee_estimation,
+            )
+            .await?;
+
+            let (fee_bump, psbt) = bitcoin_wallet.bump_fee(txid, fee_rate).await?;
+            let signed_tx = bitcoin_wallet.sign_and_finalize(psbt).await?;
+
+            let (new_txid, _) = bitcoin_wallet.broadcast(signed_tx, "bump-fee").await?;
+            tracing::info!(%txid, %new_txid, method = %fee_bump, "Bumped fee of transaction");
+        }
+
         Command::Balance {
-            bitcoin_electrum_rpc_url,
+            bitcoin_backend,
             bitcoin_target_block,
+            bitcoin_fee_estimation,
         } => {
             cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
 
             let seed = Seed::from_file_or_generate(data_dir.as_path())
                 .context("Failed to read in seed file")?;
             let bitcoin_wallet = init_bitcoin_wallet(
-                bitcoin_electrum_rpc_url,
+                bitcoin_backend,
                 &seed,
                 data_dir.clone(),
                 env_config,
                 bitcoin_target_block,
+                bitcoin::FeePolicies::default(),
+                bitcoin_fee_estimation,
             )
             .await?;
 
@@ -256,11 +500,15 @@ async fn main() -> Result<()> {
         }
         Command::Resume {
             swap_id,
-            bitcoin_electrum_rpc_url,
+            bitcoin_backend,
             bitcoin_target_block,
+            bitcoin_fee_policies,
+            bitcoin_fee_estimation,
             monero_daemon_address,
             tor_socks5_port,
             namespace,
+            yes,
+            psbt_file,
         } => {
             cli::tracing::init(debug, json, data_dir.join("logs"), Some(swap_id))?;
 
@@ -269,11 +517,13 @@ async fn main() -> Result<()> {
                 .context("Failed to read in seed file")?;
 
             let bitcoin_wallet = init_bitcoin_wallet(
-                bitcoin_electrum_rpc_url,
+                bitcoin_backend,
                 &seed,
                 data_dir.clone(),
                 env_config,
                 bitcoin_target_block,
+                bitcoin_fee_policies,
+                bitcoin_fee_estimation,
             )
             .await?;
             let (monero_wallet, _process) =
@@ -283,25 +533,17 @@ async fn main() -> Result<()> {
             let seller_peer_id = db.get_peer_id(swap_id).await?;
             let seller_addresses = db.get_addresses(seller_peer_id).await?;
 
-            let behaviour = cli::Behaviour::new(
-                seller_peer_id,
-                env_config,
+            let (handle, controller) = init_event_loop(
+                &seed,
                 bitcoin_wallet.clone(),
-                (seed.derive_libp2p_identity(), namespace),
-            );
-            let mut swarm =
-                swarm::cli(seed.derive_libp2p_identity(), tor_socks5_port, behaviour).await?;
-            let our_peer_id = swarm.local_peer_id();
-            tracing::debug!(peer_id = %our_peer_id, "Network layer initialized");
-
-            for seller_address in seller_addresses {
-                swarm
-                    .behaviour_mut()
-                    .add_address(seller_peer_id, seller_address);
-            }
-
-            let (event_loop, event_loop_handle) = EventLoop::new(swap_id, swarm, seller_peer_id)?;
-            let handle = tokio::spawn(event_loop.run());
+                env_config,
+                namespace,
+                tor_socks5_port,
+                db.clone(),
+            )
+            .await?;
+            let event_loop_handle =
+                controller.swap_handle(swap_id, seller_peer_id, seller_addresses)?;
 
             let monero_receive_address = db.get_monero_address(swap_id).await?;
             let swap = Swap::from_db(
@@ -313,7 +555,16 @@ async fn main() -> Result<()> {
                 event_loop_handle,
                 monero_receive_address,
             )
-            .await?;
+            .await?
+            .with_external_signer(Arc::new(PsbtPrompt {
+                lock_psbt_file: psbt_file,
+            }))
+            .with_lock_confirmation(LockConfirmation {
+                seller: seller_peer_id,
+                bitcoin_avg_block_time: env_config.bitcoin_avg_block_time,
+                json,
+                assume_yes: yes,
+            });
 
             tokio::select! {
                 event_loop_result = handle => {
@@ -324,10 +575,247 @@ async fn main() -> Result<()> {
                 }
             }
         }
+        Command::ResumeAll {
+            bitcoin_backend,
+            bitcoin_target_block,
+            bitcoin_fee_policies,
+            bitcoin_fee_estimation,
+            monero_daemon_address,
+            tor_socks5_port,
+            namespace,
+            yes,
+        } => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
+
+            let db = open_db(data_dir.join("sqlite")).await?;
+            let swap_ids = db
+                .all()
+                .await?
+                .into_iter()
+                .filter(|(_, state)| !state.swap_finished())
+                .map(|(swap_id, _)| swap_id)
+                .collect::<Vec<_>>();
+
+            if swap_ids.is_empty() {
+                tracing::info!("There are no unfinished swaps to resume");
+                return Ok(());
+            }
+
+            let seed = Seed::from_file_or_generate(data_dir.as_path())
+                .context("Failed to read in seed file")?;
+
+            let bitcoin_wallet = init_bitcoin_wallet(
+                bitcoin_backend,
+                &seed,
+                data_dir.clone(),
+                env_config,
+                bitcoin_target_block,
+                bitcoin_fee_policies,
+                bitcoin_fee_estimation,
+            )
+            .await?;
+            let (monero_wallet, _process) =
+                init_monero_wallet(data_dir, monero_daemon_address, env_config).await?;
+            let bitcoin_wallet = Arc::new(bitcoin_wallet);
+            let monero_wallet = Arc::new(monero_wallet);
+
+            let (handle, controller) = init_event_loop(
+                &seed,
+                bitcoin_wallet.clone(),
+                env_config,
+                namespace,
+                tor_socks5_port,
+                db.clone(),
+            )
+            .await?;
+
+            tracing::info!(swaps = swap_ids.len(), "Resuming unfinished swaps");
+
+            let swaps = swap_ids.iter().map(|&swap_id| {
+                let db = db.clone();
+                let bitcoin_wallet = bitcoin_wallet.clone();
+                let monero_wallet = monero_wallet.clone();
+                let controller = controller.clone();
+
+                async move {
+                    let seller_peer_id = db.get_peer_id(swap_id).await?;
+                    let seller_addresses = db.get_addresses(seller_peer_id).await?;
+                    let event_loop_handle =
+                        controller.swap_handle(swap_id, seller_peer_id, seller_addresses)?;
+
+                    let monero_receive_address = db.get_monero_address(swap_id).await?;
+                    let swap = Swap::from_db(
+                        db,
+                        swap_id,
+                        bitcoin_wallet,
+                        monero_wallet,
+                        env_config,
+                        event_loop_handle,
+                        monero_receive_address,
+                    )
+                    .await?
+                    .with_external_signer(Arc::new(PsbtPrompt::default()))
+                    .with_lock_confirmation(LockConfirmation {
+                        seller: seller_peer_id,
+                        bitcoin_avg_block_time: env_config.bitcoin_avg_block_time,
+                        json,
+                        assume_yes: yes,
+                    });
+
+                    bob::run(swap).await
+                }
+            });
+
+            let results = tokio::select! {
+                event_loop_result = handle => {
+                    event_loop_result?;
+                    bail!("EventLoop stopped before the swaps were finished")
+                },
+                results = futures::future::join_all(swaps) => results,
+            };
+
+            let failed = results.iter().filter(|result| result.is_err()).count();
+
+            if json {
+                for (swap_id, result) in swap_ids.iter().zip(&results) {
+                    match result {
+                        Ok(state) => {
+                            tracing::info!(swap_id=%swap_id.to_string(), state=%state.to_string(), "Swap finished")
+                        }
+                        Err(error) => {
+                            tracing::error!(swap_id=%swap_id.to_string(), error=%format!("{:#}", error), "Swap failed")
+                        }
+                    }
+                }
+            } else {
+                let mut table = Table::new();
+
+                table.set_header(vec!["SWAP ID", "RESULT"]);
+
+                for (swap_id, result) in swap_ids.iter().zip(&results) {
+                    let result = match result {
+                        Ok(state) => state.to_string(),
+                        Err(error) => format!("failed: {:#}", error),
+                    };
+                    table.add_row(vec![swap_id.to_string(), result]);
+                }
+
+                println!("{}", table);
+            }
+
+            if failed > 0 {
+                bail!("{} of {} resumed swaps failed", failed, results.len())
+            }
+        }
+        Command::Cancel {
+            swap_id,
+            bitcoin_backend,
+            bitcoin_target_block,
+            bitcoin_fee_estimation,
+            dry_run,
+        } => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), Some(swap_id))?;
+
+            let db = open_db(data_dir.join("sqlite")).await?;
+            let seed = Seed::from_file_or_generate(data_dir.as_path())
+                .context("Failed to read in seed file")?;
+
+            let bitcoin_wallet = init_bitcoin_wallet(
+                bitcoin_backend,
+                &seed,
+                data_dir,
+                env_config,
+                bitcoin_target_block,
+                bitcoin::FeePolicies::default(),
+                bitcoin_fee_estimation,
+            )
+            .await?;
+            let bitcoin_wallet = Arc::new(bitcoin_wallet);
+
+            if dry_run {
+                let transactions = cli::dry_run(swap_id, bitcoin_wallet, db).await?;
+                print_refund_transactions(json, &[("cancel", transactions.cancel)]);
+            } else {
+                let (txid, _, _) = cli::cancel(swap_id, bitcoin_wallet, db).await?;
+                tracing::info!(%txid, "Cancel transaction submitted");
+            }
+        }
+        Command::Refund {
+            swap_id,
+            bitcoin_backend,
+            bitcoin_target_block,
+            bitcoin_fee_estimation,
+            dry_run,
+        } => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), Some(swap_id))?;
+
+            let db = open_db(data_dir.join("sqlite")).await?;
+            let seed = Seed::from_file_or_generate(data_dir.as_path())
+                .context("Failed to read in seed file")?;
+
+            let bitcoin_wallet = init_bitcoin_wallet(
+                bitcoin_backend,
+                &seed,
+                data_dir,
+                env_config,
+                bitcoin_target_block,
+                bitcoin::FeePolicies::default(),
+                bitcoin_fee_estimation,
+            )
+            .await?;
+            let bitcoin_wallet = Arc::new(bitcoin_wallet);
+
+            if dry_run {
+                let transactions = cli::dry_run(swap_id, bitcoin_wallet, db).await?;
+                print_refund_transactions(json, &[("refund", transactions.refund)]);
+            } else {
+                cli::refund(swap_id, bitcoin_wallet, db).await?;
+                tracing::info!("Refund transaction submitted");
+            }
+        }
         Command::CancelAndRefund {
             swap_id,
-            bitcoin_electrum_rpc_url,
+            bitcoin_backend,
+            bitcoin_target_block,
+            bitcoin_fee_estimation,
+            dry_run,
+        } => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), Some(swap_id))?;
+
+            let db = open_db(data_dir.join("sqlite")).await?;
+            let seed = Seed::from_file_or_generate(data_dir.as_path())
+                .context("Failed to read in seed file")?;
+
+            let bitcoin_wallet = init_bitcoin_wallet(
+                bitcoin_backend,
+                &seed,
+                data_dir,
+                env_config,
+                bitcoin_target_block,
+                bitcoin::FeePolicies::default(),
+                bitcoin_fee_estimation,
+            )
+            .await?;
+            let bitcoin_wallet = Arc::new(bitcoin_wallet);
+
+            if dry_run {
+                let transactions = cli::dry_run(swap_id, bitcoin_wallet, db).await?;
+                print_refund_transactions(
+                    json,
+                    &[
+                        ("cancel", transactions.cancel),
+                        ("refund", transactions.refund),
+                    ],
+                );
+            } else {
+                cli::cancel_and_refund(swap_id, bitcoin_wallet, db).await?;
+            }
+        }
+        Command::Abort {
+            swap_id,
+            bitcoin_backend,
             bitcoin_target_block,
+            bitcoin_fee_estimation,
         } => {
             cli::tracing::init(debug, json, data_dir.join("logs"), Some(swap_id))?;
 
@@ -336,20 +824,123 @@ async fn main() -> Result<()> {
                 .context("Failed to read in seed file")?;
 
             let bitcoin_wallet = init_bitcoin_wallet(
-                bitcoin_electrum_rpc_url,
+                bitcoin_backend,
                 &seed,
                 data_dir,
                 env_config,
                 bitcoin_target_block,
+                bitcoin::FeePolicies::default(),
+                bitcoin_fee_estimation,
             )
             .await?;
 
-            cli::cancel_and_refund(swap_id, Arc::new(bitcoin_wallet), db).await?;
+            cli::abort(swap_id, Arc::new(bitcoin_wallet), db).await?;
+            tracing::info!(%swap_id, "Swap aborted");
+        }
+        Command::Archive { swap_id } => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
+
+            let db = open_db(data_dir.join("sqlite")).await?;
+            let state = db.get_state(swap_id).await?;
+            if !state.swap_finished() {
+                let state: BobState = state.try_into()?;
+                bail!(
+                    "Cannot archive swap {} because it is not finished yet, it is in state {}",
+                    swap_id,
+                    state
+                )
+            }
+
+            db.archive(swap_id).await?;
+            tracing::info!(%swap_id, "Archived swap, use `history --all` to show it");
+        }
+        Command::Unarchive { swap_id } => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
+
+            let db = open_db(data_dir.join("sqlite")).await?;
+            db.unarchive(swap_id).await?;
+            tracing::info!(%swap_id, "Unarchived swap");
+        }
+        Command::ListKnownSellers => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
+
+            let db = open_db(data_dir.join("sqlite")).await?;
+            let sellers = known_sellers(db.as_ref()).await?;
+
+            if json {
+                for seller in sellers {
+                    tracing::info!(
+                        peer_id = %seller.peer_id,
+                        name = %seller.name.unwrap_or_default(),
+                        addresses = ?seller.addresses,
+                        agent_version = %seller.agent_version.unwrap_or_default(),
+                        completed = seller.stats.completed,
+                        refunded = seller.stats.refunded,
+                        punished = seller.stats.punished,
+                        avg_time_to_xmr_lock = %seller.stats.avg_time_to_xmr_lock(),
+                        last_seen = %seller.stats.last_seen(),
+                        "Read seller from address book"
+                    );
+                }
+            } else {
+                let mut table = Table::new();
+
+                table.set_header(vec![
+                    "PEER ID",
+                    "NAME",
+                    "ADDRESSES",
+                    "AGENT VERSION",
+                    "HISTORY",
+                    "AVG TIME TO XMR LOCK",
+                    "LAST SEEN",
+                ]);
+
+                for seller in sellers {
+                    table.add_row(vec![
+                        seller.peer_id.to_string(),
+                        seller.name.unwrap_or_default(),
+                        seller
+                            .addresses
+                            .iter()
+                            .map(|address| address.to_string())
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                        seller.agent_version.unwrap_or_default(),
+                        seller.stats.outcomes(),
+                        seller.stats.avg_time_to_xmr_lock(),
+                        seller.stats.last_seen(),
+                    ]);
+                }
+
+                println!("{}", table);
+            }
+        }
+        Command::AddSeller { address, name } => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
+
+            let db = open_db(data_dir.join("sqlite")).await?;
+            let peer_id = add_seller(db.as_ref(), address, name).await?;
+            tracing::info!(%peer_id, "Added seller to the address book");
+        }
+        Command::RemoveSeller { peer_id } => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
+
+            let db = open_db(data_dir.join("sqlite")).await?;
+            db.remove_seller(peer_id).await?;
+            tracing::info!(%peer_id, "Removed seller from the address book");
+        }
+        Command::RenameSeller { peer_id, name } => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
+
+            let db = open_db(data_dir.join("sqlite")).await?;
+            db.rename_seller(peer_id, name).await?;
+            tracing::info!(%peer_id, "Renamed seller");
         }
         Command::ListSellers {
             rendezvous_point,
             namespace,
             tor_socks5_port,
+            reference_price,
         } => {
             let rendezvous_node_peer_id = rendezvous_point
                 .extract_peer_id()
@@ -370,6 +961,75 @@ async fn main() -> Result<()> {
             )
             .await?;
 
+            let db = open_db(data_dir.join("sqlite")).await?;
+            let known_sellers = known_sellers(db.as_ref())
+                .await?
+                .into_iter()
+                .map(|seller| (seller.peer_id, seller))
+                .collect::<HashMap<_, _>>();
+            let config = cli::config::Config::read(&data_dir)?;
+            let known_addresses = db.get_all_addresses().await?;
+            for seller in &sellers {
+                let peer_id = match seller.multiaddr.extract_peer_id() {
+                    Some(peer_id) => peer_id,
+                    None => continue,
+                };
+
+                match seller_identity::identify(
+                    peer_id,
+                    &seller.multiaddr,
+                    &config.pinned_sellers,
+                    &known_addresses,
+                ) {
+                    Ok(Identity::Changed { previous }) => {
+                        tracing::warn!(address = %seller.multiaddr, ?previous, "The seller's address belonged to a different peer before");
+                    }
+                    Ok(_) => {}
+                    Err(error) => tracing::warn!("{}", error),
+                }
+            }
+            let known_seller = |address: &Multiaddr| {
+                address
+                    .extract_peer_id()
+                    .and_then(|peer_id| known_sellers.get(&peer_id))
+            };
+            let name = |address: &Multiaddr| {
+                known_seller(address)
+                    .and_then(|seller| seller.name.clone())
+                    .unwrap_or_default()
+            };
+            let history = |address: &Multiaddr| {
+                known_seller(address)
+                    .map(|seller| seller.stats.outcomes())
+                    .unwrap_or_default()
+            };
+
+            let market_price = match &reference_price {
+                Some(reference_price) => {
+                    let tor_socks5_port = tor_socks5_port_if_running(tor_socks5_port).await;
+                    match reference_price.market_price(tor_socks5_port).await {
+                        Ok(market_price) => Some(market_price),
+                        Err(error) => {
+                            tracing::warn!(
+                                "Failed to fetch the market price, listing sellers without premium: {:#}",
+                                error
+                            );
+                            None
+                        }
+                    }
+                }
+                None => None,
+            };
+            let premium = |quote: BidQuote| {
+                let (reference_price, market_price) = (reference_price.as_ref()?, market_price?);
+                let premium = cli::reference_price::premium(quote.price, market_price);
+
+                Some(match reference_price.check(quote.price, market_price) {
+                    Ok(_) => format!("{:+}%", premium),
+                    Err(_) => format!("{:+}% (above max)", premium),
+                })
+            };
+
             if json {
                 for seller in sellers {
                     match seller.status {
@@ -378,8 +1038,11 @@ async fn main() -> Result<()> {
                                 price = %quote.price.to_string(),
                                 min_quantity = %quote.min_quantity.to_string(),
                                 max_quantity = %quote.max_quantity.to_string(),
+                                premium = %premium(quote).unwrap_or_default(),
                                 status = "Online",
                                 address = %seller.multiaddr.to_string(),
+                                name = %name(&seller.multiaddr),
+                                history = %history(&seller.multiaddr),
                                 "Fetched peer status"
                             );
                         }
@@ -387,6 +1050,8 @@ async fn main() -> Result<()> {
                             tracing::info!(
                                 status = "Unreachable",
                                 address = %seller.multiaddr.to_string(),
+                                name = %name(&seller.multiaddr),
+                                history = %history(&seller.multiaddr),
                                 "Fetched peer status"
                             );
                         }
@@ -395,35 +1060,49 @@ async fn main() -> Result<()> {
             } else {
                 let mut table = Table::new();
 
-                table.set_header(vec![
-                    "PRICE",
+                let mut header = vec!["PRICE"];
+                if let Some(market_price) = market_price {
+                    tracing::info!(%market_price, "Fetched market price of XMR");
+                    header.push("PREMIUM");
+                }
+                header.extend([
                     "MIN_QUANTITY",
                     "MAX_QUANTITY",
                     "STATUS",
                     "ADDRESS",
+                    "NAME",
+                    "HISTORY",
                 ]);
+                table.set_header(header);
 
                 for seller in sellers {
-                    let row = match seller.status {
+                    let mut row = match seller.status {
                         SellerStatus::Online(quote) => {
-                            vec![
-                                quote.price.to_string(),
+                            let mut row = vec![quote.price.to_string()];
+                            row.extend(premium(quote));
+                            row.extend([
                                 quote.min_quantity.to_string(),
                                 quote.max_quantity.to_string(),
                                 "Online".to_owned(),
                                 seller.multiaddr.to_string(),
-                            ]
+                            ]);
+                            row
                         }
                         SellerStatus::Unreachable => {
-                            vec![
-                                "???".to_owned(),
+                            let mut row = vec!["???".to_owned()];
+                            if market_price.is_some() {
+                                row.push("???".to_owned());
+                            }
+                            row.extend([
                                 "???".to_owned(),
                                 "???".to_owned(),
                                 "Unreachable".to_owned(),
                                 seller.multiaddr.to_string(),
-                            ]
+                            ]);
+                            row
                         }
                     };
+                    row.extend([name(&seller.multiaddr), history(&seller.multiaddr)]);
 
                     table.add_row(row);
                 }
@@ -432,19 +1111,22 @@ async fn main() -> Result<()> {
             }
         }
         Command::ExportBitcoinWallet {
-            bitcoin_electrum_rpc_url,
+            bitcoin_backend,
             bitcoin_target_block,
+            bitcoin_fee_estimation,
         } => {
             cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
 
             let seed = Seed::from_file_or_generate(data_dir.as_path())
                 .context("Failed to read in seed file")?;
             let bitcoin_wallet = init_bitcoin_wallet(
-                bitcoin_electrum_rpc_url,
+                bitcoin_backend,
                 &seed,
                 data_dir.clone(),
                 env_config,
                 bitcoin_target_block,
+                bitcoin::FeePolicies::default(),
+                bitcoin_fee_estimation,
             )
             .await?;
             let wallet_export = bitcoin_wallet.wallet_export("cli").await?;
@@ -489,26 +1171,61 @@ async fn main() -> Result<()> {
                 }
             }
         }
+        Command::EncryptSeed => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
+
+            // Checked first, reading a missing seed file would generate a new seed
+            if Seed::is_encrypted(data_dir.as_path()).context("Failed to read in seed file")? {
+                bail!("Seed file is already encrypted, use `change-seed-passphrase` to change its passphrase")
+            }
+            let seed = Seed::from_file_or_generate(data_dir.as_path())
+                .context("Failed to read in seed file")?;
+
+            let passphrase = Passphrase::new_from_env_or_prompt()?;
+            seed.encrypt_file(data_dir.as_path(), &passphrase)
+                .context("Failed to encrypt seed file")?;
+
+            tracing::info!(path=%format!("{}/seed.pem", data_dir.display()), "Encrypted seed file");
+        }
+        Command::ChangeSeedPassphrase => {
+            cli::tracing::init(debug, json, data_dir.join("logs"), None)?;
+
+            if !Seed::is_encrypted(data_dir.as_path()).context("Failed to read in seed file")? {
+                bail!("Seed file is not encrypted, use `encrypt-seed` to encrypt it")
+            }
+            let seed = Seed::from_file_or_generate(data_dir.as_path())
+                .context("Failed to read in seed file")?;
+
+            let passphrase = Passphrase::new_from_env_or_prompt()?;
+            seed.encrypt_file(data_dir.as_path(), &passphrase)
+                .context("Failed to re-encrypt seed file")?;
+
+            tracing::info!(path=%format!("{}/seed.pem", data_dir.display()), "Changed seed file passphrase");
+        }
     };
     Ok(())
 }
 
 async fn init_bitcoin_wallet(
-    electrum_rpc_url: Url,
+    bitcoin_backend: bitcoin::Backend,
     seed: &Seed,
     data_dir: PathBuf,
     env_config: Config,
     bitcoin_target_block: usize,
+    bitcoin_fee_policies: bitcoin::FeePolicies,
+    bitcoin_fee_estimation: bitcoin::FeeEstimation,
 ) -> Result<bitcoin::Wallet> {
     tracing::debug!("Initializing bitcoin wallet");
     let xprivkey = seed.derive_extended_private_key(env_config.bitcoin_network)?;
 
     let wallet = bitcoin::Wallet::new(
-        electrum_rpc_url.clone(),
+        bitcoin_backend,
         data_dir,
         xprivkey,
         env_config,
         bitcoin_target_block,
+        bitcoin_fee_policies,
+        bitcoin_fee_estimation,
     )
     .await
     .context("Failed to initialize Bitcoin wallet")?;
@@ -554,6 +1271,264 @@ fn qr_code(value: &impl ToString) -> Result<String> {
     Ok(qr_code)
 }
 
+/// The highest price per XMR to accept during swap setup, given the price
+/// quoted by the seller and the limits set by the user.
+fn price_limit(
+    quoted: bitcoin::Amount,
+    max_price: Option<bitcoin::Amount>,
+    max_slippage: Option<Decimal>,
+) -> Result<Option<bitcoin::Amount>> {
+    let slippage_limit = match max_slippage {
+        Some(max_slippage) => {
+            let factor = Decimal::ONE + max_slippage / Decimal::ONE_HUNDRED;
+            let limit = Decimal::from(quoted.to_sat())
+                .checked_mul(factor)
+                .and_then(|limit| limit.floor().to_u64())
+                .context("Maximum slippage is too large")?;
+
+            Some(bitcoin::Amount::from_sat(limit))
+        }
+        None => None,
+    };
+
+    Ok(match (max_price, slippage_limit) {
+        (Some(max_price), Some(slippage_limit)) => Some(min(max_price, slippage_limit)),
+        (max_price, slippage_limit) => max_price.or(slippage_limit),
+    })
+}
+
+/// The port of the Tor SOCKS5 proxy if Tor is running, like the swarm only
+/// connects through Tor if it is running.
+async fn tor_socks5_port_if_running(tor_socks5_port: u16) -> Option<u16> {
+    match tor::Client::new(tor_socks5_port).assert_tor_running().await {
+        Ok(()) => Some(tor_socks5_port),
+        Err(_) => None,
+    }
+}
+
+/// Initializes the network layer and spawns the event loop that serves the
+/// swaps of this process.
+async fn init_event_loop(
+    seed: &Seed,
+    bitcoin_wallet: Arc<bitcoin::Wallet>,
+    env_config: Config,
+    namespace: XmrBtcNamespace,
+    tor_socks5_port: u16,
+    db: Arc<dyn Database + Send + Sync>,
+) -> Result<(JoinHandle<()>, EventLoopController)> {
+    let behaviour = cli::Behaviour::new(
+        env_config,
+        bitcoin_wallet,
+        (seed.derive_libp2p_identity(), namespace),
+    );
+    let swarm = swarm::cli(seed.derive_libp2p_identity(), tor_socks5_port, behaviour).await?;
+
+    tracing::debug!(peer_id = %swarm.local_peer_id(), "Network layer initialized");
+
+    let (event_loop, controller) = EventLoop::new(swarm, db)?;
+    let event_loop = tokio::spawn(event_loop.run());
+
+    Ok((event_loop, controller))
+}
+
+/// Registers the swap with the seller at the given address with the event
+/// loop, after checking the seller against the pinned and previously seen
+/// sellers.
+async fn connect_to_seller(
+    seller: Multiaddr,
+    swap_id: Uuid,
+    db: &Arc<dyn Database + Send + Sync>,
+    pinned_sellers: &[PinnedSeller],
+    controller: &EventLoopController,
+) -> Result<(PeerId, cli::EventLoopHandle)> {
+    let seller_peer_id = seller
+        .extract_peer_id()
+        .context("Seller address must contain peer ID")?;
+    seller_identity::check_seller(db.as_ref(), pinned_sellers, seller_peer_id, &seller).await?;
+    db.insert_address(seller_peer_id, seller.clone()).await?;
+    db.insert_seller_if_unknown(seller_peer_id).await?;
+
+    let event_loop_handle = controller.swap_handle(swap_id, seller_peer_id, vec![seller])?;
+
+    Ok((seller_peer_id, event_loop_handle))
+}
+
+/// Shows the transactions of a dry run of `cancel` and `refund`.
+fn print_refund_transactions(json: bool, transactions: &[(&str, TxSummary)]) {
+    if json {
+        for (name, tx) in transactions {
+            tracing::info!(transaction = %name, txid = %tx.txid, fee = %tx.fee, publishable = %tx.valid, "Dry run, not publishing the transaction");
+        }
+        return;
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec!["TRANSACTION", "TXID", "FEE", "PUBLISHABLE"]);
+
+    for (name, tx) in transactions {
+        table.add_row(vec![
+            name.to_string(),
+            tx.txid.to_string(),
+            tx.fee.to_string(),
+            tx.valid.to_string(),
+        ]);
+    }
+
+    println!("{}", table);
+}
+
+fn is_declined_by_seller(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<SwapSetupError>()
+        .map_or(false, SwapSetupError::is_declined_by_seller)
+}
+
+/// Asks the user for an unsigned PSBT, created by an external wallet, that
+/// pays the amount to swap to a placeholder address of the internal wallet.
+///
+/// Using an address of the internal wallet as placeholder ensures the funds
+/// are not lost if the funding PSBT is signed and broadcast by accident.
+async fn request_external_funding(
+    bid_quote: impl Future<Output = Result<BidQuote>>,
+    get_new_address: impl Future<Output = Result<bitcoin::Address>>,
+) -> Result<bitcoin::ExternalFunding> {
+    tracing::debug!("Requesting quote");
+    let bid_quote = bid_quote.await?;
+
+    if bid_quote.max_quantity == bitcoin::Amount::ZERO {
+        bail!(ZeroQuoteReceived)
+    }
+
+    let placeholder_address = get_new_address.await?;
+    let minimum_amount = bid_quote.min_quantity;
+    let maximum_amount = bid_quote.max_quantity;
+
+    tracing::info!(
+        price = %bid_quote.price,
+        %minimum_amount,
+        %maximum_amount,
+        "Received quote",
+    );
+    tracing::info!(
+        %placeholder_address,
+        "Create a PSBT with your external wallet that pays the amount to swap to the placeholder address, using native segwit inputs only. Do not sign it yet.",
+    );
+
+    let psbt = cli::external_funding::read_psbt(String::from(
+        "Enter the unsigned funding PSBT (base64) or the path of the file containing it:",
+    ))
+    .await?;
+    let funding = bitcoin::ExternalFunding::new(psbt, placeholder_address.script_pubkey())?;
+    let amount = funding.amount()?;
+
+    if amount < minimum_amount || amount > maximum_amount {
+        bail!(
+            "Funding PSBT pays {} but the seller only accepts amounts between {} and {}",
+            amount,
+            minimum_amount,
+            maximum_amount
+        )
+    }
+
+    Ok(funding)
+}
+
+/// Calculates the amount of Bitcoin needed to receive exactly `xmr_amount`
+/// after paying the Monero network fee at the seller's price and waits until
+/// the internal wallet can lock that amount.
+#[allow(clippy::too_many_arguments)]
+async fn determine_btc_for_xmr<FB, TB, FMG, TMG, FS, TS, FFE, TFE>(
+    json: bool,
+    xmr_amount: monero::Amount,
+    bid_quote: impl Future<Output = Result<BidQuote>>,
+    get_new_address: impl Future<Output = Result<bitcoin::Address>>,
+    balance: FB,
+    max_giveable_fn: FMG,
+    sync: FS,
+    estimate_fee: FFE,
+) -> Result<(bitcoin::Amount, bitcoin::Amount)>
+where
+    TB: Future<Output = Result<bitcoin::Amount>>,
+    FB: Fn() -> TB,
+    TMG: Future<Output = Result<bitcoin::Amount>>,
+    FMG: Fn() -> TMG,
+    TS: Future<Output = Result<()>>,
+    FS: Fn() -> TS,
+    FFE: Fn(bitcoin::Amount) -> TFE,
+    TFE: Future<Output = Result<bitcoin::Amount>>,
+{
+    tracing::debug!("Requesting quote");
+    let bid_quote = bid_quote.await?;
+
+    if bid_quote.max_quantity == bitcoin::Amount::ZERO {
+        bail!(ZeroQuoteReceived)
+    }
+
+    let btc_amount = xmr_amount
+        .min_bitcoin_for_price(bid_quote.price)
+        .context("Failed to calculate the amount of Bitcoin to swap")?;
+
+    tracing::info!(
+        price = %bid_quote.price,
+        minimum_amount = %bid_quote.min_quantity,
+        maximum_amount = %bid_quote.max_quantity,
+        "Received quote",
+    );
+
+    if btc_amount < bid_quote.min_quantity || btc_amount > bid_quote.max_quantity {
+        bail!(
+            "Buying {} requires {} but the seller only accepts amounts between {} and {}",
+            xmr_amount,
+            btc_amount,
+            bid_quote.min_quantity,
+            bid_quote.max_quantity
+        )
+    }
+
+    let mut max_giveable = max_giveable_fn().await?;
+
+    if max_giveable < btc_amount {
+        let deposit_address = get_new_address.await?;
+
+        if !json {
+            eprintln!("{}", qr_code(&deposit_address)?);
+        }
+
+        while max_giveable < btc_amount {
+            let outstanding = btc_amount - max_giveable;
+            let fee = estimate_fee(outstanding).await?;
+            let deposit = outstanding + fee;
+
+            tracing::info!("Deposit {} to buy {} with fee!", deposit, xmr_amount);
+            tracing::info!(
+                %deposit_address,
+                %deposit,
+                %max_giveable,
+                %btc_amount,
+                "Waiting for Bitcoin deposit",
+            );
+
+            max_giveable = loop {
+                sync().await?;
+                let new_max_givable = max_giveable_fn().await?;
+
+                if new_max_givable > max_giveable {
+                    break new_max_givable;
+                }
+
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            };
+
+            let new_balance = balance().await?;
+            tracing::info!(%new_balance, %max_giveable, "Received Bitcoin");
+        }
+    }
+
+    let fees = estimate_fee(btc_amount).await?;
+
+    Ok((btc_amount, fees))
+}
+
 async fn determine_btc_to_swap<FB, TB, FMG, TMG, FS, TS, FFE, TFE>(
     json: bool,
     bid_quote: impl Future<Output = Result<BidQuote>>,
@@ -650,7 +1625,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::determine_btc_to_swap;
+    use crate::{determine_btc_for_xmr, determine_btc_to_swap, price_limit};
     use ::bitcoin::Amount;
     use std::sync::Mutex;
     use swap::tracing_ext::capture_logs;
@@ -986,6 +1961,92 @@ mod tests {
         assert_eq!("Received quote of 0", determination_error);
     }
 
+    #[tokio::test]
+    async fn given_exact_xmr_amount_and_no_balance_then_requests_exact_deposit() {
+        let writer = capture_logs(LevelFilter::INFO);
+        let givable = Arc::new(Mutex::new(MaxGiveable::new(vec![
+            Amount::ZERO,
+            Amount::from_btc(0.0005).unwrap(),
+            Amount::from_btc(0.00100002).unwrap(),
+        ])));
+
+        let (amount, fees) = determine_btc_for_xmr(
+            true,
+            monero::Amount::parse_monero("1").unwrap(),
+            async { Ok(quote_with_max(0.01)) },
+            get_dummy_address(),
+            || async { Ok(Amount::from_btc(0.0011)?) },
+            || async {
+                let mut result = givable.lock().unwrap();
+                result.give()
+            },
+            || async { Ok(()) },
+            |_| async { Ok(Amount::from_sat(1000)) },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            (amount, fees),
+            (
+                Amount::from_btc(0.00100002).unwrap(),
+                Amount::from_sat(1000)
+            )
+        );
+        assert_eq!(
+            writer.captured(),
+            r" INFO swap: Received quote price=0.00100000 BTC minimum_amount=0.00000000 BTC maximum_amount=0.01000000 BTC
+ INFO swap: Deposit 0.00101002 BTC to buy 1.000000000000 XMR with fee!
+ INFO swap: Waiting for Bitcoin deposit deposit_address=1PdfytjS7C8wwd9Lq5o4x9aXA2YRqaCpH6 deposit=0.00101002 BTC max_giveable=0.00000000 BTC btc_amount=0.00100002 BTC
+ INFO swap: Received Bitcoin new_balance=0.00110000 BTC max_giveable=0.00050000 BTC
+ INFO swap: Deposit 0.00051002 BTC to buy 1.000000000000 XMR with fee!
+ INFO swap: Waiting for Bitcoin deposit deposit_address=1PdfytjS7C8wwd9Lq5o4x9aXA2YRqaCpH6 deposit=0.00051002 BTC max_giveable=0.00050000 BTC btc_amount=0.00100002 BTC
+ INFO swap: Received Bitcoin new_balance=0.00110000 BTC max_giveable=0.00100002 BTC
+"
+        );
+    }
+
+    #[tokio::test]
+    async fn given_exact_xmr_amount_above_max_quantity_then_fails() {
+        let result = determine_btc_for_xmr(
+            true,
+            monero::Amount::parse_monero("100").unwrap(),
+            async { Ok(quote_with_max(0.01)) },
+            async { panic!("should not request new address when amount is not accepted") },
+            || async { Ok(Amount::ZERO) },
+            || async { Ok(Amount::ZERO) },
+            || async { Ok(()) },
+            |_| async { Ok(Amount::from_sat(1000)) },
+        )
+        .await;
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn price_limit_is_the_lower_of_max_price_and_slippage() {
+        let quoted = Amount::from_sat(600_000);
+
+        assert_eq!(price_limit(quoted, None, None).unwrap(), None);
+        assert_eq!(
+            price_limit(quoted, Some(Amount::from_sat(650_000)), None).unwrap(),
+            Some(Amount::from_sat(650_000))
+        );
+        assert_eq!(
+            price_limit(quoted, None, Some(Decimal::new(15, 1))).unwrap(),
+            Some(Amount::from_sat(609_000))
+        );
+        assert_eq!(
+            price_limit(
+                quoted,
+                Some(Amount::from_sat(605_000)),
+                Some(Decimal::new(15, 1))
+            )
+            .unwrap(),
+            Some(Amount::from_sat(605_000))
+        );
+    }
+
     struct MaxGiveable {
         amounts: Vec<Amount>,
         call_counter: usize,
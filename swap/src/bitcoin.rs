@@ -1,18 +1,30 @@
+pub mod backend;
 pub mod wallet;
 
 mod cancel;
+mod fee_estimator;
+mod fee_policy;
 mod lock;
 mod punish;
 mod redeem;
 mod refund;
 mod timelocks;
+mod withdraw;
 
+pub use crate::bitcoin::backend::{Backend, BitcoindRpc, ElectrumConfig, EsploraConfig};
 pub use crate::bitcoin::cancel::{CancelTimelock, PunishTimelock, TxCancel};
-pub use crate::bitcoin::lock::TxLock;
+pub use crate::bitcoin::fee_estimator::{Combine, FeeEstimation, FeeSource};
+pub use crate::bitcoin::fee_policy::{
+    FeePolicies, FeePolicy, FeePolicyOverride, FeePolicySetting, TxKind,
+};
+pub use crate::bitcoin::lock::{ExternalFunding, TxLock};
 pub use crate::bitcoin::punish::TxPunish;
 pub use crate::bitcoin::redeem::TxRedeem;
 pub use crate::bitcoin::refund::TxRefund;
 pub use crate::bitcoin::timelocks::{BlockHeight, ExpiredTimelocks};
+pub use crate::bitcoin::withdraw::{
+    parse_fee_rate, psbt_to_base64, to_fee_rate, Recipient, Withdrawal,
+};
 pub use ::bitcoin::util::amount::Amount;
 pub use ::bitcoin::util::psbt::PartiallySignedTransaction;
 pub use ::bitcoin::{Address, Network, Transaction, Txid};
@@ -222,6 +234,25 @@ pub fn build_shared_output_descriptor(A: Point, B: Point) -> Descriptor<bitcoin:
     Descriptor::Wsh(Wsh::new(miniscript).expect("a valid descriptor"))
 }
 
+/// The weight of a transaction spending the shared output of [`TxLock`] or
+/// [`TxCancel`] to a single output paying to the given address.
+///
+/// Everything but the output is the same for all of them, the output itself
+/// depends on the length of the script of the address, e.g. P2WSH and P2TR
+/// outputs are 12 bytes larger than P2WPKH ones.
+fn weight_spending_shared_output_to(address: &Address) -> usize {
+    /// The input with its witness of two signatures and the script of the
+    /// shared output, plus the version, locktime and segwit marker.
+    const WEIGHT_WITHOUT_OUTPUT: usize = 424;
+
+    let output = ::bitcoin::TxOut {
+        value: 0,
+        script_pubkey: address.script_pubkey(),
+    };
+
+    WEIGHT_WITHOUT_OUTPUT + 4 * ::bitcoin::consensus::encode::serialize(&output).len()
+}
+
 pub fn recover(S: PublicKey, sig: Signature, encsig: EncryptedSignature) -> Result<SecretKey> {
     let adaptor = Adaptor::<HashTranscript<Sha256>, Deterministic<Sha256>>::default();
 
@@ -372,7 +403,58 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn calculate_transaction_weights() {
+    async fn calculate_transaction_weights_paying_to_p2wpkh() {
+        let wallet = WalletBuilder::new(0).build();
+        let address = wallet.new_address().await.unwrap();
+
+        assert_transaction_weights(address).await;
+    }
+
+    #[tokio::test]
+    async fn calculate_transaction_weights_paying_to_p2wsh() {
+        assert_transaction_weights(p2wsh_address()).await;
+    }
+
+    #[tokio::test]
+    async fn calculate_transaction_weights_paying_to_p2tr() {
+        assert_transaction_weights(p2tr_address()).await;
+    }
+
+    #[tokio::test]
+    async fn weight_depends_on_the_length_of_the_output_script() {
+        let wallet = WalletBuilder::new(0).build();
+        let p2wpkh = wallet.new_address().await.unwrap();
+
+        for tx_weight in [TxRedeem::weight, TxPunish::weight, TxRefund::weight] {
+            assert_eq!(tx_weight(&p2wpkh), 548);
+            assert_eq!(tx_weight(&p2wsh_address()), 596);
+            assert_eq!(tx_weight(&p2tr_address()), 596);
+        }
+    }
+
+    fn p2wsh_address() -> Address {
+        // OP_TRUE, the script does not matter, only the length of its hash
+        Address::p2wsh(&::bitcoin::Script::from(vec![0x51]), Network::Regtest)
+    }
+
+    fn p2tr_address() -> Address {
+        let internal_key = secp256k1::XOnlyPublicKey::from_str(
+            "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115",
+        )
+        .unwrap();
+
+        Address::p2tr(
+            &secp256k1::Secp256k1::verification_only(),
+            internal_key,
+            None,
+            Network::Regtest,
+        )
+    }
+
+    /// Runs the protocol up to the point where all transactions can be signed
+    /// with Alice redeeming and punishing to, and Bob refunding to, the given
+    /// address.
+    async fn assert_transaction_weights(address: Address) {
         let alice_wallet = WalletBuilder::new(Amount::ONE_BTC.to_sat()).build();
         let bob_wallet = WalletBuilder::new(Amount::ONE_BTC.to_sat()).build();
         let spending_fee = Amount::from_sat(1_000);
@@ -380,15 +462,15 @@ mod tests {
         let xmr_amount = crate::monero::Amount::from_piconero(10000);
 
         let tx_redeem_fee = alice_wallet
-            .estimate_fee(TxRedeem::weight(), btc_amount)
+            .estimate_fee(TxKind::Redeem, TxRedeem::weight(&address), btc_amount)
             .await
             .unwrap();
         let tx_punish_fee = alice_wallet
-            .estimate_fee(TxPunish::weight(), btc_amount)
+            .estimate_fee(TxKind::Punish, TxPunish::weight(&address), btc_amount)
             .await
             .unwrap();
-        let redeem_address = alice_wallet.new_address().await.unwrap();
-        let punish_address = alice_wallet.new_address().await.unwrap();
+        let redeem_address = address.clone();
+        let punish_address = address.clone();
 
         let config = Regtest::get_config();
         let alice_state0 = alice::State0::new(
@@ -409,10 +491,11 @@ mod tests {
             xmr_amount,
             config.bitcoin_cancel_timelock,
             config.bitcoin_punish_timelock,
-            bob_wallet.new_address().await.unwrap(),
+            address.clone(),
             config.monero_finality_confirmations,
             spending_fee,
             spending_fee,
+            None,
         );
 
         let message0 = bob_state0.next_message();
@@ -446,10 +529,10 @@ mod tests {
             .unwrap();
         let refund_transaction = bob_state6.signed_refund_transaction().unwrap();
 
-        assert_weight(redeem_transaction, TxRedeem::weight(), "TxRedeem");
+        assert_weight(redeem_transaction, TxRedeem::weight(&address), "TxRedeem");
         assert_weight(cancel_transaction, TxCancel::weight(), "TxCancel");
-        assert_weight(punish_transaction, TxPunish::weight(), "TxPunish");
-        assert_weight(refund_transaction, TxRefund::weight(), "TxRefund");
+        assert_weight(punish_transaction, TxPunish::weight(&address), "TxPunish");
+        assert_weight(refund_transaction, TxRefund::weight(&address), "TxRefund");
     }
 
     // Weights fluctuate because of the length of the signatures. Valid ecdsa
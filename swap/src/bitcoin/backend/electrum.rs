@@ -0,0 +1,509 @@
+use crate::bitcoin::backend::{ChainBackend, Changes, ElectrumConfig, HistoryEntry};
+use crate::bitcoin::timelocks::BlockHeight;
+use crate::bitcoin::{Amount, Transaction};
+use ::bitcoin::{BlockHash, Script, Txid};
+use anyhow::{bail, Context, Result};
+use bdk::blockchain::{AnyBlockchain, Blockchain, ElectrumBlockchain, GetTx};
+use bdk::electrum_client::ElectrumApi;
+use bdk::FeeRate;
+use once_cell::sync::OnceCell;
+use reqwest::Url;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How many blocks a server may be away from the other servers before we
+/// consider it to be lagging or lying.
+const MAX_BLOCK_HEIGHT_DEVIATION: u32 = 2;
+
+/// The client reconnects transparently but does not renew our subscriptions
+/// on the new connection. Lost script subscriptions are noticed when popping
+/// their notifications, the header subscription is renewed regularly.
+const RENEW_SUBSCRIPTIONS_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Talks to one or more Electrum servers.
+///
+/// Requests go to the current server. If it fails, the other servers are
+/// tried, preferring those with the fewest failed requests in a row, and the
+/// first one that answers becomes the current server. Servers we could not
+/// connect to are connected to when they are tried.
+pub struct Electrum {
+    servers: Vec<Server>,
+    current: AtomicUsize,
+    cross_check_block_height: bool,
+}
+
+struct Server {
+    url: Url,
+    connection: OnceCell<Connection>,
+    /// The number of failed requests since the last successful one.
+    failures: AtomicU32,
+    subscriptions: Mutex<Subscriptions>,
+}
+
+struct Connection {
+    electrum: bdk::electrum_client::Client,
+    blockchain: AnyBlockchain,
+}
+
+/// What we subscribed to on a server.
+#[derive(Default)]
+struct Subscriptions {
+    /// The height of the latest header we were notified about.
+    block_height: Option<BlockHeight>,
+    scripts: HashSet<Script>,
+    /// Scripts we were notified about that were not reported as changed yet.
+    notified: HashSet<Script>,
+    renewed_at: Option<Instant>,
+}
+
+impl Electrum {
+    pub fn new(config: &ElectrumConfig) -> Result<Self> {
+        let servers = config.urls.iter().map(Server::new).collect::<Vec<_>>();
+
+        let mut current = None;
+        for (index, server) in servers.iter().enumerate() {
+            match server.connection() {
+                Ok(_) => {
+                    current.get_or_insert(index);
+                }
+                Err(error) => {
+                    tracing::warn!(url = %server.url, "Failed to connect to Electrum server, will retry it later: {:#}", error);
+                    server.failures.store(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        let current = match current {
+            Some(current) => current,
+            None => bail!("Failed to connect to any of the Electrum servers"),
+        };
+
+        Ok(Self {
+            servers,
+            current: AtomicUsize::new(current),
+            cross_check_block_height: config.cross_check_block_height,
+        })
+    }
+
+    /// Runs the request against the current server, falling back to the
+    /// other servers on errors.
+    fn with_failover<T>(&self, request: impl Fn(&Server) -> Result<T>) -> Result<T> {
+        let failures = self
+            .servers
+            .iter()
+            .map(|server| server.failures.load(Ordering::Relaxed))
+            .collect::<Vec<_>>();
+        let mut last_error = None;
+
+        for index in failover_order(self.current.load(Ordering::Relaxed), &failures) {
+            let server = &self.servers[index];
+
+            match request(server) {
+                Ok(value) => {
+                    server.succeeded();
+                    self.current.store(index, Ordering::Relaxed);
+                    return Ok(value);
+                }
+                Err(error) => {
+                    server.failed(&error);
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        Err(last_error.expect("at least one server"))
+    }
+
+    /// Asks all servers for their block height and ignores those that are too
+    /// far off the median. Outvoting a lying server needs at least three
+    /// servers.
+    fn cross_checked_block_height(&self) -> Result<BlockHeight> {
+        let mut heights = Vec::new();
+        for (index, server) in self.servers.iter().enumerate() {
+            match server.block_height() {
+                Ok(height) => {
+                    server.succeeded();
+                    heights.push((index, u32::from(height)));
+                }
+                Err(error) => server.failed(&error),
+            }
+        }
+
+        let outliers = block_height_outliers(&heights);
+        for (index, height) in heights.iter().filter(|(index, _)| outliers.contains(index)) {
+            let server = &self.servers[*index];
+            tracing::warn!(
+                url = %server.url,
+                block_height = height,
+                "Electrum server is lagging behind or ahead of the other servers, ignoring it"
+            );
+            server.failures.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let current = self.current.load(Ordering::Relaxed);
+        let (index, height) = heights
+            .iter()
+            .filter(|(index, _)| !outliers.contains(index))
+            .min_by_key(|(index, _)| (*index != current, *index))
+            .context("None of the Electrum servers reported a block height")?;
+        self.current.store(*index, Ordering::Relaxed);
+
+        Ok(BlockHeight::from(*height))
+    }
+}
+
+impl Server {
+    fn new(url: &Url) -> Self {
+        Self {
+            url: url.clone(),
+            connection: OnceCell::new(),
+            failures: AtomicU32::new(0),
+            subscriptions: Mutex::new(Subscriptions::default()),
+        }
+    }
+
+    /// Returns the connection to the server, connecting first if we have not
+    /// managed to do so yet.
+    fn connection(&self) -> Result<&Connection> {
+        self.connection
+            .get_or_try_init(|| Connection::new(&self.url))
+    }
+
+    fn electrum(&self) -> Result<&bdk::electrum_client::Client> {
+        Ok(&self.connection()?.electrum)
+    }
+
+    fn blockchain(&self) -> Result<&AnyBlockchain> {
+        Ok(&self.connection()?.blockchain)
+    }
+
+    fn block_height(&self) -> Result<BlockHeight> {
+        // We do not act on this subscription after this call, as we cannot rely on
+        // subscription push notifications because eventually the Electrum server will
+        // close the connection and subscriptions are not automatically renewed
+        // upon renewing the connection.
+        let latest_block = self
+            .electrum()?
+            .block_headers_subscribe()
+            .context("Failed to subscribe to header notifications")?;
+
+        BlockHeight::try_from(latest_block)
+    }
+
+    /// Subscribes to headers and the given scripts and collects the
+    /// notifications received since the last call.
+    ///
+    /// Scripts that were not subscribed to on the current connection are
+    /// reported as changed, we may have missed notifications for them.
+    fn changes(&self, scripts: &[Script]) -> Result<Changes> {
+        let mut subscriptions = self
+            .subscriptions
+            .lock()
+            .expect("no other thread panicked while holding the lock");
+
+        let changes = self.collect_changes(&mut subscriptions, scripts);
+        if changes.is_err() {
+            // the connection may have been renewed in the meantime
+            subscriptions.renewed_at = None;
+        }
+
+        changes
+    }
+
+    fn collect_changes(
+        &self,
+        subscriptions: &mut Subscriptions,
+        scripts: &[Script],
+    ) -> Result<Changes> {
+        let renew = subscriptions.renewed_at.map_or(true, |renewed_at| {
+            renewed_at.elapsed() > RENEW_SUBSCRIPTIONS_INTERVAL
+        });
+        if renew {
+            subscriptions.block_height = Some(self.block_height()?);
+            subscriptions.renewed_at = Some(Instant::now());
+        }
+
+        for script in scripts {
+            if renew || !subscriptions.scripts.contains(script) {
+                if self.subscribe(script)? {
+                    subscriptions.notified.insert(script.clone());
+                }
+                subscriptions.scripts.insert(script.clone());
+            }
+        }
+
+        let unwatched = subscriptions
+            .scripts
+            .iter()
+            .filter(|script| !scripts.contains(script))
+            .cloned()
+            .collect::<Vec<_>>();
+        for script in unwatched {
+            // the server keeps sending notifications otherwise
+            let _ = self.electrum()?.script_unsubscribe(&script);
+            subscriptions.scripts.remove(&script);
+            subscriptions.notified.remove(&script);
+        }
+
+        self.pop_notifications(subscriptions)?;
+
+        Ok(Changes {
+            block_height: subscriptions
+                .block_height
+                .context("Not subscribed to headers")?,
+            scripts: subscriptions.notified.drain().collect(),
+        })
+    }
+
+    /// Reads the notifications received since the last call, returns whether
+    /// there were any.
+    fn notified(&self) -> Result<bool> {
+        let mut subscriptions = self
+            .subscriptions
+            .lock()
+            .expect("no other thread panicked while holding the lock");
+
+        let notified = self.pop_notifications(&mut subscriptions);
+        if notified.is_err() {
+            subscriptions.renewed_at = None;
+        }
+
+        notified
+    }
+
+    fn pop_notifications(&self, subscriptions: &mut Subscriptions) -> Result<bool> {
+        // Notifications are only read from the connection as part of a request.
+        self.electrum()?
+            .ping()
+            .context("Failed to ping Electrum server")?;
+
+        let mut notified = false;
+        while let Some(header) = self
+            .electrum()?
+            .block_headers_pop()
+            .context("Failed to read header notifications")?
+        {
+            subscriptions.block_height = Some(BlockHeight::try_from(header)?);
+            notified = true;
+        }
+
+        let scripts = subscriptions.scripts.iter().cloned().collect::<Vec<_>>();
+        for script in scripts {
+            match self.electrum()?.script_pop(&script) {
+                Ok(Some(_)) => {}
+                Ok(None) => continue,
+                Err(bdk::electrum_client::Error::NotSubscribed(_)) => {
+                    tracing::debug!(url = %self.url, "Lost subscriptions to Electrum server, renewing them");
+                    subscriptions.renewed_at = None;
+                    self.subscribe(&script)?;
+                }
+                Err(error) => {
+                    return Err(error).context("Failed to read script notifications");
+                }
+            }
+
+            subscriptions.notified.insert(script);
+            notified = true;
+        }
+
+        Ok(notified)
+    }
+
+    /// Subscribes to the script, returns false if we already were subscribed
+    /// on this connection.
+    fn subscribe(&self, script: &Script) -> Result<bool> {
+        match self.electrum()?.script_subscribe(script) {
+            Ok(_) => Ok(true),
+            Err(bdk::electrum_client::Error::AlreadySubscribed(_)) => Ok(false),
+            Err(error) => Err(error).context("Failed to subscribe to script notifications"),
+        }
+    }
+
+    fn succeeded(&self) {
+        let failures = self.failures.swap(0, Ordering::Relaxed);
+        if failures > 0 {
+            tracing::info!(url = %self.url, "Electrum server is back after {} failed requests", failures);
+        }
+    }
+
+    fn failed(&self, error: &anyhow::Error) {
+        let failures = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::warn!(url = %self.url, %failures, "Electrum server failed: {:#}", error);
+    }
+}
+
+impl Connection {
+    fn new(url: &Url) -> Result<Self> {
+        let config = bdk::electrum_client::ConfigBuilder::default()
+            .retry(5)
+            .build();
+        let electrum = bdk::electrum_client::Client::from_config(url.as_str(), config)
+            .context("Failed to initialize Electrum RPC client")?;
+
+        let client = bdk::electrum_client::Client::new(url.as_str())
+            .context("Failed to initialize Electrum RPC client")?;
+        let blockchain = AnyBlockchain::from(ElectrumBlockchain::from(client));
+
+        Ok(Self {
+            electrum,
+            blockchain,
+        })
+    }
+}
+
+impl ChainBackend for Electrum {
+    fn sync_with(&self, sync: &dyn Fn(&AnyBlockchain) -> Result<()>) -> Result<()> {
+        self.with_failover(|server| sync(server.blockchain()?))
+    }
+
+    fn broadcast(&self, transaction: &Transaction) -> Result<()> {
+        self.with_failover(|server| Ok(server.blockchain()?.broadcast(transaction)?))
+    }
+
+    fn block_height(&self) -> Result<BlockHeight> {
+        if self.cross_check_block_height && self.servers.len() > 1 {
+            return self.cross_checked_block_height();
+        }
+
+        self.with_failover(Server::block_height)
+    }
+
+    fn block_hash(&self, height: BlockHeight) -> Result<BlockHash> {
+        let height = usize::try_from(u32::from(height))?;
+
+        self.with_failover(|server| Ok(server.electrum()?.block_header(height)?.block_hash()))
+    }
+
+    fn script_history(&self, watched: &[(Txid, Script)]) -> Result<Vec<Vec<HistoryEntry>>> {
+        let histories = self.with_failover(|server| {
+            Ok(server
+                .electrum()?
+                .batch_script_get_history(watched.iter().map(|(_, script)| script))?)
+        })?;
+
+        if histories.len() != watched.len() {
+            bail!(
+                "Expected {} history entries, received {}",
+                watched.len(),
+                histories.len()
+            );
+        }
+
+        Ok(histories
+            .into_iter()
+            .map(|history| {
+                history
+                    .into_iter()
+                    .map(|entry| HistoryEntry {
+                        txid: entry.tx_hash,
+                        height: entry.height,
+                        block_hash: None,
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn changes(&self, scripts: &[Script]) -> Result<Changes> {
+        let mut changes = self.with_failover(|server| server.changes(scripts))?;
+
+        if self.cross_check_block_height && self.servers.len() > 1 {
+            changes.block_height = self.cross_checked_block_height()?;
+        }
+
+        Ok(changes)
+    }
+
+    fn pushes_notifications(&self) -> bool {
+        true
+    }
+
+    fn notified(&self) -> Result<bool> {
+        self.servers[self.current.load(Ordering::Relaxed)].notified()
+    }
+
+    fn estimate_fee_rate(&self, target_block: usize) -> Result<FeeRate> {
+        // https://github.com/romanz/electrs/blob/f9cf5386d1b5de6769ee271df5eef324aa9491bc/src/rpc.rs#L213
+        // Returned estimated fees are per BTC/kb.
+        let fee_per_byte =
+            self.with_failover(|server| Ok(server.electrum()?.estimate_fee(target_block)?))?;
+        // we do not expect fees being that high.
+        #[allow(clippy::cast_possible_truncation)]
+        Ok(FeeRate::from_btc_per_kvb(fee_per_byte as f32))
+    }
+
+    fn min_relay_fee(&self) -> Result<Amount> {
+        // https://github.com/romanz/electrs/blob/f9cf5386d1b5de6769ee271df5eef324aa9491bc/src/rpc.rs#L219
+        // Returned fee is in BTC/kb
+        let relay_fee = self.with_failover(|server| Ok(server.electrum()?.relay_fee()?))?;
+        Ok(Amount::from_btc(relay_fee)?)
+    }
+
+    fn get_tx(&self, txid: &Txid) -> Result<Option<Transaction>> {
+        self.with_failover(|server| Ok(server.blockchain()?.get_tx(txid)?))
+    }
+}
+
+/// The order in which to try the servers: those with fewer failures first,
+/// starting from the current server.
+fn failover_order(current: usize, failures: &[u32]) -> Vec<usize> {
+    let mut order = (0..failures.len())
+        .map(|offset| (current + offset) % failures.len())
+        .collect::<Vec<_>>();
+    order.sort_by_key(|index| failures[*index]);
+
+    order
+}
+
+/// Returns the servers whose block height is more than
+/// [`MAX_BLOCK_HEIGHT_DEVIATION`] blocks away from the median.
+fn block_height_outliers(heights: &[(usize, u32)]) -> Vec<usize> {
+    let mut sorted = heights
+        .iter()
+        .map(|(_, height)| *height)
+        .collect::<Vec<_>>();
+    sorted.sort_unstable();
+
+    let median = match sorted.get(sorted.len() / 2) {
+        Some(median) => *median,
+        None => return vec![],
+    };
+
+    heights
+        .iter()
+        .filter(|(_, height)| height.abs_diff(median) > MAX_BLOCK_HEIGHT_DEVIATION)
+        .map(|(index, _)| *index)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tries_servers_with_fewest_failures_first() {
+        assert_eq!(failover_order(0, &[0, 0, 0]), vec![0, 1, 2]);
+        assert_eq!(failover_order(2, &[0, 0, 0]), vec![2, 0, 1]);
+        assert_eq!(failover_order(0, &[3, 0, 1]), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn detects_lagging_and_lying_servers() {
+        assert_eq!(
+            block_height_outliers(&[(0, 100), (1, 101), (2, 100)]),
+            Vec::<usize>::new()
+        );
+        assert_eq!(
+            block_height_outliers(&[(0, 90), (1, 100), (2, 100)]),
+            vec![0]
+        );
+        assert_eq!(
+            block_height_outliers(&[(0, 100), (1, 5000), (2, 101)]),
+            vec![1]
+        );
+        assert!(block_height_outliers(&[]).is_empty());
+    }
+}
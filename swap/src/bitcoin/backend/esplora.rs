@@ -0,0 +1,324 @@
+use crate::bitcoin::backend::{ChainBackend, EsploraConfig, HistoryEntry};
+use crate::bitcoin::timelocks::BlockHeight;
+use crate::bitcoin::{Amount, Transaction};
+use ::bitcoin::{BlockHash, Script, Txid};
+use anyhow::{Context, Result};
+use bdk::blockchain::{AnyBlockchain, EsploraBlockchain};
+use bdk::esplora_client::{BlockingClient, Builder, Tx};
+use bdk::FeeRate;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// How many unused addresses bdk looks ahead when syncing the wallet.
+const STOP_GAP: usize = 20;
+const TIMEOUT_SECS: u64 = 30;
+/// Esplora returns the confirmed transactions of a script in pages of this
+/// size.
+const CONFIRMED_TXS_PER_PAGE: usize = 25;
+
+/// Esplora does not tell us the minimum relay fee of its node, unless it is
+/// configured assume the default of Bitcoin Core, 1 sat/vB.
+const DEFAULT_MIN_RELAY_FEE_SATS_PER_KVB: u64 = 1_000;
+
+/// Talks to the HTTP API of an Esplora instance, e.g. blockstream.info.
+pub struct Esplora {
+    client: BlockingClient,
+    blockchain: AnyBlockchain,
+    min_relay_fee: Amount,
+}
+
+impl Esplora {
+    pub fn new(config: &EsploraConfig) -> Result<Self> {
+        let client = config
+            .client()
+            .context("Failed to initialize Esplora client")?;
+        let blockchain = EsploraBlockchain::from_client(
+            config
+                .client()
+                .context("Failed to initialize Esplora client")?,
+            STOP_GAP,
+        );
+
+        Ok(Self {
+            client,
+            blockchain: AnyBlockchain::from(blockchain),
+            min_relay_fee: Amount::from_sat(
+                config
+                    .min_relay_fee_sat_per_kvb
+                    .unwrap_or(DEFAULT_MIN_RELAY_FEE_SATS_PER_KVB),
+            ),
+        })
+    }
+}
+
+impl Esplora {
+    /// Returns all transactions of the script, the first page includes the
+    /// unconfirmed ones and the following pages continue after the last
+    /// confirmed transaction seen.
+    fn script_txs(&self, script: &Script) -> Result<Vec<Tx>> {
+        let mut txs = self.client.scripthash_txs(script, None)?;
+        let mut confirmed_in_page = txs.iter().filter(|tx| tx.status.confirmed).count();
+
+        while confirmed_in_page >= CONFIRMED_TXS_PER_PAGE {
+            let last_seen = txs.last().map(|tx| tx.txid);
+            let page = self.client.scripthash_txs(script, last_seen)?;
+            confirmed_in_page = page.len();
+            txs.extend(page);
+        }
+
+        Ok(txs)
+    }
+}
+
+impl EsploraConfig {
+    fn client(&self) -> Result<BlockingClient> {
+        let mut builder =
+            Builder::new(self.url.as_str().trim_end_matches('/')).timeout(TIMEOUT_SECS);
+        if let Some(proxy) = &self.socks5_proxy {
+            // resolve host names through the proxy, onion addresses cannot be resolved locally
+            builder = builder.proxy(&format!("socks5h://{}", proxy));
+        }
+
+        Ok(builder.build_blocking()?)
+    }
+}
+
+impl ChainBackend for Esplora {
+    fn sync_with(&self, sync: &dyn Fn(&AnyBlockchain) -> Result<()>) -> Result<()> {
+        sync(&self.blockchain)
+    }
+
+    fn broadcast(&self, transaction: &Transaction) -> Result<()> {
+        self.client.broadcast(transaction)?;
+
+        Ok(())
+    }
+
+    fn block_height(&self) -> Result<BlockHeight> {
+        Ok(BlockHeight::from(self.client.get_height()?))
+    }
+
+    fn block_hash(&self, height: BlockHeight) -> Result<BlockHash> {
+        Ok(self.client.get_block_hash(u32::from(height))?)
+    }
+
+    fn script_history(&self, watched: &[(Txid, Script)]) -> Result<Vec<Vec<HistoryEntry>>> {
+        let mut histories = Vec::with_capacity(watched.len());
+
+        for (_, script) in watched {
+            let mut history = Vec::new();
+
+            for tx in self.script_txs(script)? {
+                let (height, block_hash) = match tx.status.block_height {
+                    Some(height) if tx.status.confirmed => {
+                        (i32::try_from(height)?, tx.status.block_hash)
+                    }
+                    _ => (0, None),
+                };
+
+                history.push(HistoryEntry {
+                    txid: tx.txid,
+                    height,
+                    block_hash,
+                });
+            }
+
+            histories.push(history);
+        }
+
+        Ok(histories)
+    }
+
+    fn estimate_fee_rate(&self, target_block: usize) -> Result<FeeRate> {
+        let estimates = self.client.get_fee_estimates()?;
+
+        fee_rate_for_target(target_block, &estimates)
+    }
+
+    fn min_relay_fee(&self) -> Result<Amount> {
+        Ok(self.min_relay_fee)
+    }
+
+    fn get_tx(&self, txid: &Txid) -> Result<Option<Transaction>> {
+        Ok(self.client.get_tx(txid)?)
+    }
+}
+
+/// Picks the estimate for the largest confirmation target that does not
+/// exceed `target_block`, or the fastest one if `target_block` is below all
+/// of them.
+///
+/// Esplora returns estimates in sat/vB, keyed by the confirmation target.
+fn fee_rate_for_target(target_block: usize, estimates: &HashMap<String, f64>) -> Result<FeeRate> {
+    let mut estimates = estimates
+        .iter()
+        .map(|(target, sats_per_vb)| Ok((target.parse::<usize>()?, *sats_per_vb)))
+        .collect::<Result<Vec<_>>>()
+        .context("Invalid fee estimate")?;
+    estimates.sort_by_key(|(target, _)| *target);
+
+    let (_, sats_per_vb) = estimates
+        .iter()
+        .rev()
+        .find(|(target, _)| *target <= target_block)
+        .or_else(|| estimates.first())
+        .context("Esplora returned no fee estimates")?;
+
+    // we do not expect fees being that high.
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(FeeRate::from_sat_per_vb(*sats_per_vb as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::bitcoin::hashes::Hash;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serves canned responses of the Esplora API, keyed by a part of the path,
+    /// the first matching one is used.
+    fn esplora_stand_in(responses: Vec<(&'static str, String)>) -> EsploraConfig {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // read the headers, closing the connection with unread data resets it
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+
+                let (status, body) = responses
+                    .iter()
+                    .find(|(pattern, _)| path.contains(pattern))
+                    .map_or(("404 Not Found", ""), |(_, body)| ("200 OK", body.as_str()));
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+
+        EsploraConfig {
+            url: url.parse().unwrap(),
+            socks5_proxy: None,
+            min_relay_fee_sat_per_kvb: None,
+        }
+    }
+
+    #[test]
+    fn reads_block_height_fees_and_script_history() {
+        let txid = "ea030832ea030832ea030832ea030832ea030832ea030832ea030832ea030832";
+        let unconfirmed_txid = "0bc0ffee0bc0ffee0bc0ffee0bc0ffee0bc0ffee0bc0ffee0bc0ffee0bc0ffee";
+        let history = format!(
+            r#"[
+                {{"txid":"{}","version":2,"locktime":0,"vin":[],"vout":[],"size":0,"weight":0,"fee":0,"status":{{"confirmed":false}}}},
+                {{"txid":"{}","version":2,"locktime":0,"vin":[],"vout":[],"size":0,"weight":0,"fee":0,"status":{{"confirmed":true,"block_height":120,"block_hash":null,"block_time":null}}}}
+            ]"#,
+            unconfirmed_txid, txid
+        );
+        let config = esplora_stand_in(vec![
+            ("/blocks/tip/height", "123".to_owned()),
+            (
+                "/fee-estimates",
+                r#"{"1":20.5,"3":10.0,"6":5.0}"#.to_owned(),
+            ),
+            ("/scripthash/", history),
+        ]);
+
+        let esplora = Esplora::new(&config).unwrap();
+
+        assert_eq!(esplora.block_height().unwrap(), BlockHeight::from(123));
+        assert_eq!(
+            esplora.estimate_fee_rate(4).unwrap(),
+            FeeRate::from_sat_per_vb(10.0)
+        );
+        assert_eq!(
+            esplora
+                .script_history(&[(txid.parse().unwrap(), Script::new())])
+                .unwrap(),
+            vec![vec![
+                HistoryEntry {
+                    txid: unconfirmed_txid.parse().unwrap(),
+                    height: 0,
+                    block_hash: None,
+                },
+                HistoryEntry {
+                    txid: txid.parse().unwrap(),
+                    height: 120,
+                    block_hash: None,
+                }
+            ]]
+        );
+        assert_eq!(esplora.get_tx(&txid.parse().unwrap()).unwrap(), None);
+        assert_eq!(esplora.min_relay_fee().unwrap(), Amount::from_sat(1_000));
+    }
+
+    #[test]
+    fn uses_configured_min_relay_fee() {
+        let config = EsploraConfig {
+            min_relay_fee_sat_per_kvb: Some(100),
+            ..esplora_stand_in(vec![])
+        };
+
+        let esplora = Esplora::new(&config).unwrap();
+
+        assert_eq!(esplora.min_relay_fee().unwrap(), Amount::from_sat(100));
+    }
+
+    #[test]
+    fn pages_through_confirmed_script_history() {
+        let tx = |n: usize| {
+            format!(
+                r#"{{"txid":"{:064x}","version":2,"locktime":0,"vin":[],"vout":[],"size":0,"weight":0,"fee":0,"status":{{"confirmed":true,"block_height":{},"block_hash":null,"block_time":null}}}}"#,
+                n, n
+            )
+        };
+        let first_page = (1..=CONFIRMED_TXS_PER_PAGE)
+            .rev()
+            .map(tx)
+            .collect::<Vec<_>>();
+        let config = esplora_stand_in(vec![
+            ("/txs/chain/", format!("[{}]", tx(0))),
+            ("/scripthash/", format!("[{}]", first_page.join(","))),
+        ]);
+
+        let esplora = Esplora::new(&config).unwrap();
+        let history = esplora
+            .script_history(&[(Txid::all_zeros(), Script::new())])
+            .unwrap()
+            .pop()
+            .unwrap();
+
+        assert_eq!(history.len(), CONFIRMED_TXS_PER_PAGE + 1);
+        assert_eq!(history.last().unwrap().txid, Txid::all_zeros());
+    }
+
+    #[test]
+    fn fee_rate_falls_back_to_fastest_target() {
+        let estimates = HashMap::from([("2".to_owned(), 30.0), ("6".to_owned(), 8.0)]);
+
+        assert_eq!(
+            fee_rate_for_target(1, &estimates).unwrap(),
+            FeeRate::from_sat_per_vb(30.0)
+        );
+        assert_eq!(
+            fee_rate_for_target(144, &estimates).unwrap(),
+            FeeRate::from_sat_per_vb(8.0)
+        );
+        assert!(fee_rate_for_target(1, &HashMap::new()).is_err());
+    }
+}
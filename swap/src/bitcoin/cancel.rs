@@ -30,6 +30,12 @@ impl CancelTimelock {
     }
 }
 
+impl From<CancelTimelock> for u32 {
+    fn from(timelock: CancelTimelock) -> Self {
+        timelock.0
+    }
+}
+
 impl Add<CancelTimelock> for BlockHeight {
     type Output = BlockHeight;
 
@@ -70,6 +76,12 @@ impl PunishTimelock {
     }
 }
 
+impl From<PunishTimelock> for u32 {
+    fn from(timelock: PunishTimelock) -> Self {
+        timelock.0
+    }
+}
+
 impl Add<PunishTimelock> for BlockHeight {
     type Output = BlockHeight;
 
@@ -90,6 +102,12 @@ impl PartialEq<PunishTimelock> for u32 {
     }
 }
 
+impl fmt::Display for PunishTimelock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} blocks", self.0)
+    }
+}
+
 #[derive(Debug)]
 pub struct TxCancel {
     inner: Transaction,
@@ -0,0 +1,711 @@
+use crate::bitcoin::backend::ChainBackend;
+use crate::bitcoin::wallet::EstimateFeeRate;
+use crate::bitcoin::{parse_fee_rate, to_fee_rate, Amount};
+use anyhow::{bail, Context, Result};
+use bdk::FeeRate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::runtime::RuntimeFlavor;
+use url::Url;
+
+/// Assume the default of Bitcoin Core if none of the sources knows the
+/// minimum relay fee.
+const DEFAULT_MIN_RELAY_FEE_SATS_PER_KVB: u64 = 1_000;
+
+const HTTP_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+/// Estimates of an HTTP API that could not be refreshed for this long are
+/// not used anymore.
+const HTTP_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+/// Fee commands that take longer are killed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the wallet gets fee rate estimates from and how they are combined.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeeEstimation {
+    pub combine: Combine,
+    /// The lowest fee rate in sat/vB to estimate, also used if none of the
+    /// sources returns an estimate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub floor_sat_per_vb: Option<Decimal>,
+    // Last, TOML cannot serialize values after an array of tables.
+    pub sources: Vec<FeeSource>,
+}
+
+impl Default for FeeEstimation {
+    fn default() -> Self {
+        Self {
+            combine: Combine::Max,
+            floor_sat_per_vb: None,
+            sources: vec![FeeSource::Backend],
+        }
+    }
+}
+
+impl FeeEstimation {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Fetches the fees of HTTP sources without a proxy of their own through
+    /// the given SOCKS5 proxy.
+    pub fn with_socks5_proxy(mut self, proxy: Option<String>) -> Self {
+        for source in &mut self.sources {
+            if let FeeSource::Http { socks5_proxy, .. } = source {
+                if socks5_proxy.is_none() {
+                    *socks5_proxy = proxy.clone();
+                }
+            }
+        }
+
+        self
+    }
+}
+
+/// A source of fee rate estimates.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeeSource {
+    /// The Electrum server, Bitcoin Core or Esplora the wallet uses.
+    Backend,
+    /// An HTTP API returning fees like `/api/v1/fees/recommended` of
+    /// mempool.space.
+    Http {
+        url: Url,
+        /// A SOCKS5 proxy to connect through, e.g. `127.0.0.1:9050` for Tor.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        socks5_proxy: Option<String>,
+    },
+    /// A file containing a fee rate in sat/vB.
+    File { path: PathBuf },
+    /// A command printing a fee rate in sat/vB. The confirmation target is
+    /// passed after the given arguments.
+    Command {
+        program: PathBuf,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
+    },
+}
+
+impl fmt::Display for FeeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeeSource::Backend => write!(f, "backend"),
+            FeeSource::Http { url, .. } => write!(f, "http:{}", url),
+            FeeSource::File { path } => write!(f, "file:{}", path.display()),
+            FeeSource::Command { program, args } => {
+                write!(f, "command:{}", program.display())?;
+                for arg in args {
+                    write!(f, " {}", arg)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Parses a fee source given as `backend`, `http:<url>`, `file:<path>` or
+/// `command:<program>`.
+impl FromStr for FeeSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.split_once(':') {
+            None if s == "backend" => FeeSource::Backend,
+            Some(("http", url)) => FeeSource::Http {
+                url: Url::parse(url).with_context(|| format!("Invalid fee API URL {}", url))?,
+                socks5_proxy: None,
+            },
+            Some(("file", path)) => FeeSource::File {
+                path: PathBuf::from(path),
+            },
+            Some(("command", program)) if !program.trim().is_empty() => FeeSource::Command {
+                program: PathBuf::from(program),
+                args: vec![],
+            },
+            _ => bail!(
+                "Unknown fee source {}, expected backend, http:<url>, file:<path> or command:<program>",
+                s
+            ),
+        })
+    }
+}
+
+/// How the estimates of several sources are combined.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Combine {
+    /// The highest estimate, which gets transactions confirmed even if some
+    /// sources underestimate.
+    Max,
+    /// The median of the estimates, which ignores outliers in both directions.
+    Median,
+}
+
+impl Combine {
+    fn apply(self, mut sats_per_vb: Vec<f32>) -> Option<f32> {
+        sats_per_vb.sort_by(f32::total_cmp);
+
+        match self {
+            Combine::Max => sats_per_vb.last().copied(),
+            Combine::Median if sats_per_vb.is_empty() => None,
+            Combine::Median if sats_per_vb.len() % 2 == 0 => {
+                let upper = sats_per_vb.len() / 2;
+                Some((sats_per_vb[upper - 1] + sats_per_vb[upper]) / 2.0)
+            }
+            Combine::Median => Some(sats_per_vb[sats_per_vb.len() / 2]),
+        }
+    }
+}
+
+impl FromStr for Combine {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "max" => Ok(Combine::Max),
+            "median" => Ok(Combine::Median),
+            _ => bail!(
+                "Unknown way to combine fee estimates {}, expected max or median",
+                s
+            ),
+        }
+    }
+}
+
+/// Combines the fee rate estimates of several sources.
+///
+/// Sources that fail or return an estimate that is not positive, like
+/// Electrum servers without enough data, are skipped.
+pub struct FeeEstimator {
+    sources: Vec<(String, Box<dyn EstimateFeeRate + Send + Sync>)>,
+    combine: Combine,
+    floor: Option<FeeRate>,
+}
+
+impl FeeEstimator {
+    /// Creates the estimators of the sources, HTTP APIs are fetched once
+    /// before returning.
+    pub async fn new(config: &FeeEstimation, backend: Arc<dyn ChainBackend>) -> Result<Self> {
+        if config.sources.is_empty() {
+            bail!("At least one fee source has to be configured")
+        }
+
+        let mut sources = Vec::with_capacity(config.sources.len());
+        for source in &config.sources {
+            let estimator: Box<dyn EstimateFeeRate + Send + Sync> = match source {
+                FeeSource::Backend => Box::new(BackendFeeRate(backend.clone())),
+                FeeSource::Http { url, socks5_proxy } => {
+                    Box::new(HttpFeeRate::start(url.clone(), socks5_proxy.as_deref()).await?)
+                }
+                FeeSource::File { path } => Box::new(FileFeeRate(path.clone())),
+                FeeSource::Command { program, args } => {
+                    Box::new(CommandFeeRate::new(program.clone(), args.clone()))
+                }
+            };
+
+            sources.push((source.to_string(), estimator));
+        }
+        let floor = config.floor_sat_per_vb.map(to_fee_rate).transpose()?;
+
+        Ok(Self::from_sources(sources, config.combine, floor))
+    }
+
+    fn from_sources(
+        sources: Vec<(String, Box<dyn EstimateFeeRate + Send + Sync>)>,
+        combine: Combine,
+        floor: Option<FeeRate>,
+    ) -> Self {
+        Self {
+            sources,
+            combine,
+            floor,
+        }
+    }
+}
+
+impl EstimateFeeRate for FeeEstimator {
+    fn estimate_feerate(&self, target_block: usize) -> Result<FeeRate> {
+        let mut estimates = Vec::with_capacity(self.sources.len());
+
+        for (source, estimator) in &self.sources {
+            match estimator.estimate_feerate(target_block) {
+                Ok(fee_rate) if fee_rate.as_sat_per_vb() > 0.0 => {
+                    estimates.push(fee_rate.as_sat_per_vb())
+                }
+                Ok(fee_rate) => tracing::debug!(
+                    %source,
+                    sats_per_vb = %fee_rate.as_sat_per_vb(),
+                    "Ignoring fee rate estimate that is not positive"
+                ),
+                Err(error) => {
+                    tracing::warn!(%source, "Failed to estimate fee rate: {:#}", error)
+                }
+            }
+        }
+
+        match (self.combine.apply(estimates), self.floor) {
+            (Some(sats_per_vb), Some(floor)) => Ok(FeeRate::from_sat_per_vb(
+                sats_per_vb.max(floor.as_sat_per_vb()),
+            )),
+            (Some(sats_per_vb), None) => Ok(FeeRate::from_sat_per_vb(sats_per_vb)),
+            (None, Some(floor)) => {
+                tracing::warn!(
+                    sats_per_vb = %floor.as_sat_per_vb(),
+                    "None of the fee sources returned an estimate, using the floor"
+                );
+                Ok(floor)
+            }
+            (None, None) => bail!("None of the fee sources returned an estimate"),
+        }
+    }
+
+    /// The highest minimum relay fee known to the sources.
+    fn min_relay_fee(&self) -> Result<Amount> {
+        let min_relay_fee = self
+            .sources
+            .iter()
+            .filter_map(|(_, estimator)| estimator.min_relay_fee().ok())
+            .max()
+            .unwrap_or_else(|| Amount::from_sat(DEFAULT_MIN_RELAY_FEE_SATS_PER_KVB));
+
+        Ok(min_relay_fee)
+    }
+}
+
+struct BackendFeeRate(Arc<dyn ChainBackend>);
+
+impl EstimateFeeRate for BackendFeeRate {
+    fn estimate_feerate(&self, target_block: usize) -> Result<FeeRate> {
+        self.0.estimate_fee_rate(target_block)
+    }
+
+    fn min_relay_fee(&self) -> Result<Amount> {
+        self.0.min_relay_fee()
+    }
+}
+
+/// Polls an HTTP fee API in the background, estimating is synchronous.
+struct HttpFeeRate {
+    url: Url,
+    latest: Arc<Mutex<Option<(Instant, RecommendedFees)>>>,
+}
+
+impl HttpFeeRate {
+    /// Fetches the fees once and keeps polling them in the background.
+    ///
+    /// If the first fetch fails the source is skipped until a later poll
+    /// succeeds.
+    async fn start(url: Url, socks5_proxy: Option<&str>) -> Result<Self> {
+        let mut builder = reqwest::Client::builder().timeout(HTTP_TIMEOUT);
+        if let Some(proxy) = socks5_proxy {
+            let proxy = reqwest::Proxy::all(format!("socks5h://{}", proxy).as_str())
+                .with_context(|| format!("Invalid SOCKS5 proxy {}", proxy))?;
+            builder = builder.proxy(proxy);
+        }
+        let client = builder.build()?;
+
+        let fetched = match fetch_recommended_fees(&client, &url).await {
+            Ok(fees) => Some((Instant::now(), fees)),
+            Err(error) => {
+                tracing::warn!(%url, "Failed to fetch fees: {:#}", error);
+                None
+            }
+        };
+        let latest = Arc::new(Mutex::new(fetched));
+        tokio::spawn(poll_fee_api(client, url.clone(), Arc::downgrade(&latest)));
+
+        Ok(Self { url, latest })
+    }
+
+    fn recommended_fees(&self) -> Result<RecommendedFees> {
+        match *self.latest.lock().expect("lock not to be poisoned") {
+            Some((fetched_at, fees)) if fetched_at.elapsed() < HTTP_MAX_AGE => Ok(fees),
+            Some(_) => bail!("Fees of {} are outdated", self.url),
+            None => bail!("Fees of {} were not fetched yet", self.url),
+        }
+    }
+}
+
+impl EstimateFeeRate for HttpFeeRate {
+    fn estimate_feerate(&self, target_block: usize) -> Result<FeeRate> {
+        let sats_per_vb = self.recommended_fees()?.for_target(target_block);
+
+        Ok(FeeRate::from_sat_per_vb(sats_per_vb))
+    }
+
+    fn min_relay_fee(&self) -> Result<Amount> {
+        let sats_per_vb = self.recommended_fees()?.minimum_fee;
+
+        // we do not expect fees being that high.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Ok(Amount::from_sat((sats_per_vb * 1_000.0) as u64))
+    }
+}
+
+async fn poll_fee_api(
+    client: reqwest::Client,
+    url: Url,
+    latest: Weak<Mutex<Option<(Instant, RecommendedFees)>>>,
+) {
+    let mut interval = tokio::time::interval_at(
+        tokio::time::Instant::now() + HTTP_REFRESH_INTERVAL,
+        HTTP_REFRESH_INTERVAL,
+    );
+
+    loop {
+        interval.tick().await;
+
+        let latest = match latest.upgrade() {
+            Some(latest) => latest,
+            None => return,
+        };
+
+        match fetch_recommended_fees(&client, &url).await {
+            Ok(fees) => {
+                *latest.lock().expect("lock not to be poisoned") = Some((Instant::now(), fees))
+            }
+            Err(error) => tracing::warn!(%url, "Failed to fetch fees: {:#}", error),
+        }
+    }
+}
+
+async fn fetch_recommended_fees(client: &reqwest::Client, url: &Url) -> Result<RecommendedFees> {
+    let body = client
+        .get(url.clone())
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    serde_json::from_str(&body).context("Unexpected response of fee API")
+}
+
+/// The fee rates in sat/vB recommended by mempool.space and compatible APIs.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct RecommendedFees {
+    fastest_fee: f32,
+    half_hour_fee: f32,
+    hour_fee: f32,
+    economy_fee: Option<f32>,
+    minimum_fee: f32,
+}
+
+impl RecommendedFees {
+    /// Picks the fee rate for the confirmation target, assuming a block every
+    /// ten minutes.
+    fn for_target(&self, target_block: usize) -> f32 {
+        match target_block {
+            0 | 1 => self.fastest_fee,
+            2 | 3 => self.half_hour_fee,
+            4..=6 => self.hour_fee,
+            _ => self.economy_fee.unwrap_or(self.hour_fee),
+        }
+    }
+}
+
+struct FileFeeRate(PathBuf);
+
+impl EstimateFeeRate for FileFeeRate {
+    fn estimate_feerate(&self, _target_block: usize) -> Result<FeeRate> {
+        let content = std::fs::read_to_string(&self.0)
+            .with_context(|| format!("Failed to read fee rate from {}", self.0.display()))?;
+
+        to_fee_rate(parse_fee_rate(content.trim())?)
+    }
+
+    fn min_relay_fee(&self) -> Result<Amount> {
+        bail!("A fee file does not know the minimum relay fee")
+    }
+}
+
+struct CommandFeeRate {
+    program: PathBuf,
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+impl CommandFeeRate {
+    fn new(program: PathBuf, args: Vec<String>) -> Self {
+        Self {
+            program,
+            args,
+            timeout: COMMAND_TIMEOUT,
+        }
+    }
+
+    /// Runs the command and returns what it printed, killing it if it does
+    /// not finish in time.
+    ///
+    /// Estimating is synchronous, the other tasks of the runtime are moved to
+    /// other threads while waiting for the command.
+    fn run(&self, target_block: usize) -> Result<String> {
+        in_place(|| {
+            let mut child = std::process::Command::new(&self.program)
+                .args(&self.args)
+                .arg(target_block.to_string())
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .spawn()
+                .with_context(|| format!("Failed to run fee command {}", self.program.display()))?;
+
+            let started = Instant::now();
+            let status = loop {
+                if let Some(status) = child.try_wait()? {
+                    break status;
+                }
+                if started.elapsed() > self.timeout {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    bail!(
+                        "Fee command {} did not finish within {} seconds",
+                        self.program.display(),
+                        self.timeout.as_secs_f32()
+                    )
+                }
+
+                std::thread::sleep(Duration::from_millis(10));
+            };
+            if !status.success() {
+                bail!(
+                    "Fee command {} failed with {}",
+                    self.program.display(),
+                    status
+                )
+            }
+
+            let mut stdout = String::new();
+            child
+                .stdout
+                .take()
+                .context("Output of fee command is not captured")?
+                .read_to_string(&mut stdout)
+                .context("Fee command printed no text")?;
+
+            Ok(stdout)
+        })
+    }
+}
+
+impl EstimateFeeRate for CommandFeeRate {
+    fn estimate_feerate(&self, target_block: usize) -> Result<FeeRate> {
+        let stdout = self.run(target_block)?;
+
+        to_fee_rate(parse_fee_rate(stdout.trim())?)
+    }
+
+    fn min_relay_fee(&self) -> Result<Amount> {
+        bail!("A fee command does not know the minimum relay fee")
+    }
+}
+
+/// Runs blocking work without stalling the other tasks of a multi-threaded
+/// runtime.
+fn in_place<T>(f: impl FnOnce() -> T) -> T {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::wallet::StaticFeeRate;
+    use std::io::Write;
+
+    struct NoEstimate;
+
+    impl EstimateFeeRate for NoEstimate {
+        fn estimate_feerate(&self, _: usize) -> Result<FeeRate> {
+            bail!("no estimate")
+        }
+
+        fn min_relay_fee(&self) -> Result<Amount> {
+            bail!("no min relay fee")
+        }
+    }
+
+    fn source(
+        name: &str,
+        estimator: impl EstimateFeeRate + Send + Sync + 'static,
+    ) -> (String, Box<dyn EstimateFeeRate + Send + Sync>) {
+        (name.to_owned(), Box::new(estimator))
+    }
+
+    fn estimator(
+        sats_per_vb: &[f32],
+        combine: Combine,
+        floor_sats_per_vb: Option<f32>,
+    ) -> FeeEstimator {
+        let mut sources = vec![source("failing", NoEstimate)];
+        for sats_per_vb in sats_per_vb {
+            sources.push(source(
+                &sats_per_vb.to_string(),
+                StaticFeeRate::new(*sats_per_vb, 1_000),
+            ));
+        }
+
+        FeeEstimator::from_sources(
+            sources,
+            combine,
+            floor_sats_per_vb.map(FeeRate::from_sat_per_vb),
+        )
+    }
+
+    #[test]
+    fn combines_estimates_of_working_sources() {
+        let sats_per_vb = [2.0, 10.0, 4.0, -1.0];
+
+        assert_eq!(
+            estimator(&sats_per_vb, Combine::Max, None)
+                .estimate_feerate(1)
+                .unwrap(),
+            FeeRate::from_sat_per_vb(10.0)
+        );
+        assert_eq!(
+            estimator(&sats_per_vb, Combine::Median, None)
+                .estimate_feerate(1)
+                .unwrap(),
+            FeeRate::from_sat_per_vb(4.0)
+        );
+        assert_eq!(
+            estimator(&[2.0, 10.0, 4.0, 6.0], Combine::Median, None)
+                .estimate_feerate(1)
+                .unwrap(),
+            FeeRate::from_sat_per_vb(5.0)
+        );
+    }
+
+    #[test]
+    fn falls_back_to_floor() {
+        assert_eq!(
+            estimator(&[], Combine::Max, Some(3.0))
+                .estimate_feerate(1)
+                .unwrap(),
+            FeeRate::from_sat_per_vb(3.0)
+        );
+        assert_eq!(
+            estimator(&[2.0], Combine::Max, Some(3.0))
+                .estimate_feerate(1)
+                .unwrap(),
+            FeeRate::from_sat_per_vb(3.0)
+        );
+        assert!(estimator(&[], Combine::Max, None)
+            .estimate_feerate(1)
+            .is_err());
+    }
+
+    #[test]
+    fn min_relay_fee_is_highest_known() {
+        let estimator = FeeEstimator::from_sources(
+            vec![
+                source("failing", NoEstimate),
+                source("low", StaticFeeRate::new(1.0, 1_000)),
+                source("high", StaticFeeRate::new(1.0, 5_000)),
+            ],
+            Combine::Max,
+            None,
+        );
+        assert_eq!(estimator.min_relay_fee().unwrap(), Amount::from_sat(5_000));
+
+        assert_eq!(
+            FeeEstimator::from_sources(vec![source("failing", NoEstimate)], Combine::Max, None)
+                .min_relay_fee()
+                .unwrap(),
+            Amount::from_sat(DEFAULT_MIN_RELAY_FEE_SATS_PER_KVB)
+        );
+    }
+
+    #[test]
+    fn parses_fee_sources() {
+        assert_eq!("backend".parse::<FeeSource>().unwrap(), FeeSource::Backend);
+        assert_eq!(
+            "http:https://mempool.space/api/v1/fees/recommended"
+                .parse::<FeeSource>()
+                .unwrap(),
+            FeeSource::Http {
+                url: "https://mempool.space/api/v1/fees/recommended"
+                    .parse()
+                    .unwrap(),
+                socks5_proxy: None,
+            }
+        );
+        assert_eq!(
+            "command:/opt/fee estimator/estimate"
+                .parse::<FeeSource>()
+                .unwrap(),
+            FeeSource::Command {
+                program: PathBuf::from("/opt/fee estimator/estimate"),
+                args: vec![],
+            }
+        );
+        assert!("mempool".parse::<FeeSource>().is_err());
+        assert!("command:".parse::<FeeSource>().is_err());
+    }
+
+    #[test]
+    fn picks_recommended_fee_for_target() {
+        let fees = serde_json::from_str::<RecommendedFees>(
+            r#"{"fastestFee":30,"halfHourFee":20,"hourFee":10,"economyFee":5,"minimumFee":1}"#,
+        )
+        .unwrap();
+
+        assert_eq!(fees.for_target(1), 30.0);
+        assert_eq!(fees.for_target(3), 20.0);
+        assert_eq!(fees.for_target(6), 10.0);
+        assert_eq!(fees.for_target(144), 5.0);
+    }
+
+    #[test]
+    fn reads_fee_rate_from_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "12.5").unwrap();
+
+        assert_eq!(
+            FileFeeRate(file.path().to_path_buf())
+                .estimate_feerate(1)
+                .unwrap(),
+            FeeRate::from_sat_per_vb(12.5)
+        );
+    }
+
+    #[test]
+    fn runs_fee_command_with_arguments() {
+        let estimator = CommandFeeRate::new(
+            PathBuf::from("sh"),
+            vec!["-c".to_owned(), "echo 12.5".to_owned(), "sh".to_owned()],
+        );
+
+        assert_eq!(
+            estimator.estimate_feerate(1).unwrap(),
+            FeeRate::from_sat_per_vb(12.5)
+        );
+    }
+
+    #[test]
+    fn kills_fee_command_that_does_not_finish() {
+        let estimator = CommandFeeRate {
+            program: PathBuf::from("sh"),
+            args: vec!["-c".to_owned(), "sleep 10".to_owned(), "sh".to_owned()],
+            timeout: Duration::from_millis(100),
+        };
+
+        let started = Instant::now();
+        assert!(estimator.estimate_feerate(1).is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
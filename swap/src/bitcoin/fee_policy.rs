@@ -0,0 +1,390 @@
+use crate::bitcoin::{parse_fee_rate, to_fee_rate, Amount};
+use anyhow::{bail, Context, Result};
+use bdk::FeeRate;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Assuming we add a spread of 3% we don't want to pay more than 3% of the
+/// amount for tx fees.
+const DEFAULT_MAX_RELATIVE_TX_FEE: Decimal = dec!(0.03);
+const DEFAULT_MAX_ABSOLUTE_TX_FEE: Amount = Amount::from_sat(100_000);
+
+/// The transactions of a swap that pay a fee, each has its own [`FeePolicy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxKind {
+    Lock,
+    Cancel,
+    Refund,
+    Redeem,
+    Punish,
+}
+
+impl TxKind {
+    const ALL: [TxKind; 5] = [
+        TxKind::Lock,
+        TxKind::Cancel,
+        TxKind::Refund,
+        TxKind::Redeem,
+        TxKind::Punish,
+    ];
+
+    /// The transactions whose fee the CLI estimates.
+    pub const CLI: [TxKind; 3] = [TxKind::Lock, TxKind::Cancel, TxKind::Refund];
+
+    /// The transactions whose fee the ASB estimates, withdrawals use the
+    /// policy of the lock transaction.
+    pub const ASB: [TxKind; 3] = [TxKind::Lock, TxKind::Redeem, TxKind::Punish];
+}
+
+impl fmt::Display for TxKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxKind::Lock => write!(f, "lock"),
+            TxKind::Cancel => write!(f, "cancel"),
+            TxKind::Refund => write!(f, "refund"),
+            TxKind::Redeem => write!(f, "redeem"),
+            TxKind::Punish => write!(f, "punish"),
+        }
+    }
+}
+
+impl FromStr for TxKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        TxKind::ALL
+            .into_iter()
+            .find(|kind| kind.to_string() == s)
+            .with_context(|| {
+                format!(
+                    "Unknown transaction {}, expected one of lock, cancel, refund, redeem or punish",
+                    s
+                )
+            })
+    }
+}
+
+/// Bounds on the fee paid for a kind of transaction.
+///
+/// The lowest fee rate, also used if it cannot be estimated, is the same for
+/// all transactions, see [`crate::bitcoin::FeeEstimation`].
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeePolicy {
+    /// Never pay a higher fee rate, in sat/vB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_sat_per_vb: Option<Decimal>,
+    /// A fee above this share of the transferred amount is limited to it, or
+    /// to `max_absolute_fee_sat` if it exceeds that as well.
+    pub max_relative_fee: Decimal,
+    /// The limit for a fee exceeding both this and `max_relative_fee`.
+    #[serde(
+        rename = "max_absolute_fee_sat",
+        with = "::bitcoin::util::amount::serde::as_sat"
+    )]
+    pub max_absolute_fee: Amount,
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        Self {
+            max_sat_per_vb: None,
+            max_relative_fee: DEFAULT_MAX_RELATIVE_TX_FEE,
+            max_absolute_fee: DEFAULT_MAX_ABSOLUTE_TX_FEE,
+        }
+    }
+}
+
+impl FeePolicy {
+    /// Returns the fee rate to pay given the outcome of estimating it, limited
+    /// to the maximum fee rate.
+    pub fn fee_rate(&self, kind: TxKind, estimate: Result<FeeRate>) -> Result<FeeRate> {
+        let fee_rate = estimate
+            .with_context(|| format!("Failed to estimate fee rate of {} transaction", kind))?;
+
+        let sats_per_vb =
+            Decimal::from_f32(fee_rate.as_sat_per_vb()).context("Failed to parse fee rate")?;
+
+        match self.max_sat_per_vb {
+            Some(max) if sats_per_vb > max => {
+                tracing::warn!(
+                    %kind,
+                    %sats_per_vb,
+                    max_sat_per_vb = %max,
+                    "Limiting fee rate to maximum"
+                );
+                to_fee_rate(max)
+            }
+            _ => Ok(fee_rate),
+        }
+    }
+}
+
+/// The fee policy of each kind of transaction.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeePolicies {
+    pub lock: FeePolicy,
+    pub cancel: FeePolicy,
+    pub refund: FeePolicy,
+    pub redeem: FeePolicy,
+    pub punish: FeePolicy,
+}
+
+impl FeePolicies {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn get(&self, kind: TxKind) -> &FeePolicy {
+        match kind {
+            TxKind::Lock => &self.lock,
+            TxKind::Cancel => &self.cancel,
+            TxKind::Refund => &self.refund,
+            TxKind::Redeem => &self.redeem,
+            TxKind::Punish => &self.punish,
+        }
+    }
+
+    fn get_mut(&mut self, kind: TxKind) -> &mut FeePolicy {
+        match kind {
+            TxKind::Lock => &mut self.lock,
+            TxKind::Cancel => &mut self.cancel,
+            TxKind::Refund => &mut self.refund,
+            TxKind::Redeem => &mut self.redeem,
+            TxKind::Punish => &mut self.punish,
+        }
+    }
+
+    /// Changes the settings given on the command line.
+    pub fn apply(&mut self, overrides: &[FeePolicyOverride]) {
+        for FeePolicyOverride { kind, settings } in overrides {
+            let policy = self.get_mut(*kind);
+
+            for setting in settings {
+                match *setting {
+                    FeePolicySetting::MaxSatPerVb(value) => policy.max_sat_per_vb = Some(value),
+                    FeePolicySetting::MaxRelativeFee(value) => policy.max_relative_fee = value,
+                    FeePolicySetting::MaxAbsoluteFee(value) => policy.max_absolute_fee = value,
+                }
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for kind in TxKind::ALL {
+            let policy = self.get(kind);
+
+            if matches!(policy.max_sat_per_vb, Some(max) if max < Decimal::ONE) {
+                bail!(
+                    "Maximum fee rate of {} transactions must be at least 1 sat/vB",
+                    kind
+                )
+            }
+            if policy.max_relative_fee <= Decimal::ZERO {
+                bail!(
+                    "Maximum relative fee of {} transactions must be positive",
+                    kind
+                )
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fails if a policy was changed for a kind of transaction other than the
+    /// given ones, it would silently have no effect.
+    pub fn ensure_only(&self, kinds: &[TxKind]) -> Result<()> {
+        let unused = TxKind::ALL
+            .into_iter()
+            .filter(|kind| !kinds.contains(kind))
+            .find(|kind| self.get(*kind) != &FeePolicy::default());
+
+        if let Some(kind) = unused {
+            bail!(
+                "The fee of {} transactions is never estimated here, only the fee policies of {} transactions are used",
+                kind,
+                kinds
+                    .iter()
+                    .map(|kind| kind.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+
+        Ok(())
+    }
+}
+
+/// Settings of the fee policy of a kind of transaction, given as
+/// `<transaction>:<setting>=<value>[,<setting>=<value>...]`, e.g.
+/// `refund:max_sat_per_vb=200,max_relative_fee=0.1`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeePolicyOverride {
+    pub kind: TxKind,
+    pub settings: Vec<FeePolicySetting>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeePolicySetting {
+    MaxSatPerVb(Decimal),
+    MaxRelativeFee(Decimal),
+    MaxAbsoluteFee(Amount),
+}
+
+impl FromStr for FeePolicyOverride {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, settings) = s
+            .split_once(':')
+            .context("Fee policy must be given as <transaction>:<setting>=<value>")?;
+
+        let settings = settings
+            .split(',')
+            .map(FeePolicySetting::from_str)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            kind: kind.parse()?,
+            settings,
+        })
+    }
+}
+
+impl FromStr for FeePolicySetting {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, value) = s.split_once('=').with_context(|| {
+            format!(
+                "Fee policy setting {} must be given as <setting>=<value>",
+                s
+            )
+        })?;
+
+        Ok(match name {
+            "max_sat_per_vb" => FeePolicySetting::MaxSatPerVb(parse_fee_rate(value)?),
+            "max_relative_fee" => FeePolicySetting::MaxRelativeFee(
+                Decimal::from_str(value).context("Maximum relative fee must be a number")?,
+            ),
+            "max_absolute_fee_sat" => FeePolicySetting::MaxAbsoluteFee(Amount::from_sat(
+                value
+                    .parse()
+                    .context("Maximum absolute fee must be a number of satoshis")?,
+            )),
+            _ => bail!(
+                "Unknown fee policy setting {}, expected one of max_sat_per_vb, max_relative_fee or max_absolute_fee_sat",
+                name
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn parses_fee_policy_override() {
+        let fee_policy =
+            "refund:max_sat_per_vb=200,max_relative_fee=0.1,max_absolute_fee_sat=500000"
+                .parse::<FeePolicyOverride>()
+                .unwrap();
+
+        assert_eq!(
+            fee_policy,
+            FeePolicyOverride {
+                kind: TxKind::Refund,
+                settings: vec![
+                    FeePolicySetting::MaxSatPerVb(dec!(200)),
+                    FeePolicySetting::MaxRelativeFee(dec!(0.1)),
+                    FeePolicySetting::MaxAbsoluteFee(Amount::from_sat(500_000)),
+                ],
+            }
+        );
+        assert!("refund".parse::<FeePolicyOverride>().is_err());
+        assert!("swap:max_sat_per_vb=200"
+            .parse::<FeePolicyOverride>()
+            .is_err());
+        assert!("refund:max_fee=200".parse::<FeePolicyOverride>().is_err());
+        assert!("refund:min_sat_per_vb=10"
+            .parse::<FeePolicyOverride>()
+            .is_err());
+        assert!("refund:max_sat_per_vb=0.5"
+            .parse::<FeePolicyOverride>()
+            .is_err());
+    }
+
+    #[test]
+    fn overrides_only_change_the_given_settings() {
+        let mut fee_policies = FeePolicies::default();
+
+        fee_policies.apply(&["cancel:max_sat_per_vb=50"
+            .parse::<FeePolicyOverride>()
+            .unwrap()]);
+
+        assert_eq!(fee_policies.cancel.max_sat_per_vb, Some(dec!(50)));
+        assert_eq!(
+            fee_policies.cancel.max_relative_fee,
+            DEFAULT_MAX_RELATIVE_TX_FEE
+        );
+        assert_eq!(fee_policies.refund, FeePolicy::default());
+    }
+
+    #[test]
+    fn limits_fee_rate_to_maximum() {
+        let fee_policy = FeePolicy {
+            max_sat_per_vb: Some(dec!(50)),
+            ..FeePolicy::default()
+        };
+
+        let fee_rate = |sats_per_vb| {
+            fee_policy
+                .fee_rate(TxKind::Refund, Ok(FeeRate::from_sat_per_vb(sats_per_vb)))
+                .unwrap()
+        };
+
+        assert_eq!(fee_rate(1.0), FeeRate::from_sat_per_vb(1.0));
+        assert_eq!(fee_rate(10.0), FeeRate::from_sat_per_vb(10.0));
+        assert_eq!(fee_rate(80.0), FeeRate::from_sat_per_vb(50.0));
+    }
+
+    #[test]
+    fn fails_if_estimation_fails() {
+        assert!(FeePolicy::default()
+            .fee_rate(TxKind::Refund, Err(anyhow!("no estimate")))
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_inconsistent_fee_policies() {
+        let mut fee_policies = FeePolicies::default();
+        assert!(fee_policies.validate().is_ok());
+
+        fee_policies.punish.max_sat_per_vb = Some(dec!(0.5));
+        assert!(fee_policies.validate().is_err());
+
+        fee_policies.punish = FeePolicy {
+            max_relative_fee: Decimal::ZERO,
+            ..FeePolicy::default()
+        };
+        assert!(fee_policies.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_fee_policies_that_are_never_used() {
+        let mut fee_policies = FeePolicies::default();
+        assert!(fee_policies.ensure_only(&TxKind::ASB).is_ok());
+        assert!(fee_policies.ensure_only(&TxKind::CLI).is_ok());
+
+        fee_policies.refund.max_sat_per_vb = Some(dec!(200));
+        assert!(fee_policies.ensure_only(&TxKind::CLI).is_ok());
+        assert!(fee_policies.ensure_only(&TxKind::ASB).is_err());
+    }
+}
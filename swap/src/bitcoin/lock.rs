@@ -2,10 +2,12 @@ use crate::bitcoin::wallet::{EstimateFeeRate, Watchable};
 use crate::bitcoin::{
     build_shared_output_descriptor, Address, Amount, PublicKey, Transaction, Wallet,
 };
+use ::bitcoin::secp256k1::Secp256k1;
 use ::bitcoin::util::psbt::PartiallySignedTransaction;
 use ::bitcoin::{OutPoint, TxIn, TxOut, Txid};
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use bdk::database::BatchDatabase;
+use bdk::miniscript::psbt::PsbtExt;
 use bdk::miniscript::Descriptor;
 use bitcoin::{PackedLockTime, Script, Sequence};
 use serde::{Deserialize, Serialize};
@@ -96,6 +98,68 @@ impl TxLock {
         })
     }
 
+    /// Creates an instance of `TxLock` from a PSBT that was funded by an
+    /// external wallet.
+    ///
+    /// The placeholder output of the funding PSBT is replaced with the shared
+    /// output. The resulting PSBT is validated the same way as in
+    /// [`TxLock::from_psbt`].
+    pub fn from_external_funding(
+        funding: ExternalFunding,
+        A: PublicKey,
+        B: PublicKey,
+    ) -> Result<Self> {
+        let amount = funding.amount()?;
+        let vout = funding.placeholder_vout()?;
+
+        let mut psbt = funding.psbt;
+        psbt.unsigned_tx.output[vout].script_pubkey =
+            build_shared_output_descriptor(A.0, B.0).script_pubkey();
+        // The metadata of the placeholder output does not apply to the shared
+        // output
+        psbt.outputs[vout] = Default::default();
+
+        TxLock::from_psbt(psbt, A, B, amount)
+    }
+
+    /// Validates a PSBT that was signed by an external wallet and extracts
+    /// the signed lock transaction from it.
+    ///
+    /// The signed PSBT must be identical to this lock transaction apart from
+    /// the signatures, otherwise the transaction id would not match the one
+    /// that the cancel and refund transactions were signed for.
+    pub fn extract_externally_signed(
+        &self,
+        mut signed: PartiallySignedTransaction,
+        A: PublicKey,
+        B: PublicKey,
+    ) -> Result<Transaction> {
+        if signed.unsigned_tx.txid() != self.txid() {
+            bail!("Signed PSBT does not match the agreed lock transaction")
+        }
+
+        TxLock::from_psbt(signed.clone(), A, B, self.lock_amount())
+            .context("Signed PSBT is not a valid lock transaction")?;
+
+        if signed
+            .inputs
+            .iter()
+            .any(|input| input.final_script_witness.is_none())
+        {
+            signed
+                .finalize_mut(&Secp256k1::verification_only())
+                .map_err(|errors| anyhow!("Failed to finalize signed PSBT: {:?}", errors))?;
+        }
+
+        let transaction = signed.extract_tx();
+
+        if transaction.txid() != self.txid() {
+            bail!("Signing the PSBT changed the id of the lock transaction")
+        }
+
+        Ok(transaction)
+    }
+
     pub fn lock_amount(&self) -> Amount {
         Amount::from_sat(self.inner.clone().extract_tx().output[self.lock_output_vout()].value)
     }
@@ -104,6 +168,36 @@ impl TxLock {
         self.inner.clone().extract_tx().txid()
     }
 
+    /// The fee paid by the lock transaction, or `None` if the values of the
+    /// funding inputs are not known.
+    pub fn fee(&self) -> Option<Amount> {
+        let inputs = self
+            .inner
+            .inputs
+            .iter()
+            .zip(&self.inner.unsigned_tx.input)
+            .map(
+                |(input, txin)| match (&input.witness_utxo, &input.non_witness_utxo) {
+                    (Some(utxo), _) => Some(utxo.value),
+                    (None, Some(tx)) => tx
+                        .output
+                        .get(txin.previous_output.vout as usize)
+                        .map(|output| output.value),
+                    (None, None) => None,
+                },
+            )
+            .sum::<Option<u64>>()?;
+        let outputs = self
+            .inner
+            .unsigned_tx
+            .output
+            .iter()
+            .map(|output| output.value)
+            .sum::<u64>();
+
+        inputs.checked_sub(outputs).map(Amount::from_sat)
+    }
+
     pub fn as_outpoint(&self) -> OutPoint {
         // This is fine because a transaction that has that many outputs is not
         // realistic
@@ -169,6 +263,94 @@ impl TxLock {
     }
 }
 
+/// An unsigned PSBT, created by a wallet other than the CLI's internal one,
+/// that pays the swap amount to a placeholder output.
+///
+/// The placeholder is swapped for the shared output once the public keys of
+/// both parties are known. Alice signs the cancel transaction for the id of the
+/// lock transaction before it is signed, hence all inputs have to be native
+/// segwit: their signatures do not change the transaction id.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExternalFunding {
+    psbt: PartiallySignedTransaction,
+    placeholder: Script,
+}
+
+impl ExternalFunding {
+    pub fn new(psbt: PartiallySignedTransaction, placeholder: Script) -> Result<Self> {
+        let funding = Self { psbt, placeholder };
+
+        funding.placeholder_vout()?;
+
+        if funding.psbt.unsigned_tx.output.len() > 2 {
+            bail!(
+                "Funding PSBT has {} outputs, expected the placeholder output and at most one change output",
+                funding.psbt.unsigned_tx.output.len()
+            )
+        }
+
+        for (index, (input, tx_in)) in funding
+            .psbt
+            .inputs
+            .iter()
+            .zip(funding.psbt.unsigned_tx.input.iter())
+            .enumerate()
+        {
+            let spent_script = match (&input.witness_utxo, &input.non_witness_utxo) {
+                (Some(utxo), _) => utxo.script_pubkey.clone(),
+                (None, Some(tx)) => tx
+                    .output
+                    .get(tx_in.previous_output.vout as usize)
+                    .with_context(|| {
+                        format!("Input {} spends an output that does not exist", index)
+                    })?
+                    .script_pubkey
+                    .clone(),
+                (None, None) => bail!(
+                    "Input {} of the funding PSBT does not include the output it spends",
+                    index
+                ),
+            };
+
+            if !spent_script.is_witness_program() {
+                bail!(
+                    "Input {} of the funding PSBT is not a native segwit input",
+                    index
+                )
+            }
+        }
+
+        Ok(funding)
+    }
+
+    /// The amount paid to the placeholder output, i.e. the amount of Bitcoin
+    /// to swap.
+    pub fn amount(&self) -> Result<Amount> {
+        let vout = self.placeholder_vout()?;
+
+        Ok(Amount::from_sat(self.psbt.unsigned_tx.output[vout].value))
+    }
+
+    fn placeholder_vout(&self) -> Result<usize> {
+        let mut placeholders = self
+            .psbt
+            .unsigned_tx
+            .output
+            .iter()
+            .enumerate()
+            .filter(|(_, output)| output.script_pubkey == self.placeholder)
+            .map(|(vout, _)| vout);
+
+        match (placeholders.next(), placeholders.next()) {
+            (Some(vout), None) => Ok(vout),
+            (None, _) => bail!("Funding PSBT does not pay to the placeholder address"),
+            (Some(_), Some(_)) => {
+                bail!("Funding PSBT pays to the placeholder address more than once")
+            }
+        }
+    }
+}
+
 impl From<TxLock> for PartiallySignedTransaction {
     fn from(from: TxLock) -> Self {
         from.inner
@@ -222,6 +404,24 @@ mod tests {
         result.expect("PSBT to be valid");
     }
 
+    #[tokio::test]
+    async fn calculates_fee_from_funding_inputs() {
+        let (A, B) = alice_and_bob();
+        let wallet = WalletBuilder::new(50_000).build();
+        let agreed_amount = Amount::from_sat(10000);
+
+        let psbt = bob_make_psbt(A, B, &wallet, agreed_amount).await;
+        let outputs = psbt
+            .unsigned_tx
+            .output
+            .iter()
+            .map(|output| output.value)
+            .sum::<u64>();
+        let tx_lock = TxLock::from_psbt(psbt, A, B, agreed_amount).unwrap();
+
+        assert_eq!(tx_lock.fee(), Some(Amount::from_sat(50_000 - outputs)));
+    }
+
     #[tokio::test]
     async fn given_bob_is_sending_less_than_agreed_when_reconstructing_txlock_then_fails() {
         let (A, B) = alice_and_bob();
@@ -248,6 +448,53 @@ mod tests {
         result.expect_err("PSBT to be invalid");
     }
 
+    #[tokio::test]
+    async fn given_externally_funded_psbt_when_replacing_placeholder_then_pays_to_shared_output() {
+        let (A, B) = alice_and_bob();
+        let external_wallet = WalletBuilder::new(50_000).build();
+        let agreed_amount = Amount::from_sat(10000);
+        let placeholder = external_wallet.new_address().await.unwrap();
+
+        let funding =
+            external_make_funding_psbt(&external_wallet, &placeholder, agreed_amount).await;
+        let funding = ExternalFunding::new(funding, placeholder.script_pubkey()).unwrap();
+        let tx_lock = TxLock::from_external_funding(funding, A, B).unwrap();
+
+        assert_eq!(tx_lock.lock_amount(), agreed_amount);
+        TxLock::from_psbt(tx_lock.into(), A, B, agreed_amount).expect("PSBT to be valid");
+    }
+
+    #[tokio::test]
+    async fn given_funding_psbt_without_placeholder_output_then_fails() {
+        let external_wallet = WalletBuilder::new(50_000).build();
+        let agreed_amount = Amount::from_sat(10000);
+        let placeholder = external_wallet.new_address().await.unwrap();
+        let other = external_wallet.new_address().await.unwrap();
+
+        let funding = external_make_funding_psbt(&external_wallet, &other, agreed_amount).await;
+        let result = ExternalFunding::new(funding, placeholder.script_pubkey());
+
+        result.expect_err("funding PSBT to be invalid");
+    }
+
+    #[tokio::test]
+    async fn given_signed_psbt_for_different_transaction_then_fails() {
+        let (A, B) = alice_and_bob();
+        let external_wallet = WalletBuilder::new(50_000).build();
+        let agreed_amount = Amount::from_sat(10000);
+        let placeholder = external_wallet.new_address().await.unwrap();
+
+        let funding =
+            external_make_funding_psbt(&external_wallet, &placeholder, agreed_amount).await;
+        let funding = ExternalFunding::new(funding, placeholder.script_pubkey()).unwrap();
+        let tx_lock = TxLock::from_external_funding(funding, A, B).unwrap();
+
+        let other_psbt = bob_make_psbt(A, B, &external_wallet, agreed_amount).await;
+        let result = tx_lock.extract_externally_signed(other_psbt, A, B);
+
+        result.expect_err("signed PSBT to be rejected");
+    }
+
     proptest::proptest! {
         #[test]
         fn estimated_tx_lock_script_size_never_changes(a in crate::proptest::ecdsa_fun::point(), b in crate::proptest::ecdsa_fun::point()) {
@@ -275,6 +522,18 @@ mod tests {
             .into()
     }
 
+    async fn external_make_funding_psbt(
+        wallet: &Wallet<bdk::database::MemoryDatabase, StaticFeeRate>,
+        placeholder: &Address,
+        amount: Amount,
+    ) -> PartiallySignedTransaction {
+        let change = wallet.new_address().await.unwrap();
+        wallet
+            .send_to_address(placeholder.clone(), amount, Some(change))
+            .await
+            .unwrap()
+    }
+
     fn alice_and_bob() -> (PublicKey, PublicKey) {
         (PublicKey::random(), PublicKey::random())
     }
@@ -91,8 +91,8 @@ impl TxPunish {
         Ok(tx_punish)
     }
 
-    pub fn weight() -> usize {
-        548
+    pub fn weight(address: &Address) -> usize {
+        bitcoin::weight_spending_shared_output_to(address)
     }
 }
 
@@ -140,8 +140,8 @@ impl TxRedeem {
         Ok(sig)
     }
 
-    pub fn weight() -> usize {
-        548
+    pub fn weight(address: &Address) -> usize {
+        crate::bitcoin::weight_spending_shared_output_to(address)
     }
 
     #[cfg(test)]
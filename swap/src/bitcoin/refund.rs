@@ -147,8 +147,8 @@ impl TxRefund {
         Ok(sig)
     }
 
-    pub fn weight() -> usize {
-        548
+    pub fn weight(address: &Address) -> usize {
+        bitcoin::weight_spending_shared_output_to(address)
     }
 }
 
@@ -17,6 +17,12 @@ impl From<BlockHeight> for u32 {
     }
 }
 
+impl From<u32> for BlockHeight {
+    fn from(height: u32) -> Self {
+        Self(height)
+    }
+}
+
 impl TryFrom<HeaderNotification> for BlockHeight {
     type Error = anyhow::Error;
 
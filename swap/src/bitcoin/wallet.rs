@@ -1,57 +1,68 @@
+use crate::bitcoin::backend::{Backend, ChainBackend, HistoryEntry};
+use crate::bitcoin::fee_estimator::FeeEstimator;
 use crate::bitcoin::timelocks::BlockHeight;
-use crate::bitcoin::{Address, Amount, Transaction};
+use crate::bitcoin::{
+    to_fee_rate, Address, Amount, FeeEstimation, FeePolicies, FeePolicy, Transaction, TxKind,
+    Withdrawal,
+};
 use crate::env;
 use ::bitcoin::util::psbt::PartiallySignedTransaction;
 use ::bitcoin::Txid;
 use anyhow::{bail, Context, Result};
-use bdk::blockchain::{Blockchain, ElectrumBlockchain, GetTx};
+use bdk::blockchain::AnyBlockchain;
 use bdk::database::BatchDatabase;
-use bdk::electrum_client::{ElectrumApi, GetHistoryRes};
 use bdk::sled::Tree;
 use bdk::wallet::export::FullyNodedExport;
-use bdk::wallet::AddressIndex;
+use bdk::wallet::{wallet_name_from_descriptor, AddressIndex};
 use bdk::{FeeRate, KeychainKind, SignOptions, SyncOptions};
+use bitcoin::secp256k1::Secp256k1;
 use bitcoin::util::bip32::ExtendedPrivKey;
-use bitcoin::{Network, Script};
-use reqwest::Url;
+use bitcoin::{BlockHash, Network, OutPoint, Script};
 use rust_decimal::prelude::*;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::fmt;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
-use tokio::sync::{watch, Mutex};
+use tokio::sync::{watch, Mutex, Notify};
 
 const SLED_TREE_NAME: &str = "default_tree";
 
-/// Assuming we add a spread of 3% we don't want to pay more than 3% of the
-/// amount for tx fees.
-const MAX_RELATIVE_TX_FEE: Decimal = dec!(0.03);
-const MAX_ABSOLUTE_TX_FEE: Decimal = dec!(100_000);
 const DUST_AMOUNT: u64 = 546;
 
 const WALLET: &str = "wallet";
 const WALLET_OLD: &str = "wallet-old";
 
+/// How often a backend that pushes notifications is asked whether it received
+/// one, to update the status of transactions right away.
+const NOTIFICATION_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
 pub struct Wallet<D = Tree, C = Client> {
-    client: Arc<Mutex<C>>,
+    client: Arc<C>,
     wallet: Arc<Mutex<bdk::Wallet<D>>>,
     finality_confirmations: u32,
     network: Network,
     target_block: usize,
+    fee_policies: FeePolicies,
 }
 
 impl Wallet {
     pub async fn new(
-        electrum_rpc_url: Url,
+        backend: Backend,
         data_dir: impl AsRef<Path>,
         xprivkey: ExtendedPrivKey,
         env_config: env::Config,
         target_block: usize,
+        fee_policies: FeePolicies,
+        fee_estimation: FeeEstimation,
     ) -> Result<Self> {
+        fee_policies
+            .validate()
+            .context("Invalid Bitcoin fee policy")?;
+
         let data_dir = data_dir.as_ref();
         let wallet_dir = data_dir.join(WALLET);
         let database = bdk::sled::open(wallet_dir)?.open_tree(SLED_TREE_NAME)?;
@@ -70,16 +81,28 @@ impl Wallet {
             err => err?,
         };
 
-        let client = Client::new(electrum_rpc_url, env_config.bitcoin_sync_interval())?;
+        let wallet_name = wallet_name_from_descriptor(
+            bdk::template::Bip84(xprivkey, KeychainKind::External),
+            Some(bdk::template::Bip84(xprivkey, KeychainKind::Internal)),
+            network,
+            &Secp256k1::new(),
+        )?;
+        let client = Client::new(
+            backend.connect(network, wallet_name)?,
+            env_config.bitcoin_sync_interval(),
+            &fee_estimation,
+        )
+        .await?;
 
         let network = wallet.network();
 
         Ok(Self {
-            client: Arc::new(Mutex::new(client)),
+            client: Arc::new(client),
             wallet: Arc::new(Mutex::new(wallet)),
             finality_confirmations: env_config.bitcoin_finality_confirmations,
             network,
             target_block,
+            fee_policies,
         })
     }
 
@@ -126,10 +149,7 @@ impl Wallet {
             .subscribe_to((txid, transaction.output[0].script_pubkey.clone()))
             .await;
 
-        let client = self.client.lock().await;
-        let blockchain = client.blockchain();
-
-        blockchain.broadcast(&transaction).with_context(|| {
+        self.client.broadcast(&transaction).with_context(|| {
             format!("Failed to broadcast Bitcoin {} transaction {}", kind, txid)
         })?;
 
@@ -148,61 +168,17 @@ impl Wallet {
     where
         T: Watchable,
     {
-        self.client.lock().await.status_of_script(tx)
+        self.client.status_of_script(tx)
     }
 
     pub async fn subscribe_to(&self, tx: impl Watchable + Send + 'static) -> Subscription {
         let txid = tx.id();
-        let script = tx.script();
-
-        let sub = self
-            .client
-            .lock()
-            .await
-            .subscriptions
-            .entry((txid, script.clone()))
-            .or_insert_with(|| {
-                let (sender, receiver) = watch::channel(ScriptStatus::Unseen);
-                let client = self.client.clone();
-
-                tokio::spawn(async move {
-                    let mut last_status = None;
-
-                    loop {
-                        let new_status = match client.lock().await.status_of_script(&tx) {
-                            Ok(new_status) => new_status,
-                            Err(error) => {
-                                tracing::warn!(%txid, "Failed to get status of script: {:#}", error);
-                                ScriptStatus::Retrying
-                            }
-                        };
-
-                        if new_status != ScriptStatus::Retrying
-                        {
-                            last_status = Some(print_status_change(txid, last_status, new_status));
-
-                            let all_receivers_gone = sender.send(new_status).is_err();
-
-                            if all_receivers_gone {
-                                tracing::debug!(%txid, "All receivers gone, removing subscription");
-                                client.lock().await.subscriptions.remove(&(txid, script));
-                                return;
-                            }
-                        }
-
-                        tokio::time::sleep(Duration::from_secs(5)).await;
-                    }
-                });
 
-                Subscription {
-                    receiver,
-                    finality_confirmations: self.finality_confirmations,
-                    txid,
-                }
-            })
-            .clone();
-
-        sub
+        Subscription {
+            receiver: self.client.subscribe(txid, tx.script()),
+            finality_confirmations: self.finality_confirmations,
+            txid,
+        }
     }
 
     pub async fn wallet_export(&self, role: &str) -> Result<FullyNodedExport> {
@@ -223,6 +199,11 @@ fn print_status_change(txid: Txid, old: Option<ScriptStatus>, new: ScriptStatus)
         (None, new_status) => {
             tracing::debug!(%txid, status = %new_status, "Found relevant Bitcoin transaction");
         }
+        (Some(old_status), new_status)
+            if new_status.confirmations() < old_status.confirmations() =>
+        {
+            tracing::warn!(%txid, %new_status, %old_status, "Bitcoin transaction lost confirmations in a reorg");
+        }
         (Some(old_status), new_status) if old_status != new_status => {
             tracing::debug!(%txid, %new_status, %old_status, "Bitcoin transaction status changed");
         }
@@ -358,7 +339,8 @@ where
         Ok(Amount::from_sat(fees))
     }
 
-    /// Builds a partially signed transaction
+    /// Builds a partially signed transaction paying the fee rate of the lock
+    /// transaction.
     ///
     /// Ensures that the address script is at output index `0`
     /// for the partially signed transaction.
@@ -368,19 +350,18 @@ where
         amount: Amount,
         change_override: Option<Address>,
     ) -> Result<PartiallySignedTransaction> {
-        if self.network != address.network {
+        if !address.is_valid_for_network(self.network) {
             bail!("Cannot build PSBT because network of given address is {} but wallet is on network {}", address.network, self.network);
         }
 
         if let Some(change) = change_override.as_ref() {
-            if self.network != change.network {
+            if !change.is_valid_for_network(self.network) {
                 bail!("Cannot build PSBT because network of given address is {} but wallet is on network {}", change.network, self.network);
             }
         }
 
         let wallet = self.wallet.lock().await;
-        let client = self.client.lock().await;
-        let fee_rate = client.estimate_feerate(self.target_block)?;
+        let fee_rate = self.fee_rate(TxKind::Lock)?;
         let script = address.script_pubkey();
 
         let mut tx_builder = wallet.build_tx();
@@ -418,6 +399,193 @@ where
         Ok(psbt)
     }
 
+    /// Builds an unsigned transaction paying the recipients of the withdrawal.
+    pub async fn build_withdrawal(
+        &self,
+        withdrawal: &Withdrawal,
+    ) -> Result<PartiallySignedTransaction> {
+        for address in withdrawal.addresses() {
+            if !address.is_valid_for_network(self.network) {
+                bail!("Cannot build PSBT because network of given address is {} but wallet is on network {}", address.network, self.network);
+            }
+        }
+
+        let wallet = self.wallet.lock().await;
+        let fee_rate = match withdrawal.bdk_fee_rate()? {
+            Some(fee_rate) => fee_rate,
+            None => self.client.estimate_feerate(self.target_block)?,
+        };
+
+        let mut tx_builder = wallet.build_tx();
+        for recipient in &withdrawal.recipients {
+            tx_builder.add_recipient(recipient.address.script_pubkey(), recipient.amount.to_sat());
+        }
+        if let Some(drain_to) = &withdrawal.drain_to {
+            tx_builder.drain_to(drain_to.script_pubkey());
+
+            if withdrawal.utxos.is_empty() {
+                tx_builder.drain_wallet();
+            }
+        }
+        if !withdrawal.utxos.is_empty() {
+            tx_builder
+                .add_utxos(&withdrawal.utxos)
+                .context("Selected UTXO does not belong to the wallet")?;
+            tx_builder.manually_selected_only();
+        }
+        if withdrawal.rbf {
+            tx_builder.enable_rbf();
+        }
+        tx_builder.fee_rate(fee_rate);
+
+        let (psbt, _details) = tx_builder.finish()?;
+
+        Ok(psbt)
+    }
+
+    /// Builds a transaction that gets the given unconfirmed transaction
+    /// confirmed faster by paying a higher fee rate.
+    ///
+    /// Transactions of the wallet that signal replaceability are replaced
+    /// (RBF). Otherwise an output of the transaction that belongs to the wallet
+    /// is spent by a child transaction paying enough fees for both (CPFP). This
+    /// is what allows to speed up transactions like the redeem transaction of
+    /// Alice or the refund transaction of Bob.
+    pub async fn bump_fee(
+        &self,
+        txid: Txid,
+        fee_rate: Option<Decimal>,
+    ) -> Result<(FeeBump, PartiallySignedTransaction)>
+    where
+        C: GetTransaction,
+    {
+        let wallet = self.wallet.lock().await;
+        let fee_rate = match fee_rate {
+            Some(fee_rate) => to_fee_rate(fee_rate)?,
+            None => self.client.estimate_feerate(self.target_block)?,
+        };
+
+        let details = wallet
+            .get_tx(&txid, true)?
+            .with_context(|| format!("Transaction {} is not known to the wallet", txid))?;
+        if details.confirmation_time.is_some() {
+            bail!("Transaction {} is already confirmed", txid)
+        }
+        let transaction = details
+            .transaction
+            .context("Wallet did not return the raw transaction")?;
+
+        if transaction
+            .input
+            .iter()
+            .any(|input| input.sequence.is_rbf())
+        {
+            let replacement = wallet.build_fee_bump(txid).and_then(|mut tx_builder| {
+                tx_builder.fee_rate(fee_rate);
+                tx_builder.enable_rbf();
+                tx_builder.finish()
+            });
+
+            match replacement {
+                Ok((psbt, _)) => return Ok((FeeBump::Replace, psbt)),
+                Err(error) => {
+                    tracing::debug!(%txid, "Cannot replace transaction, falling back to CPFP: {:#}", error)
+                }
+            }
+        }
+
+        let mut own_outputs = Vec::new();
+        for (vout, output) in transaction.output.iter().enumerate() {
+            if wallet.is_mine(&output.script_pubkey)? {
+                own_outputs.push(OutPoint::new(txid, u32::try_from(vout)?));
+            }
+        }
+        if own_outputs.is_empty() {
+            bail!(
+                "Cannot bump the fee of transaction {} because none of its outputs belong to the wallet",
+                txid
+            )
+        }
+        // Backends only report the fee if all inputs belong to the wallet.
+        let parent_fee = self.fee_of(&wallet, &transaction)?;
+        let drain_to = wallet.get_address(AddressIndex::New)?.script_pubkey();
+
+        let build_child = |fee: ChildFee| {
+            let mut tx_builder = wallet.build_tx();
+            tx_builder.add_utxos(&own_outputs)?;
+            tx_builder.manually_selected_only();
+            tx_builder.drain_to(drain_to.clone());
+            tx_builder.enable_rbf();
+            match fee {
+                ChildFee::Rate(fee_rate) => tx_builder.fee_rate(fee_rate),
+                ChildFee::Absolute(fee) => tx_builder.fee_absolute(fee),
+            };
+
+            tx_builder.finish()
+        };
+
+        // The fee the child pays at the target fee rate tells us its size.
+        let (_, draft) = build_child(ChildFee::Rate(fee_rate))?;
+        let child_fee = cpfp_fee(
+            fee_rate,
+            transaction.vsize(),
+            parent_fee,
+            draft
+                .fee
+                .context("Fee of the child transaction is unknown")?,
+        )
+        .with_context(|| {
+            format!(
+                "Transaction {} already pays a fee rate of at least {} sat/vB",
+                txid,
+                fee_rate.as_sat_per_vb()
+            )
+        })?;
+        let (psbt, _) = build_child(ChildFee::Absolute(child_fee))?;
+
+        Ok((FeeBump::ChildPaysForParent, psbt))
+    }
+
+    /// Calculates the fee of a transaction from the outputs it spends.
+    ///
+    /// Outputs not known to the wallet are looked up on the blockchain.
+    fn fee_of(&self, wallet: &bdk::Wallet<D>, transaction: &Transaction) -> Result<u64>
+    where
+        C: GetTransaction,
+    {
+        let mut spent = 0;
+        for input in &transaction.input {
+            let previous = input.previous_output;
+            let previous_tx = match wallet
+                .get_tx(&previous.txid, true)?
+                .and_then(|details| details.transaction)
+            {
+                Some(previous_tx) => previous_tx,
+                None => self.client.get_tx(&previous.txid)?.with_context(|| {
+                    format!(
+                        "Could not find transaction {} spent by the transaction",
+                        previous.txid
+                    )
+                })?,
+            };
+            let output = previous_tx
+                .output
+                .get(previous.vout as usize)
+                .with_context(|| format!("Spent output {} does not exist", previous))?;
+
+            spent += output.value;
+        }
+        let sent = transaction
+            .output
+            .iter()
+            .map(|output| output.value)
+            .sum::<u64>();
+
+        spent
+            .checked_sub(sent)
+            .context("Transaction sends more than it spends")
+    }
+
     /// Calculates the maximum "giveable" amount of this wallet.
     ///
     /// We define this as the maximum amount we can pay to a single output,
@@ -429,14 +597,13 @@ where
         if balance.get_total() < DUST_AMOUNT {
             return Ok(Amount::ZERO);
         }
-        let client = self.client.lock().await;
-        let min_relay_fee = client.min_relay_fee()?.to_sat();
+        let min_relay_fee = self.client.min_relay_fee()?.to_sat();
 
         if balance.get_total() < min_relay_fee {
             return Ok(Amount::ZERO);
         }
 
-        let fee_rate = client.estimate_feerate(self.target_block)?;
+        let fee_rate = self.fee_rate(TxKind::Lock)?;
 
         let mut tx_builder = wallet.build_tx();
 
@@ -460,26 +627,82 @@ where
     }
 
     /// Estimate total tx fee for a pre-defined target block based on the
-    /// transaction weight. The max fee cannot be more than MAX_PERCENTAGE_FEE
-    /// of amount
+    /// transaction weight. The fee is limited by the fee policy of the kind of
+    /// transaction.
     pub async fn estimate_fee(
         &self,
+        kind: TxKind,
         weight: usize,
         transfer_amount: bitcoin::Amount,
     ) -> Result<bitcoin::Amount> {
-        let client = self.client.lock().await;
-        let fee_rate = client.estimate_feerate(self.target_block)?;
-        let min_relay_fee = client.min_relay_fee()?;
+        let fee_rate = self.fee_rate(kind)?;
+        let min_relay_fee = self.client.min_relay_fee()?;
+
+        estimate_fee(
+            weight,
+            transfer_amount,
+            fee_rate,
+            min_relay_fee,
+            self.fee_policies.get(kind),
+        )
+    }
 
-        estimate_fee(weight, transfer_amount, fee_rate, min_relay_fee)
+    /// The fee rate to pay for the given kind of transaction according to its
+    /// fee policy.
+    fn fee_rate(&self, kind: TxKind) -> Result<FeeRate> {
+        self.fee_policies
+            .get(kind)
+            .fee_rate(kind, self.client.estimate_feerate(self.target_block))
     }
 }
 
+/// How [`Wallet::bump_fee`] speeds up a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeBump {
+    /// The transaction is replaced by one paying a higher fee.
+    Replace,
+    /// A child transaction pays the fee for the transaction.
+    ChildPaysForParent,
+}
+
+impl fmt::Display for FeeBump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeeBump::Replace => write!(f, "RBF"),
+            FeeBump::ChildPaysForParent => write!(f, "CPFP"),
+        }
+    }
+}
+
+enum ChildFee {
+    Rate(FeeRate),
+    Absolute(u64),
+}
+
+/// The fee a child transaction has to pay so that the parent and the child
+/// together pay `fee_rate`.
+///
+/// Returns `None` if the parent already pays at least `fee_rate` on its own.
+fn cpfp_fee(
+    fee_rate: FeeRate,
+    parent_vsize: usize,
+    parent_fee: u64,
+    child_fee_at_rate: u64,
+) -> Option<u64> {
+    let missing = fee_rate.fee_vb(parent_vsize).checked_sub(parent_fee)?;
+    if missing == 0 {
+        return None;
+    }
+
+    Some(child_fee_at_rate + missing)
+}
+
 fn estimate_fee(
     weight: usize,
     transfer_amount: Amount,
     fee_rate: FeeRate,
     min_relay_fee: Amount,
+    fee_policy: &FeePolicy,
 ) -> Result<Amount> {
     if transfer_amount.to_sat() <= 546 {
         bail!("Amounts needs to be greater than Bitcoin dust amount.")
@@ -513,7 +736,8 @@ fn estimate_fee(
     );
 
     let transfer_amount = Decimal::from(transfer_amount.to_sat());
-    let max_allowed_fee = transfer_amount * MAX_RELATIVE_TX_FEE;
+    let max_allowed_fee = transfer_amount * fee_policy.max_relative_fee;
+    let max_absolute_fee = Decimal::from(fee_policy.max_absolute_fee.to_sat());
     let min_relay_fee = Decimal::from(min_relay_fee.to_sat());
 
     let recommended_fee = if sats_per_vbyte < min_relay_fee {
@@ -523,12 +747,12 @@ fn estimate_fee(
             min_relay_fee
         );
         min_relay_fee.to_u64()
-    } else if sats_per_vbyte > max_allowed_fee && sats_per_vbyte > MAX_ABSOLUTE_TX_FEE {
+    } else if sats_per_vbyte > max_allowed_fee && sats_per_vbyte > max_absolute_fee {
         tracing::warn!(
             "Hard bound of transaction fees reached. Falling back to: {} sats",
-            MAX_ABSOLUTE_TX_FEE
+            max_absolute_fee
         );
-        MAX_ABSOLUTE_TX_FEE.to_u64()
+        max_absolute_fee.to_u64()
     } else if sats_per_vbyte > max_allowed_fee {
         tracing::warn!(
             "Relative bound of transaction fees reached. Falling back to: {} sats",
@@ -550,20 +774,17 @@ where
     D: BatchDatabase,
 {
     pub async fn get_tx(&self, txid: Txid) -> Result<Option<Transaction>> {
-        let client = self.client.lock().await;
-        let tx = client.get_tx(&txid)?;
+        let tx = self.client.get_tx(&txid)?;
 
         Ok(tx)
     }
 
     pub async fn sync(&self) -> Result<()> {
-        let client = self.client.lock().await;
-        let blockchain = client.blockchain();
-        let sync_opts = SyncOptions::default();
-        self.wallet
-            .lock()
-            .await
-            .sync(blockchain, sync_opts)
+        let wallet = self.wallet.lock().await;
+        self.client
+            .sync_with(&|blockchain: &AnyBlockchain| {
+                Ok(wallet.sync(blockchain, SyncOptions::default())?)
+            })
             .context("Failed to sync balance of Bitcoin wallet")?;
 
         Ok(())
@@ -582,12 +803,26 @@ pub trait EstimateFeeRate {
     fn min_relay_fee(&self) -> Result<bitcoin::Amount>;
 }
 
+pub trait GetTransaction {
+    fn get_tx(&self, txid: &Txid) -> Result<Option<Transaction>>;
+}
+
 #[cfg(test)]
 pub struct StaticFeeRate {
     fee_rate: FeeRate,
     min_relay_fee: bitcoin::Amount,
 }
 
+#[cfg(test)]
+impl StaticFeeRate {
+    pub fn new(sats_per_vb: f32, min_relay_fee_sats: u64) -> Self {
+        Self {
+            fee_rate: FeeRate::from_sat_per_vb(sats_per_vb),
+            min_relay_fee: bitcoin::Amount::from_sat(min_relay_fee_sats),
+        }
+    }
+}
+
 #[cfg(test)]
 impl EstimateFeeRate for StaticFeeRate {
     fn estimate_feerate(&self, _target_block: usize) -> Result<FeeRate> {
@@ -599,6 +834,14 @@ impl EstimateFeeRate for StaticFeeRate {
     }
 }
 
+/// Knows no transactions besides those of the wallet.
+#[cfg(test)]
+impl GetTransaction for StaticFeeRate {
+    fn get_tx(&self, _: &Txid) -> Result<Option<Transaction>> {
+        Ok(None)
+    }
+}
+
 #[cfg(test)]
 #[derive(Debug)]
 pub struct WalletBuilder {
@@ -607,6 +850,7 @@ pub struct WalletBuilder {
     min_relay_fee_sats: u64,
     key: bitcoin::util::bip32::ExtendedPrivKey,
     num_utxos: u8,
+    unconfirmed: Vec<Transaction>,
 }
 
 #[cfg(test)]
@@ -622,6 +866,7 @@ impl WalletBuilder {
             min_relay_fee_sats: 1000,
             key: "tprv8ZgxMBicQKsPeZRHk4rTG6orPS2CRNFX3njhUXx5vj9qGog5ZMH4uGReDWN5kCkY3jmWEtWause41CDvBRXD1shKknAMKxT99o9qUTRVC6m".parse().unwrap(),
             num_utxos: 1,
+            unconfirmed: Vec::new(),
         }
     }
 
@@ -652,9 +897,19 @@ impl WalletBuilder {
         }
     }
 
+    /// Adds a transaction spending outputs of the wallet that is not confirmed
+    /// yet.
+    ///
+    /// Outputs of the transaction belong to the wallet if they pay to one of
+    /// its first ten addresses.
+    pub fn with_unconfirmed(mut self, transaction: Transaction) -> Self {
+        self.unconfirmed.push(transaction);
+        self
+    }
+
     pub fn build(self) -> Wallet<bdk::database::MemoryDatabase, StaticFeeRate> {
-        use bdk::database::{BatchOperations, MemoryDatabase, SyncTime};
-        use bdk::{testutils, BlockTime};
+        use bdk::database::{BatchOperations, Database, MemoryDatabase, SyncTime};
+        use bdk::{testutils, BlockTime, LocalUtxo, TransactionDetails};
 
         let descriptors = testutils!(@descriptors (&format!("wpkh({}/*)", self.key)));
 
@@ -676,17 +931,77 @@ impl WalletBuilder {
         let sync_time = SyncTime { block_time };
         database.set_sync_time(sync_time).unwrap();
 
+        if !self.unconfirmed.is_empty() {
+            let own_scripts = (0..10u32)
+                .map(|index| {
+                    let address = testutils!(@external descriptors, index)
+                        .parse::<Address>()
+                        .unwrap();
+                    database
+                        .set_script_pubkey(&address.script_pubkey(), KeychainKind::External, index)
+                        .unwrap();
+                    address.script_pubkey()
+                })
+                .collect::<BTreeSet<_>>();
+
+            for transaction in self.unconfirmed {
+                let txid = transaction.txid();
+                let mut sent = 0;
+                for input in &transaction.input {
+                    let mut utxo = database
+                        .get_utxo(&input.previous_output)
+                        .unwrap()
+                        .expect("transaction spends outputs of the wallet");
+                    sent += utxo.txout.value;
+                    utxo.is_spent = true;
+                    database.set_utxo(&utxo).unwrap();
+                }
+                let mut received = 0;
+                for (vout, output) in transaction.output.iter().enumerate() {
+                    if own_scripts.contains(&output.script_pubkey) {
+                        received += output.value;
+                        database
+                            .set_utxo(&LocalUtxo {
+                                outpoint: OutPoint::new(txid, vout as u32),
+                                txout: output.clone(),
+                                keychain: KeychainKind::External,
+                                is_spent: false,
+                            })
+                            .unwrap();
+                    }
+                }
+                let fee = sent
+                    - transaction
+                        .output
+                        .iter()
+                        .map(|output| output.value)
+                        .sum::<u64>();
+
+                database
+                    .set_tx(&TransactionDetails {
+                        transaction: Some(transaction),
+                        txid,
+                        received,
+                        sent,
+                        fee: Some(fee),
+                        confirmation_time: None,
+                    })
+                    .unwrap();
+            }
+        }
+
         let wallet = bdk::Wallet::new(&descriptors.0, None, Network::Regtest, database).unwrap();
 
         Wallet {
-            client: Arc::new(Mutex::new(StaticFeeRate {
-                fee_rate: FeeRate::from_sat_per_vb(self.sats_per_vb),
-                min_relay_fee: bitcoin::Amount::from_sat(self.min_relay_fee_sats),
-            })),
+            client: Arc::new(StaticFeeRate::new(
+                self.sats_per_vb,
+                self.min_relay_fee_sats,
+            )),
             wallet: Arc::new(Mutex::new(wallet)),
             finality_confirmations: 1,
             network: Network::Regtest,
             target_block: 1,
+            fee_policies: FeePolicies::default(),
         }
     }
 }
@@ -712,173 +1027,450 @@ impl Watchable for (Txid, Script) {
     }
 }
 
+/// Talks to the chain backend on behalf of the wallet.
+///
+/// The status of subscribed transactions is kept up to date by a single
+/// monitoring task, which sends updates to the subscribers through watch
+/// channels.
 pub struct Client {
-    electrum: bdk::electrum_client::Client,
-    blockchain: ElectrumBlockchain,
-    latest_block_height: BlockHeight,
-    last_sync: Instant,
-    sync_interval: Duration,
-    script_history: BTreeMap<Script, Vec<GetHistoryRes>>,
-    subscriptions: HashMap<(Txid, Script), Subscription>,
+    backend: Arc<dyn ChainBackend>,
+    fee_estimator: FeeEstimator,
+    subscriptions: Arc<std::sync::Mutex<Subscriptions>>,
+    new_subscription: Arc<Notify>,
+    /// The statuses of the subscribed transactions, as known to the monitor.
+    statuses: Arc<std::sync::Mutex<Statuses>>,
+    /// Statuses of other transactions and when they were fetched, they are
+    /// fetched again after one sync interval.
+    fetched_statuses: std::sync::Mutex<HashMap<(Txid, Script), (Instant, ScriptStatus)>>,
+    interval: Duration,
 }
 
+type Subscriptions = HashMap<(Txid, Script), watch::Sender<ScriptStatus>>;
+type Statuses = HashMap<(Txid, Script), ScriptStatus>;
+
 impl Client {
-    fn new(electrum_rpc_url: Url, interval: Duration) -> Result<Self> {
-        let config = bdk::electrum_client::ConfigBuilder::default()
-            .retry(5)
-            .build();
-        let electrum = bdk::electrum_client::Client::from_config(electrum_rpc_url.as_str(), config)
-            .context("Failed to initialize Electrum RPC client")?;
+    async fn new(
+        backend: Box<dyn ChainBackend>,
+        interval: Duration,
+        fee_estimation: &FeeEstimation,
+    ) -> Result<Self> {
+        let backend = Arc::<dyn ChainBackend>::from(backend);
+        let fee_estimator = FeeEstimator::new(fee_estimation, backend.clone())
+            .await
+            .context("Failed to initialize fee estimation")?;
         // Initially fetch the latest block for storing the height.
-        // We do not act on this subscription after this call.
-        let latest_block = electrum
-            .block_headers_subscribe()
-            .context("Failed to subscribe to header notifications")?;
-
-        let client = bdk::electrum_client::Client::new(electrum_rpc_url.as_str())
-            .context("Failed to initialize Electrum RPC client")?;
-        let blockchain = ElectrumBlockchain::from(client);
-        let last_sync = Instant::now()
-            .checked_sub(interval)
-            .expect("no underflow since block time is only 600 secs");
+        let latest_block_height = backend
+            .block_height()
+            .context("Failed to get latest block height")?;
+        let subscriptions = Arc::new(std::sync::Mutex::new(Subscriptions::new()));
+        let new_subscription = Arc::new(Notify::new());
+        let statuses = Arc::new(std::sync::Mutex::new(Statuses::new()));
+
+        let monitor = Monitor {
+            backend: backend.clone(),
+            subscriptions: Arc::downgrade(&subscriptions),
+            latest_block_height,
+            script_history: Default::default(),
+            statuses: statuses.clone(),
+            inclusions: Default::default(),
+        };
+        tokio::spawn(monitor.run(new_subscription.clone(), interval));
 
         Ok(Self {
-            electrum,
-            blockchain,
-            latest_block_height: BlockHeight::try_from(latest_block)?,
-            last_sync,
-            sync_interval: interval,
-            script_history: Default::default(),
-            subscriptions: Default::default(),
+            backend,
+            fee_estimator,
+            subscriptions,
+            new_subscription,
+            statuses,
+            fetched_statuses: Default::default(),
+            interval,
         })
     }
 
-    fn blockchain(&self) -> &ElectrumBlockchain {
-        &self.blockchain
+    fn sync_with(&self, sync: &dyn Fn(&AnyBlockchain) -> Result<()>) -> Result<()> {
+        self.backend.sync_with(sync)
     }
 
-    fn get_tx(&self, txid: &Txid) -> Result<Option<Transaction>, bdk::Error> {
-        self.blockchain.get_tx(txid)
+    fn broadcast(&self, transaction: &Transaction) -> Result<()> {
+        self.backend.broadcast(transaction)
     }
 
-    fn update_state(&mut self) -> Result<()> {
-        let now = Instant::now();
-        if now < self.last_sync + self.sync_interval {
-            return Ok(());
+    fn subscribe(&self, txid: Txid, script: Script) -> watch::Receiver<ScriptStatus> {
+        let receiver = self
+            .subscriptions
+            .lock()
+            .expect("no other thread panicked while holding the lock")
+            .entry((txid, script))
+            .or_insert_with(|| watch::channel(ScriptStatus::Unseen).0)
+            .subscribe();
+        self.new_subscription.notify_one();
+
+        receiver
+    }
+
+    /// Returns the status of the transaction, which is only fetched if the
+    /// transaction is not subscribed to and was not fetched during the last
+    /// sync interval.
+    fn status_of_script<T>(&self, tx: &T) -> Result<ScriptStatus>
+    where
+        T: Watchable,
+    {
+        let key = (tx.id(), tx.script());
+        if let Some(status) = self
+            .statuses
+            .lock()
+            .expect("no other thread panicked while holding the lock")
+            .get(&key)
+        {
+            return Ok(*status);
+        }
+
+        let mut fetched_statuses = self
+            .fetched_statuses
+            .lock()
+            .expect("no other thread panicked while holding the lock");
+        fetched_statuses.retain(|_, (fetched_at, _)| fetched_at.elapsed() < self.interval);
+        if let Some((_, status)) = fetched_statuses.get(&key) {
+            return Ok(*status);
         }
 
-        self.last_sync = now;
-        self.update_latest_block()?;
-        self.update_script_histories()?;
+        let status = self.fetch_status_of_script(tx)?;
+        fetched_statuses.insert(key, (Instant::now(), status));
 
-        Ok(())
+        Ok(status)
     }
 
-    fn status_of_script<T>(&mut self, tx: &T) -> Result<ScriptStatus>
+    fn fetch_status_of_script<T>(&self, tx: &T) -> Result<ScriptStatus>
     where
         T: Watchable,
     {
         let txid = tx.id();
-        let script = tx.script();
+        let latest_block_height = self
+            .backend
+            .block_height()
+            .context("Failed to get latest block height")?;
+        let history = self
+            .backend
+            .script_history(&[(txid, tx.script())])
+            .context("Failed to get script history")?
+            .pop()
+            .context("Received no history for script")?;
+
+        status_from_history(txid, &history, latest_block_height)
+    }
+}
+
+/// Keeps the status of all subscribed transactions up to date.
+///
+/// Only the history of scripts the backend reports as changed is fetched
+/// again, so the load on the backend does not grow with the number of
+/// subscriptions as long as it supports notifications.
+struct Monitor {
+    backend: Arc<dyn ChainBackend>,
+    subscriptions: Weak<std::sync::Mutex<Subscriptions>>,
+    latest_block_height: BlockHeight,
+    script_history: BTreeMap<(Txid, Script), Vec<HistoryEntry>>,
+    statuses: Arc<std::sync::Mutex<Statuses>>,
+    /// The block each confirmed transaction was included in, to detect
+    /// reorgs.
+    inclusions: HashMap<(Txid, Script), (BlockHeight, BlockHash)>,
+}
 
-        if !self.script_history.contains_key(&script) {
-            self.script_history.insert(script.clone(), vec![]);
+impl Monitor {
+    async fn run(mut self, new_subscription: Arc<Notify>, interval: Duration) {
+        // the client is gone once the subscriptions are
+        while let Some(subscriptions) = self.subscriptions.upgrade() {
+            // the backends block while talking to the blockchain
+            let updated = tokio::task::spawn_blocking(move || {
+                let result = self.update(&subscriptions);
+                (self, result)
+            })
+            .await;
+            let (monitor, result) = match updated {
+                Ok(updated) => updated,
+                Err(error) => {
+                    tracing::error!(
+                        "Stopped updating status of Bitcoin transactions: {:#}",
+                        error
+                    );
+                    return;
+                }
+            };
+            self = monitor;
+
+            if let Err(error) = result {
+                tracing::warn!(
+                    "Failed to update status of Bitcoin transactions: {:#}",
+                    error
+                );
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = new_subscription.notified() => {}
+                _ = notification(self.backend.clone()) => {}
+            }
         }
+    }
 
-        self.update_state()?;
+    fn update(&mut self, subscriptions: &std::sync::Mutex<Subscriptions>) -> Result<()> {
+        let watched = {
+            let mut subscriptions = subscriptions
+                .lock()
+                .expect("no other thread panicked while holding the lock");
+            subscriptions.retain(|(txid, _), sender| {
+                let has_receivers = sender.receiver_count() > 0;
+                if !has_receivers {
+                    tracing::debug!(%txid, "All receivers gone, removing subscription");
+                }
+                has_receivers
+            });
 
-        let history = self.script_history.entry(script).or_default();
+            subscriptions.keys().cloned().collect::<Vec<_>>()
+        };
+        self.script_history.retain(|key, _| watched.contains(key));
+        self.statuses
+            .lock()
+            .expect("no other thread panicked while holding the lock")
+            .retain(|key, _| watched.contains(key));
+        self.inclusions.retain(|key, _| watched.contains(key));
 
-        let history_of_tx = history
+        let mut scripts = watched
             .iter()
-            .filter(|entry| entry.tx_hash == txid)
+            .map(|(_, script)| script.clone())
             .collect::<Vec<_>>();
+        scripts.sort();
+        scripts.dedup();
 
-        match history_of_tx.as_slice() {
-            [] => Ok(ScriptStatus::Unseen),
-            [remaining @ .., last] => {
-                if !remaining.is_empty() {
-                    tracing::warn!("Found more than a single history entry for script. This is highly unexpected and those history entries will be ignored")
+        let changes = self
+            .backend
+            .changes(&scripts)
+            .context("Failed to get changes from chain backend")?;
+
+        let mut outdated = watched
+            .iter()
+            .filter(|key| {
+                !self.script_history.contains_key(key) || changes.scripts.contains(&key.1)
+            })
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        // the height also goes down if blocks are invalidated
+        if changes.block_height != self.latest_block_height {
+            tracing::debug!(
+                block_height = u32::from(changes.block_height),
+                "Got notification for new block"
+            );
+            self.latest_block_height = changes.block_height;
+
+            outdated.extend(self.reorged_out()?);
+        }
+
+        if !outdated.is_empty() {
+            let outdated = outdated.into_iter().collect::<Vec<_>>();
+            let histories = self
+                .backend
+                .script_history(&outdated)
+                .context("Failed to get script histories")?;
+
+            if histories.len() != outdated.len() {
+                bail!(
+                    "Expected {} history entries, received {}",
+                    outdated.len(),
+                    histories.len()
+                );
+            }
+
+            self.script_history
+                .extend(outdated.into_iter().zip(histories));
+            self.record_inclusions()?;
+        }
+
+        let subscriptions = subscriptions
+            .lock()
+            .expect("no other thread panicked while holding the lock");
+        let mut statuses = self
+            .statuses
+            .lock()
+            .expect("no other thread panicked while holding the lock");
+        for (key, history) in &self.script_history {
+            let txid = key.0;
+            let new_status = match status_from_history(txid, history, self.latest_block_height) {
+                Ok(new_status) => new_status,
+                Err(error) => {
+                    tracing::warn!(%txid, "Failed to get status of script: {:#}", error);
+                    continue;
                 }
+            };
+            let last_status = statuses.get(key).copied();
+
+            if last_status != Some(new_status) {
+                statuses.insert(
+                    key.clone(),
+                    print_status_change(txid, last_status, new_status),
+                );
 
-                if last.height <= 0 {
-                    Ok(ScriptStatus::InMempool)
-                } else {
-                    Ok(ScriptStatus::Confirmed(
-                        Confirmed::from_inclusion_and_latest_block(
-                            u32::try_from(last.height)?,
-                            u32::from(self.latest_block_height),
-                        ),
-                    ))
+                if let Some(sender) = subscriptions.get(key) {
+                    let _ = sender.send(new_status);
                 }
             }
         }
+
+        Ok(())
     }
 
-    fn update_latest_block(&mut self) -> Result<()> {
-        // Fetch the latest block for storing the height.
-        // We do not act on this subscription after this call, as we cannot rely on
-        // subscription push notifications because eventually the Electrum server will
-        // close the connection and subscriptions are not automatically renewed
-        // upon renewing the connection.
-        let latest_block = self
-            .electrum
-            .block_headers_subscribe()
-            .context("Failed to subscribe to header notifications")?;
-        let latest_block_height = BlockHeight::try_from(latest_block)?;
+    /// Returns the transactions whose block is no longer part of the best
+    /// chain.
+    fn reorged_out(&mut self) -> Result<Vec<(Txid, Script)>> {
+        let mut block_hashes = HashMap::new();
+        let mut reorged_out = Vec::new();
+
+        for (key, (height, block_hash)) in &self.inclusions {
+            let current = match block_hashes.get(&u32::from(*height)) {
+                Some(current) => *current,
+                None => {
+                    let current = self
+                        .backend
+                        .block_hash(*height)
+                        .context("Failed to get block hash")?;
+                    block_hashes.insert(u32::from(*height), current);
+                    current
+                }
+            };
+
+            if current != *block_hash {
+                tracing::warn!(
+                    txid = %key.0,
+                    %block_hash,
+                    block_height = u32::from(*height),
+                    "Block including Bitcoin transaction was reorganized out of the blockchain"
+                );
+                reorged_out.push(key.clone());
+            }
+        }
+
+        for key in &reorged_out {
+            self.inclusions.remove(key);
+        }
 
-        if latest_block_height > self.latest_block_height {
-            tracing::debug!(
-                block_height = u32::from(latest_block_height),
-                "Got notification for new block"
-            );
-            self.latest_block_height = latest_block_height;
+        Ok(reorged_out)
+    }
+
+    /// Remembers the block each confirmed transaction was included in.
+    ///
+    /// Backends that do not report the block only report transactions of the
+    /// best chain, for them the block at that height is taken.
+    fn record_inclusions(&mut self) -> Result<()> {
+        for (key, history) in &self.script_history {
+            match inclusion(key.0, history)? {
+                Some((height, Some(block_hash))) => {
+                    self.inclusions.insert(key.clone(), (height, block_hash));
+                }
+                Some((height, None))
+                    if self.inclusions.get(key).map(|(known, _)| *known) == Some(height) => {}
+                Some((height, None)) => {
+                    let block_hash = self
+                        .backend
+                        .block_hash(height)
+                        .context("Failed to get block hash")?;
+                    self.inclusions.insert(key.clone(), (height, block_hash));
+                }
+                None => {
+                    self.inclusions.remove(key);
+                }
+            }
         }
 
         Ok(())
     }
+}
 
-    fn update_script_histories(&mut self) -> Result<()> {
-        let histories = self
-            .electrum
-            .batch_script_get_history(self.script_history.keys())
-            .context("Failed to get script histories")?;
+/// Resolves once the backend was notified about a change, or failed to tell.
+///
+/// Never resolves for backends that cannot push notifications.
+async fn notification(backend: Arc<dyn ChainBackend>) {
+    if !backend.pushes_notifications() {
+        return std::future::pending().await;
+    }
 
-        if histories.len() != self.script_history.len() {
-            bail!(
-                "Expected {} history entries, received {}",
-                self.script_history.len(),
-                histories.len()
-            );
+    loop {
+        tokio::time::sleep(NOTIFICATION_POLL_INTERVAL).await;
+
+        let backend = backend.clone();
+        match tokio::task::spawn_blocking(move || backend.notified()).await {
+            Ok(Ok(false)) => {}
+            // failures are dealt with when updating
+            _ => return,
         }
+    }
+}
 
-        let scripts = self.script_history.keys().cloned();
-        let histories = histories.into_iter();
+/// The height and, if reported, the hash of the block that includes the
+/// transaction, if it is confirmed.
+fn inclusion(
+    txid: Txid,
+    history: &[HistoryEntry],
+) -> Result<Option<(BlockHeight, Option<BlockHash>)>> {
+    match history.iter().filter(|entry| entry.txid == txid).last() {
+        Some(entry) if entry.height > 0 => Ok(Some((
+            BlockHeight::from(u32::try_from(entry.height)?),
+            entry.block_hash,
+        ))),
+        _ => Ok(None),
+    }
+}
 
-        self.script_history = scripts.zip(histories).collect::<BTreeMap<_, _>>();
+fn status_from_history(
+    txid: Txid,
+    history: &[HistoryEntry],
+    latest_block_height: BlockHeight,
+) -> Result<ScriptStatus> {
+    let history_of_tx = history
+        .iter()
+        .filter(|entry| entry.txid == txid)
+        .collect::<Vec<_>>();
+
+    match history_of_tx.as_slice() {
+        [] => Ok(ScriptStatus::Unseen),
+        [remaining @ .., last] => {
+            if !remaining.is_empty() {
+                tracing::warn!("Found more than a single history entry for script. This is highly unexpected and those history entries will be ignored")
+            }
 
-        Ok(())
+            if last.height <= 0 {
+                Ok(ScriptStatus::InMempool)
+            } else {
+                Ok(ScriptStatus::Confirmed(
+                    Confirmed::from_inclusion_and_latest_block(
+                        u32::try_from(last.height)?,
+                        u32::from(latest_block_height),
+                    ),
+                ))
+            }
+        }
     }
 }
 
 impl EstimateFeeRate for Client {
     fn estimate_feerate(&self, target_block: usize) -> Result<FeeRate> {
-        // https://github.com/romanz/electrs/blob/f9cf5386d1b5de6769ee271df5eef324aa9491bc/src/rpc.rs#L213
-        // Returned estimated fees are per BTC/kb.
-        let fee_per_byte = self.electrum.estimate_fee(target_block)?;
-        // we do not expect fees being that high.
-        #[allow(clippy::cast_possible_truncation)]
-        Ok(FeeRate::from_btc_per_kvb(fee_per_byte as f32))
+        self.fee_estimator.estimate_feerate(target_block)
     }
 
     fn min_relay_fee(&self) -> Result<bitcoin::Amount> {
-        // https://github.com/romanz/electrs/blob/f9cf5386d1b5de6769ee271df5eef324aa9491bc/src/rpc.rs#L219
-        // Returned fee is in BTC/kb
-        let relay_fee = bitcoin::Amount::from_btc(self.electrum.relay_fee()?)?;
-        Ok(relay_fee)
+        self.fee_estimator.min_relay_fee()
     }
 }
 
+impl GetTransaction for Client {
+    fn get_tx(&self, txid: &Txid) -> Result<Option<Transaction>> {
+        self.backend.get_tx(txid)
+    }
+}
+
+/// The status of a watched transaction.
+///
+/// The status moves backwards if the block including the transaction is
+/// reorganized out of the blockchain, e.g. from confirmed to in mempool.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ScriptStatus {
     Unseen,
@@ -950,6 +1542,14 @@ impl ScriptStatus {
         }
     }
 
+    /// The number of confirmations, zero if the script is not confirmed.
+    pub fn confirmations(&self) -> u32 {
+        match self {
+            ScriptStatus::Confirmed(inner) => inner.confirmations(),
+            _ => 0,
+        }
+    }
+
     pub fn has_been_seen(&self) -> bool {
         matches!(self, ScriptStatus::InMempool | ScriptStatus::Confirmed(_))
     }
@@ -971,10 +1571,12 @@ impl fmt::Display for ScriptStatus {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::bitcoin::{PublicKey, TxLock};
+    use crate::bitcoin::backend::Changes;
+    use crate::bitcoin::{PublicKey, Recipient, TxLock};
     use crate::tracing_ext::capture_logs;
     use bitcoin::hashes::Hash;
     use proptest::prelude::*;
+    use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
     use tracing::level_filters::LevelFilter;
 
     #[test]
@@ -1015,7 +1617,8 @@ mod tests {
         let fee_rate = FeeRate::from_sat_per_vb(sat_per_vb);
 
         let relay_fee = bitcoin::Amount::ONE_SAT;
-        let is_fee = estimate_fee(weight, amount, fee_rate, relay_fee).unwrap();
+        let is_fee =
+            estimate_fee(weight, amount, fee_rate, relay_fee, &FeePolicy::default()).unwrap();
 
         // weight / 4.0 *  sat_per_vb
         let should_fee = bitcoin::Amount::from_sat(10_000);
@@ -1032,7 +1635,8 @@ mod tests {
         let fee_rate = FeeRate::from_sat_per_vb(sat_per_vb);
 
         let relay_fee = bitcoin::Amount::from_sat(100_000);
-        let is_fee = estimate_fee(weight, amount, fee_rate, relay_fee).unwrap();
+        let is_fee =
+            estimate_fee(weight, amount, fee_rate, relay_fee, &FeePolicy::default()).unwrap();
 
         // weight / 4.0 *  sat_per_vb would be smaller than relay fee hence we take min
         // relay fee
@@ -1050,7 +1654,8 @@ mod tests {
         let fee_rate = FeeRate::from_sat_per_vb(sat_per_vb);
 
         let relay_fee = bitcoin::Amount::ONE_SAT;
-        let is_fee = estimate_fee(weight, amount, fee_rate, relay_fee).unwrap();
+        let is_fee =
+            estimate_fee(weight, amount, fee_rate, relay_fee, &FeePolicy::default()).unwrap();
 
         // weight / 4.0 *  sat_per_vb would be greater than 3% hence we take max
         // relative fee.
@@ -1069,11 +1674,36 @@ mod tests {
         let fee_rate = FeeRate::from_sat_per_vb(sat_per_vb);
 
         let relay_fee = bitcoin::Amount::ONE_SAT;
-        let is_fee = estimate_fee(weight, amount, fee_rate, relay_fee).unwrap();
+        let is_fee =
+            estimate_fee(weight, amount, fee_rate, relay_fee, &FeePolicy::default()).unwrap();
 
         // weight / 4.0 *  sat_per_vb would be greater than 3% hence we take total
         // max allowed fee.
-        assert_eq!(is_fee.to_sat(), MAX_ABSOLUTE_TX_FEE.to_u64().unwrap());
+        assert_eq!(
+            is_fee.to_sat(),
+            FeePolicy::default().max_absolute_fee.to_sat()
+        );
+    }
+
+    #[test]
+    fn given_1mio_sat_and_1k_sats_per_vb_fees_and_higher_relative_max_should_not_hit_max() {
+        // 400 weight = 100 vbyte
+        let weight = 400;
+        let amount = bitcoin::Amount::from_sat(1_000_000);
+
+        let sat_per_vb = 1_000.0;
+        let fee_rate = FeeRate::from_sat_per_vb(sat_per_vb);
+
+        let relay_fee = bitcoin::Amount::ONE_SAT;
+        let fee_policy = FeePolicy {
+            max_relative_fee: dec!(0.15),
+            ..FeePolicy::default()
+        };
+        let is_fee = estimate_fee(weight, amount, fee_rate, relay_fee, &fee_policy).unwrap();
+
+        // 10% of the amount is below the configured relative max
+        let should_fee = bitcoin::Amount::from_sat(100_000);
+        assert_eq!(is_fee, should_fee);
     }
 
     proptest! {
@@ -1089,7 +1719,7 @@ mod tests {
             let fee_rate = FeeRate::from_sat_per_vb(sat_per_vb);
 
             let relay_fee = bitcoin::Amount::from_sat(relay_fee);
-            let _is_fee = estimate_fee(weight, amount, fee_rate, relay_fee).unwrap();
+            let _is_fee = estimate_fee(weight, amount, fee_rate, relay_fee, &FeePolicy::default()).unwrap();
 
         }
     }
@@ -1106,10 +1736,10 @@ mod tests {
             let fee_rate = FeeRate::from_sat_per_vb(sat_per_vb);
 
             let relay_fee = bitcoin::Amount::ONE_SAT;
-            let is_fee = estimate_fee(weight, amount, fee_rate, relay_fee).unwrap();
+            let is_fee = estimate_fee(weight, amount, fee_rate, relay_fee, &FeePolicy::default()).unwrap();
 
-            // weight / 4 * 1_000 is always lower than MAX_ABSOLUTE_TX_FEE
-            assert!(is_fee.to_sat() < MAX_ABSOLUTE_TX_FEE.to_u64().unwrap());
+            // weight / 4 * 1_000 is always lower than the default max absolute fee
+            assert!(is_fee.to_sat() < FeePolicy::default().max_absolute_fee.to_sat());
         }
     }
 
@@ -1125,10 +1755,10 @@ mod tests {
             let fee_rate = FeeRate::from_sat_per_vb(sat_per_vb);
 
             let relay_fee = bitcoin::Amount::ONE_SAT;
-            let is_fee = estimate_fee(weight, amount, fee_rate, relay_fee).unwrap();
+            let is_fee = estimate_fee(weight, amount, fee_rate, relay_fee, &FeePolicy::default()).unwrap();
 
-            // weight / 4 * 1_000  is always higher than MAX_ABSOLUTE_TX_FEE
-            assert!(is_fee.to_sat() >= MAX_ABSOLUTE_TX_FEE.to_u64().unwrap());
+            // weight / 4 * 1_000  is always higher than the default max absolute fee
+            assert!(is_fee.to_sat() >= FeePolicy::default().max_absolute_fee.to_sat());
         }
     }
 
@@ -1143,7 +1773,7 @@ mod tests {
             let fee_rate = FeeRate::from_sat_per_vb(sat_per_vb);
 
             let relay_fee = bitcoin::Amount::from_sat(1);
-            assert!(estimate_fee(weight, amount, fee_rate, relay_fee).is_err());
+            assert!(estimate_fee(weight, amount, fee_rate, relay_fee, &FeePolicy::default()).is_err());
 
         }
     }
@@ -1159,7 +1789,7 @@ mod tests {
             let fee_rate = FeeRate::from_sat_per_vb(1.0);
 
             let relay_fee = bitcoin::Amount::from_sat(relay_fee);
-            assert!(estimate_fee(weight, amount, fee_rate, relay_fee).is_err());
+            assert!(estimate_fee(weight, amount, fee_rate, relay_fee, &FeePolicy::default()).is_err());
         }
     }
 
@@ -1250,6 +1880,456 @@ mod tests {
         }
     }
 
+    #[test]
+    fn child_pays_the_missing_fee_of_the_parent() {
+        let fee_rate = FeeRate::from_sat_per_vb(10.0);
+
+        assert_eq!(cpfp_fee(fee_rate, 200, 200, 1100), Some(1100 + 1800));
+        assert_eq!(cpfp_fee(fee_rate, 200, 2000, 1100), None);
+        assert_eq!(cpfp_fee(fee_rate, 200, 5000, 1100), None);
+    }
+
+    #[tokio::test]
+    async fn withdrawal_spends_selected_utxos_with_given_fee_rate() {
+        let wallet = WalletBuilder::new(50_000).with_num_utxos(2).build();
+        let utxo = wallet.wallet.lock().await.list_unspent().unwrap()[0].outpoint;
+        let recipient = "bcrt1q08pfqpsyrt7acllzyjm8q5qsz5capvyahm49rw"
+            .parse::<Address>()
+            .unwrap();
+
+        let withdrawal = Withdrawal {
+            recipients: vec![Recipient {
+                address: recipient.clone(),
+                amount: Amount::from_sat(10_000),
+            }],
+            fee_rate: Some(Decimal::from(5)),
+            utxos: vec![utxo],
+            rbf: true,
+            ..Withdrawal::default()
+        };
+        let psbt = wallet.build_withdrawal(&withdrawal).await.unwrap();
+        let spent = psbt
+            .inputs
+            .iter()
+            .map(|input| input.witness_utxo.as_ref().unwrap().value)
+            .sum::<u64>();
+        let sent = psbt
+            .unsigned_tx
+            .output
+            .iter()
+            .map(|output| output.value)
+            .sum::<u64>();
+        let transaction = wallet.sign_and_finalize(psbt).await.unwrap();
+
+        assert_eq!(transaction.input.len(), 1);
+        assert_eq!(transaction.input[0].previous_output, utxo);
+        assert!(transaction.input[0].sequence.is_rbf());
+        assert!(transaction
+            .output
+            .iter()
+            .any(|output| output.script_pubkey == recipient.script_pubkey()
+                && output.value == 10_000));
+        assert!(spent - sent >= transaction.vsize() as u64 * 5);
+    }
+
+    #[tokio::test]
+    async fn withdrawal_without_recipients_drains_the_wallet() {
+        let wallet = WalletBuilder::new(50_000).with_num_utxos(2).build();
+        let drain_to = wallet.new_address().await.unwrap();
+
+        let withdrawal = Withdrawal {
+            drain_to: Some(drain_to.clone()),
+            ..Withdrawal::default()
+        };
+        let psbt = wallet.build_withdrawal(&withdrawal).await.unwrap();
+        let transaction = wallet.sign_and_finalize(psbt).await.unwrap();
+
+        assert_eq!(transaction.input.len(), 2);
+        assert_eq!(transaction.output.len(), 1);
+        assert_eq!(
+            transaction.output[0].script_pubkey,
+            drain_to.script_pubkey()
+        );
+    }
+
+    #[tokio::test]
+    async fn withdrawal_with_recipients_drains_the_rest_of_the_wallet() {
+        let wallet = WalletBuilder::new(50_000).with_num_utxos(3).build();
+        let recipient = Recipient {
+            address: wallet.new_address().await.unwrap(),
+            amount: Amount::from_sat(10_000),
+        };
+        let drain_to = wallet.new_address().await.unwrap();
+
+        let withdrawal = Withdrawal {
+            recipients: vec![recipient.clone()],
+            drain_to: Some(drain_to.clone()),
+            ..Withdrawal::default()
+        };
+        let psbt = wallet.build_withdrawal(&withdrawal).await.unwrap();
+        let transaction = wallet.sign_and_finalize(psbt).await.unwrap();
+
+        assert_eq!(transaction.input.len(), 3);
+        assert_eq!(transaction.output.len(), 2);
+        assert!(transaction.output.iter().any(|output| output.script_pubkey
+            == recipient.address.script_pubkey()
+            && output.value == recipient.amount.to_sat()));
+        assert!(transaction
+            .output
+            .iter()
+            .any(|output| output.script_pubkey == drain_to.script_pubkey()));
+    }
+
+    /// Creates a transaction of the wallet paying 10_000 sats to a foreign
+    /// address and returns the wallet knowing about it as unconfirmed.
+    async fn wallet_with_unconfirmed_transaction(
+        rbf: bool,
+    ) -> (
+        Wallet<bdk::database::MemoryDatabase, StaticFeeRate>,
+        Transaction,
+    ) {
+        let builder = || WalletBuilder::new(50_000).with_num_utxos(2);
+        let withdrawal = Withdrawal {
+            recipients: vec![Recipient {
+                address: "bcrt1q08pfqpsyrt7acllzyjm8q5qsz5capvyahm49rw"
+                    .parse()
+                    .unwrap(),
+                amount: Amount::from_sat(10_000),
+            }],
+            fee_rate: Some(Decimal::ONE),
+            rbf,
+            ..Withdrawal::default()
+        };
+
+        let wallet = builder().build();
+        let psbt = wallet.build_withdrawal(&withdrawal).await.unwrap();
+        let transaction = wallet.sign_and_finalize(psbt).await.unwrap();
+
+        let wallet = builder().with_unconfirmed(transaction.clone()).build();
+
+        (wallet, transaction)
+    }
+
+    fn fee_of_psbt(psbt: &PartiallySignedTransaction) -> u64 {
+        let spent = psbt
+            .inputs
+            .iter()
+            .map(|input| input.witness_utxo.as_ref().unwrap().value)
+            .sum::<u64>();
+        let sent = psbt
+            .unsigned_tx
+            .output
+            .iter()
+            .map(|output| output.value)
+            .sum::<u64>();
+
+        spent - sent
+    }
+
+    #[tokio::test]
+    async fn bump_fee_replaces_transactions_that_signal_rbf() {
+        let (wallet, transaction) = wallet_with_unconfirmed_transaction(true).await;
+        let original_fee = wallet.transaction_fee(transaction.txid()).await.unwrap();
+
+        let (fee_bump, psbt) = wallet
+            .bump_fee(transaction.txid(), Some(Decimal::from(5)))
+            .await
+            .unwrap();
+
+        assert_eq!(fee_bump, FeeBump::Replace);
+        assert!(psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .any(|input| transaction.input[0].previous_output == input.previous_output));
+        assert!(fee_of_psbt(&psbt) > original_fee.to_sat());
+        assert!(psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .all(|input| input.sequence.is_rbf()));
+    }
+
+    #[tokio::test]
+    async fn bump_fee_spends_own_output_of_transactions_without_rbf() {
+        let (wallet, transaction) = wallet_with_unconfirmed_transaction(false).await;
+        let parent_fee = wallet.transaction_fee(transaction.txid()).await.unwrap();
+
+        let (fee_bump, psbt) = wallet
+            .bump_fee(transaction.txid(), Some(Decimal::from(5)))
+            .await
+            .unwrap();
+        let child_fee = fee_of_psbt(&psbt);
+        let child = wallet.sign_and_finalize(psbt).await.unwrap();
+
+        assert_eq!(fee_bump, FeeBump::ChildPaysForParent);
+        assert_eq!(child.input.len(), 1);
+        assert_eq!(child.input[0].previous_output.txid, transaction.txid());
+        assert!(
+            parent_fee.to_sat() + child_fee >= 5 * (transaction.vsize() + child.vsize()) as u64
+        );
+    }
+
+    #[tokio::test]
+    async fn bump_fee_computes_the_fee_of_the_parent_from_the_spent_outputs() {
+        let (wallet, transaction) = wallet_with_unconfirmed_transaction(false).await;
+        let expected = wallet.transaction_fee(transaction.txid()).await.unwrap();
+
+        let bdk_wallet = wallet.wallet.lock().await;
+        let fee = wallet.fee_of(&bdk_wallet, &transaction).unwrap();
+
+        assert_eq!(fee, expected.to_sat());
+    }
+
+    #[derive(Default)]
+    struct Chain {
+        block_height: AtomicU32,
+        block_hashes: std::sync::Mutex<HashMap<u32, BlockHash>>,
+        history: std::sync::Mutex<Vec<HistoryEntry>>,
+        changed: AtomicBool,
+        history_requests: AtomicUsize,
+    }
+
+    /// Notifies about changes like an Electrum server would.
+    struct ChainStandIn {
+        chain: Arc<Chain>,
+    }
+
+    impl ChainStandIn {
+        fn new(chain: Arc<Chain>) -> Self {
+            Self { chain }
+        }
+    }
+
+    impl ChainBackend for ChainStandIn {
+        fn sync_with(&self, _: &dyn Fn(&AnyBlockchain) -> Result<()>) -> Result<()> {
+            bail!("not needed to monitor transactions")
+        }
+
+        fn broadcast(&self, _: &Transaction) -> Result<()> {
+            bail!("not needed to monitor transactions")
+        }
+
+        fn block_height(&self) -> Result<BlockHeight> {
+            Ok(BlockHeight::from(
+                self.chain.block_height.load(Ordering::SeqCst),
+            ))
+        }
+
+        fn block_hash(&self, height: BlockHeight) -> Result<BlockHash> {
+            let block_hashes = self.chain.block_hashes.lock().unwrap();
+
+            Ok(block_hashes
+                .get(&u32::from(height))
+                .copied()
+                .unwrap_or_else(BlockHash::all_zeros))
+        }
+
+        fn script_history(&self, watched: &[(Txid, Script)]) -> Result<Vec<Vec<HistoryEntry>>> {
+            self.chain.history_requests.fetch_add(1, Ordering::SeqCst);
+            let history = self.chain.history.lock().unwrap().clone();
+
+            Ok(watched.iter().map(|_| history.clone()).collect())
+        }
+
+        fn changes(&self, scripts: &[Script]) -> Result<Changes> {
+            let changed = self.chain.changed.swap(false, Ordering::SeqCst);
+
+            Ok(Changes {
+                block_height: self.block_height()?,
+                scripts: if changed { scripts.to_vec() } else { vec![] },
+            })
+        }
+
+        fn estimate_fee_rate(&self, _: usize) -> Result<FeeRate> {
+            bail!("not needed to monitor transactions")
+        }
+
+        fn min_relay_fee(&self) -> Result<bitcoin::Amount> {
+            bail!("not needed to monitor transactions")
+        }
+
+        fn get_tx(&self, _: &Txid) -> Result<Option<Transaction>> {
+            bail!("not needed to monitor transactions")
+        }
+    }
+
+    #[tokio::test]
+    async fn monitor_only_fetches_history_of_changed_scripts() {
+        let chain = Arc::new(Chain::default());
+        chain.block_height.store(100, Ordering::SeqCst);
+        let client = Client::new(
+            Box::new(ChainStandIn::new(chain.clone())),
+            Duration::from_millis(10),
+            &FeeEstimation::default(),
+        )
+        .await
+        .unwrap();
+        let txid = Txid::all_zeros();
+        let subscription = Subscription {
+            receiver: client.subscribe(txid, Script::new()),
+            finality_confirmations: 1,
+            txid,
+        };
+
+        // the monitor reports the status once it fetched the history
+        let mut receiver = subscription.receiver.clone();
+        tokio::time::timeout(Duration::from_secs(1), receiver.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*receiver.borrow(), ScriptStatus::Unseen);
+        assert_eq!(chain.history_requests.load(Ordering::SeqCst), 1);
+
+        *chain.history.lock().unwrap() = vec![HistoryEntry {
+            txid,
+            height: 101,
+            block_hash: None,
+        }];
+        chain.block_height.store(101, Ordering::SeqCst);
+        chain.changed.store(true, Ordering::SeqCst);
+
+        tokio::time::timeout(Duration::from_secs(1), subscription.wait_until_final())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(chain.history_requests.load(Ordering::SeqCst), 2);
+
+        drop(receiver);
+        drop(subscription);
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while !client.subscriptions.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("monitor to drop the subscription without receivers");
+    }
+
+    #[tokio::test]
+    async fn status_of_script_is_fetched_at_most_once_per_sync_interval() {
+        let chain = Arc::new(Chain::default());
+        chain.block_height.store(100, Ordering::SeqCst);
+        let client = Client::new(
+            Box::new(ChainStandIn::new(chain.clone())),
+            Duration::from_secs(60 * 60),
+            &FeeEstimation::default(),
+        )
+        .await
+        .unwrap();
+        let tx = (Txid::all_zeros(), Script::new());
+
+        assert_eq!(client.status_of_script(&tx).unwrap(), ScriptStatus::Unseen);
+        assert_eq!(client.status_of_script(&tx).unwrap(), ScriptStatus::Unseen);
+        assert_eq!(chain.history_requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn monitor_detects_reorgs_by_block_hash() {
+        let txid = Txid::all_zeros();
+        let chain = Arc::new(Chain::default());
+        chain.block_height.store(101, Ordering::SeqCst);
+        chain
+            .block_hashes
+            .lock()
+            .unwrap()
+            .insert(101, BlockHash::hash(b"block"));
+        *chain.history.lock().unwrap() = vec![HistoryEntry {
+            txid,
+            height: 101,
+            block_hash: None,
+        }];
+        let client = Client::new(
+            Box::new(ChainStandIn::new(chain.clone())),
+            Duration::from_millis(10),
+            &FeeEstimation::default(),
+        )
+        .await
+        .unwrap();
+        let subscription = Subscription {
+            receiver: client.subscribe(txid, Script::new()),
+            finality_confirmations: 1,
+            txid,
+        };
+
+        tokio::time::timeout(Duration::from_secs(1), subscription.wait_until_final())
+            .await
+            .unwrap()
+            .unwrap();
+
+        // the block is replaced without notifying about the script
+        chain
+            .block_hashes
+            .lock()
+            .unwrap()
+            .insert(101, BlockHash::hash(b"competing block"));
+        *chain.history.lock().unwrap() = vec![HistoryEntry {
+            txid,
+            height: 0,
+            block_hash: None,
+        }];
+        chain.block_height.store(102, Ordering::SeqCst);
+
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            subscription.wait_until(|status| status == &ScriptStatus::InMempool),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn monitor_detects_reorgs_by_block_hash_reported_with_the_history() {
+        let txid = Txid::all_zeros();
+        let chain = Arc::new(Chain::default());
+        chain.block_height.store(101, Ordering::SeqCst);
+        // the block at the height of the transaction was already replaced when
+        // its history was fetched
+        chain
+            .block_hashes
+            .lock()
+            .unwrap()
+            .insert(101, BlockHash::hash(b"competing block"));
+        *chain.history.lock().unwrap() = vec![HistoryEntry {
+            txid,
+            height: 101,
+            block_hash: Some(BlockHash::hash(b"block")),
+        }];
+        let client = Client::new(
+            Box::new(ChainStandIn::new(chain.clone())),
+            Duration::from_millis(10),
+            &FeeEstimation::default(),
+        )
+        .await
+        .unwrap();
+        let subscription = Subscription {
+            receiver: client.subscribe(txid, Script::new()),
+            finality_confirmations: 1,
+            txid,
+        };
+
+        tokio::time::timeout(Duration::from_secs(1), subscription.wait_until_final())
+            .await
+            .unwrap()
+            .unwrap();
+
+        *chain.history.lock().unwrap() = vec![HistoryEntry {
+            txid,
+            height: 0,
+            block_hash: None,
+        }];
+        chain.block_height.store(102, Ordering::SeqCst);
+
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            subscription.wait_until(|status| status == &ScriptStatus::InMempool),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    }
+
     #[test]
     fn printing_status_change_doesnt_spam_on_same_status() {
         let writer = capture_logs(LevelFilter::DEBUG);
@@ -1281,6 +2361,20 @@ DEBUG swap::bitcoin::wallet: Bitcoin transaction status changed txid=00000000000
         )
     }
 
+    #[test]
+    fn printing_status_change_warns_about_lost_confirmations() {
+        let writer = capture_logs(LevelFilter::DEBUG);
+
+        let tx = Txid::all_zeros();
+        print_status_change(tx, Some(confs(2)), ScriptStatus::InMempool);
+
+        assert_eq!(
+            writer.captured(),
+            r" WARN swap::bitcoin::wallet: Bitcoin transaction lost confirmations in a reorg txid=0000000000000000000000000000000000000000000000000000000000000000 new_status=in mempool old_status=confirmed with 2 blocks
+"
+        )
+    }
+
     fn confs(confirmations: u32) -> ScriptStatus {
         ScriptStatus::from_confirmations(confirmations)
     }
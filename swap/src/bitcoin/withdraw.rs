@@ -0,0 +1,179 @@
+use crate::bitcoin::{Address, Amount, PartiallySignedTransaction};
+use ::bitcoin::consensus::encode::serialize;
+use ::bitcoin::OutPoint;
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose;
+use base64::Engine;
+use bdk::FeeRate;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// A transaction spending funds of the internal Bitcoin wallet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Withdrawal {
+    pub recipients: Vec<Recipient>,
+    /// Receives whatever is left of the wallet, or of the selected UTXOs, after
+    /// paying the recipients and the fee.
+    pub drain_to: Option<Address>,
+    /// The fee rate in sat/vB, estimated if not given.
+    pub fee_rate: Option<Decimal>,
+    /// Spend only these outputs instead of letting the wallet select them.
+    pub utxos: Vec<OutPoint>,
+    /// Signal replaceability of the transaction (BIP-125).
+    pub rbf: bool,
+}
+
+impl Withdrawal {
+    /// Combines the options of the `withdraw-btc` commands into a withdrawal.
+    ///
+    /// `address` receives `amount` if given, and everything that is left
+    /// otherwise.
+    pub fn new(
+        address: Option<Address>,
+        amount: Option<Amount>,
+        outputs: Vec<Recipient>,
+        fee_rate: Option<Decimal>,
+        utxos: Vec<OutPoint>,
+        rbf: bool,
+    ) -> Result<Self> {
+        let mut recipients = outputs;
+        let drain_to = match (address, amount) {
+            (Some(address), Some(amount)) => {
+                recipients.push(Recipient { address, amount });
+                None
+            }
+            (Some(address), None) => Some(address),
+            (None, Some(_)) => bail!("An amount can only be given together with an address"),
+            (None, None) => None,
+        };
+
+        if recipients.is_empty() && drain_to.is_none() {
+            bail!("Nothing to withdraw, give an address or at least one output")
+        }
+
+        Ok(Self {
+            recipients,
+            drain_to,
+            fee_rate,
+            utxos,
+            rbf,
+        })
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = &Address> {
+        self.recipients
+            .iter()
+            .map(|recipient| &recipient.address)
+            .chain(self.drain_to.as_ref())
+    }
+
+    pub fn bdk_fee_rate(&self) -> Result<Option<FeeRate>> {
+        self.fee_rate.map(to_fee_rate).transpose()
+    }
+}
+
+/// A payment of an amount to an address, given as `<address>:<amount>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipient {
+    pub address: Address,
+    pub amount: Amount,
+}
+
+impl FromStr for Recipient {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (address, amount) = s
+            .split_once(':')
+            .context("Output must be given as <address>:<amount>")?;
+
+        let address = Address::from_str(address)
+            .with_context(|| format!("Invalid Bitcoin address {}", address))?;
+        let amount = Amount::from_str(amount).with_context(|| {
+            format!(
+                "Invalid amount {}, amounts need a denomination, e.g. `0.1 BTC`",
+                amount
+            )
+        })?;
+
+        Ok(Self { address, amount })
+    }
+}
+
+/// Encodes a PSBT in base64, the format most wallets import.
+pub fn psbt_to_base64(psbt: &PartiallySignedTransaction) -> String {
+    general_purpose::STANDARD.encode(serialize(psbt))
+}
+
+/// Converts a fee rate in sat/vB, as given on the command line.
+pub fn to_fee_rate(sats_per_vb: Decimal) -> Result<FeeRate> {
+    let sats_per_vb = sats_per_vb.to_f32().context("Fee rate is out of range")?;
+
+    Ok(FeeRate::from_sat_per_vb(sats_per_vb))
+}
+
+/// Parses a fee rate in sat/vB.
+pub fn parse_fee_rate(s: &str) -> Result<Decimal> {
+    let fee_rate = Decimal::from_str(s).context("Fee rate must be a number of sat/vB")?;
+
+    if fee_rate < Decimal::ONE {
+        bail!("Fee rate must be at least 1 sat/vB")
+    }
+
+    Ok(fee_rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "bcrt1q08pfqpsyrt7acllzyjm8q5qsz5capvyahm49rw";
+
+    #[test]
+    fn parses_recipients() {
+        let recipient = format!("{}:0.001 BTC", ADDRESS)
+            .parse::<Recipient>()
+            .unwrap();
+
+        assert_eq!(
+            recipient,
+            Recipient {
+                address: ADDRESS.parse().unwrap(),
+                amount: Amount::from_sat(100_000),
+            }
+        );
+        assert!(format!("{}:0.001", ADDRESS).parse::<Recipient>().is_err());
+        assert!(ADDRESS.parse::<Recipient>().is_err());
+    }
+
+    #[test]
+    fn rejects_fee_rates_below_one_sat_per_vbyte() {
+        assert_eq!(parse_fee_rate("2.5").unwrap(), Decimal::new(25, 1));
+        assert!(parse_fee_rate("0.5").is_err());
+        assert!(parse_fee_rate("fast").is_err());
+    }
+
+    #[test]
+    fn address_without_amount_receives_the_rest() {
+        let address = Address::from_str(ADDRESS).unwrap();
+        let output = Recipient {
+            address: address.clone(),
+            amount: Amount::from_sat(10_000),
+        };
+
+        let withdrawal = Withdrawal::new(
+            Some(address.clone()),
+            None,
+            vec![output.clone()],
+            None,
+            vec![],
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(withdrawal.recipients, vec![output]);
+        assert_eq!(withdrawal.drain_to, Some(address));
+        assert!(Withdrawal::new(None, None, vec![], None, vec![], false).is_err());
+    }
+}
@@ -1,15 +1,25 @@
+pub mod abort;
+pub mod address_book;
 mod behaviour;
 pub mod cancel_and_refund;
 pub mod command;
+pub mod config;
 mod event_loop;
+pub mod external_funding;
 mod list_sellers;
+pub mod lock_confirmation;
+pub mod reference_price;
+pub mod seller_identity;
 pub mod tracing;
 pub mod transport;
 
+pub use abort::abort;
 pub use behaviour::{Behaviour, OutEvent};
-pub use cancel_and_refund::{cancel, cancel_and_refund, refund};
-pub use event_loop::{EventLoop, EventLoopHandle};
-pub use list_sellers::{list_sellers, Seller, Status as SellerStatus};
+pub use cancel_and_refund::{cancel, cancel_and_refund, dry_run, refund};
+pub use event_loop::{EventLoop, EventLoopController, EventLoopHandle};
+pub use list_sellers::{
+    combined_quote, list_sellers, select_sellers, Seller, Status as SellerStatus,
+};
 
 #[cfg(test)]
 mod tests {
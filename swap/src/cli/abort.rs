@@ -0,0 +1,54 @@
+use crate::bitcoin::Wallet;
+use crate::protocol::bob::BobState;
+use crate::protocol::Database;
+use anyhow::{bail, Result};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Aborts a swap for which no Bitcoin has been locked yet.
+pub async fn abort(
+    swap_id: Uuid,
+    bitcoin_wallet: Arc<Wallet>,
+    db: Arc<dyn Database>,
+) -> Result<BobState> {
+    let state = db.get_state(swap_id).await?.try_into()?;
+
+    match &state {
+        BobState::Started { .. } => {}
+        BobState::SwapSetupCompleted(state2) => {
+            // The lock transaction might have been published right before the swap was
+            // interrupted, in which case the swap has to be resumed or refunded instead.
+            let tx_lock_status = bitcoin_wallet.status_of_script(state2.tx_lock()).await?;
+            if tx_lock_status.has_been_seen() {
+                bail!(
+                    "Cannot abort swap {} because its Bitcoin lock transaction has already been published ({}). Resume the swap instead.",
+                    swap_id,
+                    tx_lock_status
+                )
+            }
+        }
+        BobState::BtcLocked { .. }
+        | BobState::XmrLockProofReceived { .. }
+        | BobState::XmrLocked(_)
+        | BobState::EncSigSent(_)
+        | BobState::BtcRedeemed(_)
+        | BobState::CancelTimelockExpired(_)
+        | BobState::BtcCancelled(_)
+        | BobState::BtcRefunded(_)
+        | BobState::XmrRedeemed { .. }
+        | BobState::BtcPunished { .. }
+        | BobState::SafelyAborted => bail!(
+            "Cannot abort swap {} because it is in state {}, only swaps that have not locked any Bitcoin can be aborted.",
+            swap_id,
+            state
+        ),
+    }
+
+    tracing::info!(%swap_id, "Manually aborting swap");
+
+    let state = BobState::SafelyAborted;
+    db.insert_latest_state(swap_id, state.clone().into())
+        .await?;
+
+    Ok(state)
+}
@@ -0,0 +1,232 @@
+use crate::libp2p_ext::MultiAddrExt;
+use crate::protocol::bob::BobState;
+use crate::protocol::{Database, State};
+use anyhow::{Context, Result};
+use libp2p::{Multiaddr, PeerId};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::time::Duration;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// A seller from the address book.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownSeller {
+    pub peer_id: PeerId,
+    pub name: Option<String>,
+    pub addresses: Vec<Multiaddr>,
+    /// The agent version the seller advertised when we last connected to it.
+    pub agent_version: Option<String>,
+    pub stats: SellerStats,
+}
+
+/// How a seller behaved in our past swaps with it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SellerStats {
+    pub completed: u32,
+    pub refunded: u32,
+    pub punished: u32,
+    /// The average time from locking the Bitcoin until the seller locked the
+    /// Monero.
+    pub avg_time_to_xmr_lock: Option<Duration>,
+    /// When we last interacted with the seller in a swap.
+    pub last_seen: Option<OffsetDateTime>,
+}
+
+impl SellerStats {
+    pub fn outcomes(&self) -> String {
+        format!(
+            "{} completed, {} refunded, {} punished",
+            self.completed, self.refunded, self.punished
+        )
+    }
+
+    pub fn avg_time_to_xmr_lock(&self) -> String {
+        self.avg_time_to_xmr_lock
+            .map_or_else(|| "-".to_owned(), format_duration)
+    }
+
+    pub fn last_seen(&self) -> String {
+        self.last_seen.map_or_else(
+            || "never".to_owned(),
+            |last_seen| {
+                format!(
+                    "{} {:02}:{:02} UTC",
+                    last_seen.date(),
+                    last_seen.hour(),
+                    last_seen.minute()
+                )
+            },
+        )
+    }
+}
+
+/// Adds the seller at the given address to the address book.
+pub async fn add_seller(
+    db: &(dyn Database + Send + Sync),
+    address: Multiaddr,
+    name: Option<String>,
+) -> Result<PeerId> {
+    let peer_id = address
+        .extract_peer_id()
+        .context("Seller address must contain peer ID")?;
+
+    db.insert_address(peer_id, address).await?;
+    db.insert_seller(peer_id, name).await?;
+
+    Ok(peer_id)
+}
+
+/// Loads the sellers of the address book together with the statistics of our
+/// swaps with them.
+pub async fn known_sellers(db: &(dyn Database + Send + Sync)) -> Result<Vec<KnownSeller>> {
+    let mut stats = seller_stats(db.get_seller_history().await?);
+
+    let mut sellers = Vec::new();
+    for (peer_id, name) in db.get_sellers().await? {
+        sellers.push(KnownSeller {
+            peer_id,
+            name,
+            addresses: db.get_addresses(peer_id).await?,
+            agent_version: db.get_seller_agent_version(peer_id).await?,
+            stats: stats.remove(&peer_id).unwrap_or_default(),
+        });
+    }
+
+    Ok(sellers)
+}
+
+/// Computes the statistics of each seller from the history of all swaps, in
+/// the order the states were entered.
+pub fn seller_stats(
+    history: impl IntoIterator<Item = (PeerId, Uuid, OffsetDateTime, State)>,
+) -> HashMap<PeerId, SellerStats> {
+    #[derive(Default)]
+    struct Swap {
+        seller: Option<PeerId>,
+        btc_locked_at: Option<OffsetDateTime>,
+        xmr_locked_at: Option<OffsetDateTime>,
+        last_seen: Option<OffsetDateTime>,
+        state: Option<BobState>,
+    }
+
+    let mut swaps = HashMap::<Uuid, Swap>::new();
+    for (seller, swap_id, entered_at, state) in history {
+        let state = match state {
+            State::Bob(state) => state,
+            State::Alice(_) => continue,
+        };
+        let swap = swaps.entry(swap_id).or_default();
+
+        match state {
+            BobState::BtcLocked { .. } => {
+                swap.btc_locked_at.get_or_insert(entered_at);
+            }
+            BobState::XmrLockProofReceived { .. } => {
+                swap.xmr_locked_at.get_or_insert(entered_at);
+            }
+            _ => {}
+        }
+        swap.seller = Some(seller);
+        swap.last_seen = Some(entered_at);
+        swap.state = Some(state);
+    }
+
+    let mut stats = HashMap::<PeerId, SellerStats>::new();
+    let mut times_to_xmr_lock = HashMap::<PeerId, Vec<Duration>>::new();
+    for swap in swaps.into_values() {
+        let seller = match swap.seller {
+            Some(seller) => seller,
+            None => continue,
+        };
+        let seller_stats = stats.entry(seller).or_default();
+
+        match swap.state {
+            Some(BobState::XmrRedeemed { .. }) => seller_stats.completed += 1,
+            Some(BobState::BtcRefunded(_)) => seller_stats.refunded += 1,
+            Some(BobState::BtcPunished { .. }) => seller_stats.punished += 1,
+            _ => {}
+        }
+        seller_stats.last_seen = seller_stats.last_seen.max(swap.last_seen);
+
+        if let (Some(btc_locked_at), Some(xmr_locked_at)) = (swap.btc_locked_at, swap.xmr_locked_at)
+        {
+            if let Ok(time_to_xmr_lock) = Duration::try_from(xmr_locked_at - btc_locked_at) {
+                times_to_xmr_lock
+                    .entry(seller)
+                    .or_default()
+                    .push(time_to_xmr_lock);
+            }
+        }
+    }
+
+    for (seller, times) in times_to_xmr_lock {
+        if let Some(seller_stats) = stats.get_mut(&seller) {
+            let total = times.iter().sum::<Duration>();
+            seller_stats.avg_time_to_xmr_lock = Some(total / times.len() as u32);
+        }
+    }
+
+    stats
+}
+
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+
+    match minutes {
+        0 => format!("{}s", duration.as_secs()),
+        minutes if minutes < 60 => format!("{}m {}s", minutes, duration.as_secs() % 60),
+        minutes => format!("{}h {}m", minutes / 60, minutes % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes: i64) -> OffsetDateTime {
+        OffsetDateTime::UNIX_EPOCH + time::Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn counts_outcomes_and_last_seen_per_seller() {
+        let seller = PeerId::random();
+        let other_seller = PeerId::random();
+        let (swap_1, swap_2, swap_3) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        let stats = seller_stats(vec![
+            (seller, swap_1, at(0), State::Bob(BobState::SafelyAborted)),
+            (
+                seller,
+                swap_2,
+                at(10),
+                State::Bob(BobState::BtcPunished {
+                    tx_lock_id: bitcoin::Txid::default(),
+                }),
+            ),
+            (
+                other_seller,
+                swap_3,
+                at(20),
+                State::Bob(BobState::SafelyAborted),
+            ),
+        ]);
+
+        assert_eq!(
+            stats[&seller],
+            SellerStats {
+                punished: 1,
+                last_seen: Some(at(10)),
+                ..SellerStats::default()
+            }
+        );
+        assert_eq!(stats[&other_seller].last_seen, Some(at(20)));
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m 5s");
+        assert_eq!(format_duration(Duration::from_secs(3 * 3600 + 60)), "3h 1m");
+    }
+}
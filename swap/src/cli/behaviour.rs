@@ -12,6 +12,7 @@ use libp2p::request_response::{RequestId, ResponseChannel};
 use libp2p::{identity, NetworkBehaviour, PeerId};
 use std::sync::Arc;
 use std::time::Duration;
+use uuid::Uuid;
 
 #[derive(Debug)]
 pub enum OutEvent {
@@ -19,7 +20,10 @@ pub enum OutEvent {
         id: RequestId,
         response: BidQuote,
     },
-    SwapSetupCompleted(Box<Result<State2>>),
+    SwapSetupCompleted {
+        swap_id: Uuid,
+        result: Box<Result<State2>>,
+    },
     TransferProofReceived {
         msg: Box<transfer_proof::Request>,
         channel: ResponseChannel<()>,
@@ -28,6 +32,11 @@ pub enum OutEvent {
     EncryptedSignatureAcknowledged {
         id: RequestId,
     },
+    /// A peer told us its agent version over the identify protocol.
+    Identified {
+        peer: PeerId,
+        agent_version: String,
+    },
     AllRedialAttemptsExhausted {
         peer: PeerId,
     },
@@ -76,7 +85,6 @@ pub struct Behaviour {
 
 impl Behaviour {
     pub fn new(
-        alice: PeerId,
         env_config: env::Config,
         bitcoin_wallet: Arc<bitcoin::Wallet>,
         identify_params: (identity::Keypair, XmrBtcNamespace),
@@ -91,7 +99,7 @@ impl Behaviour {
             swap_setup: bob::Behaviour::new(env_config, bitcoin_wallet),
             transfer_proof: transfer_proof::bob(),
             encrypted_signature: encrypted_signature::bob(),
-            redial: redial::Behaviour::new(alice, Duration::from_secs(2)),
+            redial: redial::Behaviour::new(Duration::from_secs(2)),
             ping: Ping::new(PingConfig::new().with_keep_alive(true)),
             identify: Identify::new(identifyConfig),
         }
@@ -112,7 +120,13 @@ impl From<PingEvent> for OutEvent {
 }
 
 impl From<IdentifyEvent> for OutEvent {
-    fn from(_: IdentifyEvent) -> Self {
-        OutEvent::Other
+    fn from(event: IdentifyEvent) -> Self {
+        match event {
+            IdentifyEvent::Received { peer_id, info } => OutEvent::Identified {
+                peer: peer_id,
+                agent_version: info.agent_version,
+            },
+            _ => OutEvent::Other,
+        }
     }
 }
@@ -1,9 +1,10 @@
-use crate::bitcoin::wallet::Subscription;
-use crate::bitcoin::{parse_rpc_error_code, RpcErrorCode, Wallet};
-use crate::protocol::bob::BobState;
+use crate::bitcoin::wallet::{ScriptStatus, Subscription};
+use crate::bitcoin::{parse_rpc_error_code, Amount, CancelTimelock, RpcErrorCode, Wallet};
+use crate::protocol::bob::{BobState, State6};
 use crate::protocol::Database;
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use bitcoin::Txid;
+use std::fmt;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -12,8 +13,10 @@ pub async fn cancel_and_refund(
     bitcoin_wallet: Arc<Wallet>,
     db: Arc<dyn Database>,
 ) -> Result<BobState> {
-    if let Err(err) = cancel(swap_id, bitcoin_wallet.clone(), db.clone()).await {
-        tracing::info!(%err, "Could not submit cancel transaction");
+    match cancel(swap_id, bitcoin_wallet.clone(), db.clone()).await {
+        // The refund is only accepted once the backend has seen the cancel transaction
+        Ok((_, subscription, _)) => subscription.wait_until_seen().await?,
+        Err(err) => tracing::info!(%err, "Could not submit cancel transaction"),
     };
 
     let state = match refund(swap_id, bitcoin_wallet, db).await {
@@ -31,27 +34,15 @@ pub async fn cancel(
     db: Arc<dyn Database>,
 ) -> Result<(Txid, Subscription, BobState)> {
     let state = db.get_state(swap_id).await?.try_into()?;
+    let state6 = cancellable_state(swap_id, state)?;
 
-    let state6 = match state {
-        BobState::BtcLocked { state3, .. } => state3.cancel(),
-        BobState::XmrLockProofReceived { state, .. } => state.cancel(),
-        BobState::XmrLocked(state4) => state4.cancel(),
-        BobState::EncSigSent(state4) => state4.cancel(),
-        BobState::CancelTimelockExpired(state6) => state6,
-        BobState::BtcRefunded(state6) => state6,
-        BobState::BtcCancelled(state6) => state6,
-
-        BobState::Started { .. }
-        | BobState::SwapSetupCompleted(_)
-        | BobState::BtcRedeemed(_)
-        | BobState::XmrRedeemed { .. }
-        | BobState::BtcPunished { .. }
-        | BobState::SafelyAborted => bail!(
-            "Cannot cancel swap {} because it is in state {} which is not refundable.",
-            swap_id,
-            state
-        ),
-    };
+    let transactions = RefundTransactions::new(&state6, bitcoin_wallet.as_ref()).await?;
+    if let valid @ (Validity::InBlocks(_) | Validity::AfterLockConfirmed) =
+        transactions.cancel.valid
+    {
+        return Err(NotYetValid::Cancel(valid))
+            .with_context(|| format!("Cannot cancel swap {}", swap_id));
+    }
 
     tracing::info!(%swap_id, "Manually cancelling swap");
 
@@ -83,6 +74,9 @@ pub async fn refund(
     db: Arc<dyn Database>,
 ) -> Result<BobState> {
     let state = db.get_state(swap_id).await?.try_into()?;
+    // We published the cancel transaction ourselves, the backend might just not
+    // have seen it yet
+    let cancel_published = matches!(state, BobState::BtcCancelled(_));
 
     let state6 = match state {
         BobState::BtcLocked { state3, .. } => state3.cancel(),
@@ -104,6 +98,14 @@ pub async fn refund(
         ),
     };
 
+    if !cancel_published {
+        let transactions = RefundTransactions::new(&state6, bitcoin_wallet.as_ref()).await?;
+        if transactions.cancel.valid != Validity::Published {
+            return Err(NotYetValid::Refund(transactions.cancel.valid))
+                .with_context(|| format!("Cannot refund swap {}", swap_id));
+        }
+    }
+
     tracing::info!(%swap_id, "Manually refunding swap");
     state6.publish_refund_btc(bitcoin_wallet.as_ref()).await?;
 
@@ -113,3 +115,224 @@ pub async fn refund(
 
     Ok(state)
 }
+
+/// Builds the cancel and refund transactions of a swap without publishing
+/// them.
+pub async fn dry_run(
+    swap_id: Uuid,
+    bitcoin_wallet: Arc<Wallet>,
+    db: Arc<dyn Database>,
+) -> Result<RefundTransactions> {
+    let state = db.get_state(swap_id).await?.try_into()?;
+    let state6 = cancellable_state(swap_id, state)?;
+
+    RefundTransactions::new(&state6, bitcoin_wallet.as_ref()).await
+}
+
+fn cancellable_state(swap_id: Uuid, state: BobState) -> Result<State6> {
+    let state6 = match state {
+        BobState::BtcLocked { state3, .. } => state3.cancel(),
+        BobState::XmrLockProofReceived { state, .. } => state.cancel(),
+        BobState::XmrLocked(state4) => state4.cancel(),
+        BobState::EncSigSent(state4) => state4.cancel(),
+        BobState::CancelTimelockExpired(state6) => state6,
+        BobState::BtcRefunded(state6) => state6,
+        BobState::BtcCancelled(state6) => state6,
+
+        BobState::Started { .. }
+        | BobState::SwapSetupCompleted(_)
+        | BobState::BtcRedeemed(_)
+        | BobState::XmrRedeemed { .. }
+        | BobState::BtcPunished { .. }
+        | BobState::SafelyAborted => bail!(
+            "Cannot cancel swap {} because it is in state {} which is not refundable.",
+            swap_id,
+            state
+        ),
+    };
+
+    Ok(state6)
+}
+
+/// The cancel and refund transactions of a swap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefundTransactions {
+    pub cancel: TxSummary,
+    pub refund: TxSummary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxSummary {
+    pub txid: Txid,
+    pub fee: Amount,
+    pub valid: Validity,
+}
+
+impl RefundTransactions {
+    async fn new(state6: &State6, bitcoin_wallet: &Wallet) -> Result<Self> {
+        let tx_cancel = state6.signed_cancel_transaction()?;
+        let tx_refund = state6.signed_refund_transaction()?;
+
+        let tx_lock_status = bitcoin_wallet.status_of_script(state6.tx_lock()).await?;
+        let tx_cancel_status = bitcoin_wallet.status_of_script(&state6.tx_cancel()).await?;
+        let tx_refund_status = bitcoin_wallet.status_of_script(&state6.tx_refund()).await?;
+
+        let cancel_valid =
+            cancel_validity(state6.cancel_timelock(), tx_lock_status, tx_cancel_status);
+        let refund_valid = refund_validity(cancel_valid, tx_refund_status);
+
+        Ok(Self {
+            cancel: TxSummary {
+                txid: tx_cancel.txid(),
+                fee: state6.tx_cancel_fee,
+                valid: cancel_valid,
+            },
+            refund: TxSummary {
+                txid: tx_refund.txid(),
+                fee: state6.tx_refund_fee,
+                valid: refund_valid,
+            },
+        })
+    }
+}
+
+/// When a transaction can be published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Validity {
+    /// The transaction has already been published.
+    Published,
+    /// The transaction can be published right away.
+    Now,
+    /// The timelock expires once this many more blocks are mined.
+    InBlocks(u32),
+    /// The timelock only starts once the Bitcoin lock transaction is
+    /// confirmed.
+    AfterLockConfirmed,
+}
+
+impl fmt::Display for Validity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Validity::Published => write!(f, "already published"),
+            Validity::Now => write!(f, "now"),
+            Validity::InBlocks(blocks) => write!(f, "in {} blocks", blocks),
+            Validity::AfterLockConfirmed => {
+                write!(
+                    f,
+                    "once the lock transaction is confirmed and the cancel timelock expired"
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum NotYetValid {
+    #[error(
+        "The cancel timelock has not expired yet, the cancel transaction can be published {0}"
+    )]
+    Cancel(Validity),
+    #[error("The cancel transaction has to be published before the refund transaction, it can be published {0}")]
+    Refund(Validity),
+}
+
+fn cancel_validity(
+    cancel_timelock: CancelTimelock,
+    tx_lock_status: ScriptStatus,
+    tx_cancel_status: ScriptStatus,
+) -> Validity {
+    if tx_cancel_status.has_been_seen() {
+        return Validity::Published;
+    }
+
+    match tx_lock_status {
+        ScriptStatus::Confirmed(confirmed) => {
+            let cancel_timelock = u32::from(cancel_timelock);
+
+            match cancel_timelock.checked_sub(confirmed.confirmations()) {
+                Some(blocks) if blocks > 0 => Validity::InBlocks(blocks),
+                _ => Validity::Now,
+            }
+        }
+        ScriptStatus::Unseen | ScriptStatus::InMempool | ScriptStatus::Retrying => {
+            Validity::AfterLockConfirmed
+        }
+    }
+}
+
+/// The refund transaction spends the cancel transaction and can be published
+/// as soon as the cancel transaction is.
+fn refund_validity(cancel_valid: Validity, tx_refund_status: ScriptStatus) -> Validity {
+    if tx_refund_status.has_been_seen() {
+        return Validity::Published;
+    }
+
+    match cancel_valid {
+        Validity::Published => Validity::Now,
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::wallet::Confirmed;
+
+    #[test]
+    fn cancel_is_valid_once_lock_has_enough_confirmations() {
+        let cancel_timelock = CancelTimelock::new(10);
+
+        assert_eq!(
+            cancel_validity(
+                cancel_timelock,
+                ScriptStatus::InMempool,
+                ScriptStatus::Unseen
+            ),
+            Validity::AfterLockConfirmed
+        );
+        assert_eq!(
+            cancel_validity(
+                cancel_timelock,
+                ScriptStatus::Confirmed(Confirmed::new(2)),
+                ScriptStatus::Unseen
+            ),
+            Validity::InBlocks(7)
+        );
+        assert_eq!(
+            cancel_validity(
+                cancel_timelock,
+                ScriptStatus::Confirmed(Confirmed::new(9)),
+                ScriptStatus::Unseen
+            ),
+            Validity::Now
+        );
+        assert_eq!(
+            cancel_validity(
+                cancel_timelock,
+                ScriptStatus::Confirmed(Confirmed::new(20)),
+                ScriptStatus::InMempool
+            ),
+            Validity::Published
+        );
+    }
+
+    #[test]
+    fn refund_is_valid_once_cancel_is_published() {
+        assert_eq!(
+            refund_validity(Validity::InBlocks(3), ScriptStatus::Unseen),
+            Validity::InBlocks(3)
+        );
+        assert_eq!(
+            refund_validity(Validity::Now, ScriptStatus::Unseen),
+            Validity::Now
+        );
+        assert_eq!(
+            refund_validity(Validity::Published, ScriptStatus::Unseen),
+            Validity::Now
+        );
+        assert_eq!(
+            refund_validity(Validity::Published, ScriptStatus::InMempool),
+            Validity::Published
+        );
+    }
+}
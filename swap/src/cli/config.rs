@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// The optional config file of the CLI, read from the data directory.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub pinned_sellers: Vec<PinnedSeller>,
+}
+
+/// A seller that is expected to be reachable at the given address.
+///
+/// Swapping with a different peer at this address is refused.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct PinnedSeller {
+    /// The address of the seller, the `/p2p/` part is optional.
+    pub address: Multiaddr,
+    #[serde_as(as = "DisplayFromStr")]
+    pub peer_id: PeerId,
+}
+
+impl Config {
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join(CONFIG_FILE_NAME)
+    }
+
+    /// Reads the config file from the data directory, a missing file is
+    /// treated like an empty one.
+    pub fn read(data_dir: &Path) -> Result<Self> {
+        let path = Self::path(data_dir);
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        tracing::debug!(path = %path.display(), "Reading config file");
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file at {}", path.display()))?;
+        let config = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file at {}", path.display()))?;
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn missing_config_file_is_empty() {
+        let data_dir = tempdir().unwrap();
+
+        assert_eq!(Config::read(data_dir.path()).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn reads_pinned_sellers() {
+        let data_dir = tempdir().unwrap();
+        fs::write(
+            Config::path(data_dir.path()),
+            r#"
+[[pinned_sellers]]
+address = "/dns4/seller.example.com/tcp/9939"
+peer_id = "12D3KooWCdMKjesXMJz1SiZ7HgotrxuqhQJbP5sgBm2BwP1cqThi"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::read(data_dir.path()).unwrap();
+
+        assert_eq!(
+            config.pinned_sellers,
+            vec![PinnedSeller {
+                address: "/dns4/seller.example.com/tcp/9939".parse().unwrap(),
+                peer_id: "12D3KooWCdMKjesXMJz1SiZ7HgotrxuqhQJbP5sgBm2BwP1cqThi"
+                    .parse()
+                    .unwrap(),
+            }]
+        );
+    }
+}
@@ -5,226 +5,477 @@ use crate::network::encrypted_signature;
 use crate::network::quote::BidQuote;
 use crate::network::swap_setup::bob::NewSwap;
 use crate::protocol::bob::State2;
+use crate::protocol::Database;
 use anyhow::{Context, Result};
-use futures::future::{BoxFuture, OptionFuture};
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
 use futures::{FutureExt, StreamExt};
+use libp2p::core::Multiaddr;
 use libp2p::request_response::{RequestId, ResponseChannel};
-use libp2p::swarm::dial_opts::DialOpts;
+use libp2p::swarm::dial_opts::{DialOpts, PeerCondition};
 use libp2p::swarm::SwarmEvent;
 use libp2p::{PeerId, Swarm};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
+/// Drives the network layer of all swaps running in this process.
+///
+/// A single swarm is shared by all swaps, which may be with different sellers.
+/// Each swap talks to the event loop through its own [`EventLoopHandle`],
+/// created with [`EventLoopController::swap_handle`].
 #[allow(missing_debug_implementations)]
 pub struct EventLoop {
-    swap_id: Uuid,
     swarm: libp2p::Swarm<Behaviour>,
-    alice_peer_id: PeerId,
+    /// Sellers that identified themselves together with their agent version,
+    /// saved outside of the event loop by [`remember_agent_versions`].
+    identified: mpsc::UnboundedSender<(PeerId, String)>,
+
+    /// The swaps that are served by this event loop, by swap id.
+    swaps: HashMap<Uuid, SwapChannel>,
+    /// Swaps that are about to be served by this event loop.
+    registrations: mpsc::UnboundedReceiver<Registration>,
 
-    // these streams represents outgoing requests that we have to make
-    quote_requests: bmrng::RequestReceiverStream<(), BidQuote>,
-    encrypted_signatures: bmrng::RequestReceiverStream<EncryptedSignature, ()>,
-    swap_setup_requests: bmrng::RequestReceiverStream<NewSwap, Result<State2>>,
+    // these streams represents outgoing requests that we have to make, together with the seller
+    // they have to be sent to
+    quote_requests: bmrng::RequestReceiverStream<PeerId, BidQuote>,
+    encrypted_signatures: bmrng::RequestReceiverStream<(PeerId, encrypted_signature::Request), ()>,
+    swap_setup_requests: bmrng::RequestReceiverStream<(PeerId, NewSwap), Result<State2>>,
+
+    /// Requests that are buffered until we are connected to the seller.
+    pending_requests: HashMap<PeerId, Vec<Request>>,
 
     // these represents requests that are currently in-flight.
-    // once we get a response to a matching [`RequestId`], we will use the responder to relay the
-    // response.
-    inflight_quote_requests: HashMap<RequestId, bmrng::Responder<BidQuote>>,
-    inflight_encrypted_signature_requests: HashMap<RequestId, bmrng::Responder<()>>,
-    inflight_swap_setup: Option<bmrng::Responder<Result<State2>>>,
+    // once we get a response to a matching [`RequestId`] or swap id, we will use the responder to
+    // relay the response.
+    inflight_quote_requests: HashMap<RequestId, (PeerId, bmrng::Responder<BidQuote>)>,
+    inflight_encrypted_signature_requests: HashMap<RequestId, (PeerId, bmrng::Responder<()>)>,
+    inflight_swap_setups: HashMap<Uuid, (PeerId, bmrng::Responder<Result<State2>>)>,
 
-    /// The sender we will use to relay incoming transfer proofs.
-    transfer_proof: bmrng::RequestSender<monero::TransferProof, ()>,
-    /// The future representing the successful handling of an incoming transfer
-    /// proof.
+    /// The futures representing the successful handling of incoming transfer
+    /// proofs.
     ///
     /// Once we've sent a transfer proof to the ongoing swap, this future waits
     /// until the swap took it "out" of the `EventLoopHandle`. As this future
     /// resolves, we use the `ResponseChannel` returned from it to send an ACK
     /// to Alice that we have successfully processed the transfer proof.
-    pending_transfer_proof: OptionFuture<BoxFuture<'static, ResponseChannel<()>>>,
+    pending_transfer_proofs: FuturesUnordered<BoxFuture<'static, ResponseChannel<()>>>,
+}
+
+/// The channel over which transfer proofs are relayed to a running swap.
+struct SwapChannel {
+    seller: PeerId,
+    transfer_proof: bmrng::RequestSender<monero::TransferProof, ()>,
+}
+
+#[derive(Debug)]
+struct Registration {
+    swap_id: Uuid,
+    seller: PeerId,
+    seller_addresses: Vec<Multiaddr>,
+    transfer_proof: bmrng::RequestSender<monero::TransferProof, ()>,
+}
+
+enum Request {
+    Quote(bmrng::Responder<BidQuote>),
+    SwapSetup(NewSwap, bmrng::Responder<Result<State2>>),
+    EncryptedSignature(encrypted_signature::Request, bmrng::Responder<()>),
 }
 
 impl EventLoop {
     pub fn new(
-        swap_id: Uuid,
         swarm: Swarm<Behaviour>,
-        alice_peer_id: PeerId,
-    ) -> Result<(Self, EventLoopHandle)> {
+        db: Arc<dyn Database + Send + Sync>,
+    ) -> Result<(Self, EventLoopController)> {
         let execution_setup = bmrng::channel_with_timeout(1, Duration::from_secs(60));
-        let transfer_proof = bmrng::channel_with_timeout(1, Duration::from_secs(60));
         let encrypted_signature = bmrng::channel(1);
         let quote = bmrng::channel_with_timeout(1, Duration::from_secs(60));
+        let registrations = mpsc::unbounded_channel();
+        let identified = mpsc::unbounded_channel();
+
+        tokio::spawn(remember_agent_versions(db, identified.1));
 
         let event_loop = EventLoop {
-            swap_id,
             swarm,
-            alice_peer_id,
+            identified: identified.0,
+            swaps: HashMap::default(),
+            registrations: registrations.1,
             swap_setup_requests: execution_setup.1.into(),
-            transfer_proof: transfer_proof.0,
             encrypted_signatures: encrypted_signature.1.into(),
             quote_requests: quote.1.into(),
+            pending_requests: HashMap::default(),
             inflight_quote_requests: HashMap::default(),
-            inflight_swap_setup: None,
+            inflight_swap_setups: HashMap::default(),
             inflight_encrypted_signature_requests: HashMap::default(),
-            pending_transfer_proof: OptionFuture::from(None),
+            pending_transfer_proofs: FuturesUnordered::new(),
         };
 
-        let handle = EventLoopHandle {
+        let controller = EventLoopController {
+            registrations: registrations.0,
             swap_setup: execution_setup.0,
-            transfer_proof: transfer_proof.1,
             encrypted_signature: encrypted_signature.0,
             quote: quote.0,
         };
 
-        Ok((event_loop, handle))
+        Ok((event_loop, controller))
     }
 
     pub async fn run(mut self) {
-        match self.swarm.dial(DialOpts::from(self.alice_peer_id)) {
-            Ok(()) => {}
-            Err(e) => {
-                tracing::error!("Failed to initiate dial to Alice: {}", e);
-                return;
-            }
-        }
-
         loop {
             // Note: We are making very elaborate use of `select!` macro's feature here. Make sure to read the documentation thoroughly: https://docs.rs/tokio/1.4.0/tokio/macro.select.html
             tokio::select! {
                 swarm_event = self.swarm.select_next_some() => {
-                    match swarm_event {
-                        SwarmEvent::Behaviour(OutEvent::QuoteReceived { id, response }) => {
-                            if let Some(responder) = self.inflight_quote_requests.remove(&id) {
-                                let _ = responder.respond(response);
-                            }
-                        }
-                        SwarmEvent::Behaviour(OutEvent::SwapSetupCompleted(response)) => {
-                            if let Some(responder) = self.inflight_swap_setup.take() {
-                                let _ = responder.respond(*response);
-                            }
-                        }
-                        SwarmEvent::Behaviour(OutEvent::TransferProofReceived { msg, channel, peer }) => {
-                            let swap_id = msg.swap_id;
-
-                            if peer != self.alice_peer_id {
-                                tracing::warn!(
-                                            %swap_id,
-                                            "Ignoring malicious transfer proof from {}, expected to receive it from {}",
-                                            peer,
-                                            self.alice_peer_id);
-                                        continue;
-                            }
-
-                            if swap_id != self.swap_id {
-
-                                // TODO: Save unexpected transfer proofs in the database and check for messages in the database when handling swaps
-                                tracing::warn!("Received unexpected transfer proof for swap {} while running swap {}. This transfer proof will be ignored", swap_id, self.swap_id);
-
-                                // When receiving a transfer proof that is unexpected we still have to acknowledge that it was received
-                                let _ = self.swarm.behaviour_mut().transfer_proof.send_response(channel, ());
-                                continue;
-                            }
-
-                            let mut responder = match self.transfer_proof.send(msg.tx_lock_proof).await {
-                                Ok(responder) => responder,
-                                Err(e) => {
-                                    tracing::warn!("Failed to pass on transfer proof: {:#}", e);
-                                    continue;
-                                }
-                            };
-
-                            self.pending_transfer_proof = OptionFuture::from(Some(async move {
-                                let _ = responder.recv().await;
-
-                                channel
-                            }.boxed()));
-                        }
-                        SwarmEvent::Behaviour(OutEvent::EncryptedSignatureAcknowledged { id }) => {
-                            if let Some(responder) = self.inflight_encrypted_signature_requests.remove(&id) {
-                                let _ = responder.respond(());
-                            }
-                        }
-                        SwarmEvent::Behaviour(OutEvent::AllRedialAttemptsExhausted { peer }) if peer == self.alice_peer_id => {
-                            tracing::error!("Exhausted all re-dial attempts to Alice");
-                            return;
-                        }
-                        SwarmEvent::Behaviour(OutEvent::Failure { peer, error }) => {
-                            tracing::warn!(%peer, "Communication error: {:#}", error);
-                            return;
-                        }
-                        SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } if peer_id == self.alice_peer_id => {
-                            tracing::info!("Connected to Alice at {}", endpoint.get_remote_address());
-                        }
-                        SwarmEvent::Dialing(peer_id) if peer_id == self.alice_peer_id => {
-                            tracing::debug!("Dialling Alice at {}", peer_id);
-                        }
-                        SwarmEvent::ConnectionClosed { peer_id, endpoint, num_established, cause: Some(error) } if peer_id == self.alice_peer_id && num_established == 0 => {
-                            tracing::warn!("Lost connection to Alice at {}, cause: {}", endpoint.get_remote_address(), error);
-                        }
-                        SwarmEvent::ConnectionClosed { peer_id, num_established, cause: None, .. } if peer_id == self.alice_peer_id && num_established == 0 => {
-                            // no error means the disconnection was requested
-                            tracing::info!("Successfully closed connection to Alice");
-                            return;
-                        }
-                        SwarmEvent::OutgoingConnectionError { peer_id,  error } if matches!(peer_id, Some(alice_peer_id) if alice_peer_id == self.alice_peer_id) => {
-                            tracing::warn!( "Failed to dial Alice: {}", error);
-
-                            if let Some(duration) = self.swarm.behaviour_mut().redial.until_next_redial() {
-                                tracing::info!("Next redial attempt in {}s", duration.as_secs());
-                            }
-
-                        }
-                        _ => {}
-                    }
+                    self.handle_swarm_event(swarm_event).await;
+                },
+
+                Some(registration) = self.registrations.recv() => {
+                    self.register(registration);
                 },
 
                 // Handle to-be-sent requests for all our network protocols.
-                // Use `self.is_connected_to_alice` as a guard to "buffer" requests until we are connected.
-                Some(((), responder)) = self.quote_requests.next().fuse(), if self.is_connected_to_alice() => {
-                    let id = self.swarm.behaviour_mut().quote.send_request(&self.alice_peer_id, ());
-                    self.inflight_quote_requests.insert(id, responder);
+                // Requests are buffered until we are connected to the seller they are meant for.
+                Some((peer, responder)) = self.quote_requests.next().fuse() => {
+                    self.send_request(peer, Request::Quote(responder)).await;
                 },
-                Some((swap, responder)) = self.swap_setup_requests.next().fuse(), if self.is_connected_to_alice() => {
-                    self.swarm.behaviour_mut().swap_setup.start(self.alice_peer_id, swap).await;
-                    self.inflight_swap_setup = Some(responder);
+                Some(((peer, swap), responder)) = self.swap_setup_requests.next().fuse() => {
+                    self.send_request(peer, Request::SwapSetup(swap, responder)).await;
                 },
-                Some((tx_redeem_encsig, responder)) = self.encrypted_signatures.next().fuse(), if self.is_connected_to_alice() => {
-                    let request = encrypted_signature::Request {
-                        swap_id: self.swap_id,
-                        tx_redeem_encsig
-                    };
-
-                    let id = self.swarm.behaviour_mut().encrypted_signature.send_request(&self.alice_peer_id, request);
-                    self.inflight_encrypted_signature_requests.insert(id, responder);
+                Some(((peer, request), responder)) = self.encrypted_signatures.next().fuse() => {
+                    self.send_request(peer, Request::EncryptedSignature(request, responder)).await;
                 },
 
-                Some(response_channel) = &mut self.pending_transfer_proof => {
+                Some(response_channel) = self.pending_transfer_proofs.next() => {
                     let _ = self.swarm.behaviour_mut().transfer_proof.send_response(response_channel, ());
+                }
+            }
+        }
+    }
+
+    async fn handle_swarm_event<E>(&mut self, swarm_event: SwarmEvent<OutEvent, E>)
+    where
+        E: std::fmt::Display,
+    {
+        match swarm_event {
+            SwarmEvent::Behaviour(OutEvent::QuoteReceived { id, response }) => {
+                if let Some((_, responder)) = self.inflight_quote_requests.remove(&id) {
+                    let _ = responder.respond(response);
+                }
+            }
+            SwarmEvent::Behaviour(OutEvent::SwapSetupCompleted { swap_id, result }) => {
+                if let Some((_, responder)) = self.inflight_swap_setups.remove(&swap_id) {
+                    let _ = responder.respond(*result);
+                }
+            }
+            SwarmEvent::Behaviour(OutEvent::TransferProofReceived { msg, channel, peer }) => {
+                let swap_id = msg.swap_id;
+
+                let swap = match self.swaps.get(&swap_id) {
+                    Some(swap) => swap,
+                    None => {
+                        // TODO: Save unexpected transfer proofs in the database and check for messages in the database when handling swaps
+                        tracing::warn!(%swap_id, "Received transfer proof for a swap that is not running. This transfer proof will be ignored");
+
+                        // When receiving a transfer proof that is unexpected we still have to acknowledge that it was received
+                        let _ = self
+                            .swarm
+                            .behaviour_mut()
+                            .transfer_proof
+                            .send_response(channel, ());
+                        return;
+                    }
+                };
+
+                if peer != swap.seller {
+                    tracing::warn!(
+                        %swap_id,
+                        "Ignoring malicious transfer proof from {}, expected to receive it from {}",
+                        peer,
+                        swap.seller
+                    );
+                    return;
+                }
+
+                let transfer_proof = swap.transfer_proof.clone();
+                let mut responder = match transfer_proof.send(msg.tx_lock_proof).await {
+                    Ok(responder) => responder,
+                    Err(e) => {
+                        tracing::warn!(%swap_id, "Failed to pass on transfer proof: {:#}", e);
+                        return;
+                    }
+                };
+
+                self.pending_transfer_proofs.push(
+                    async move {
+                        let _ = responder.recv().await;
 
-                    self.pending_transfer_proof = OptionFuture::from(None);
+                        channel
+                    }
+                    .boxed(),
+                );
+            }
+            SwarmEvent::Behaviour(OutEvent::EncryptedSignatureAcknowledged { id }) => {
+                if let Some((_, responder)) = self.inflight_encrypted_signature_requests.remove(&id)
+                {
+                    let _ = responder.respond(());
+                }
+            }
+            SwarmEvent::Behaviour(OutEvent::Identified {
+                peer,
+                agent_version,
+            }) => {
+                if !self.swaps.values().any(|swap| swap.seller == peer) {
+                    return;
+                }
+
+                let _ = self.identified.send((peer, agent_version));
+            }
+            SwarmEvent::Behaviour(OutEvent::AllRedialAttemptsExhausted { peer }) => {
+                tracing::error!(%peer, "Exhausted all re-dial attempts to peer");
+                self.remove_seller(peer);
+            }
+            SwarmEvent::Behaviour(OutEvent::Failure { peer, error }) => {
+                tracing::warn!(%peer, "Communication error: {:#}", error);
+                self.fail_inflight_requests(peer);
+            }
+            SwarmEvent::ConnectionEstablished {
+                peer_id, endpoint, ..
+            } => {
+                tracing::info!(peer = %peer_id, "Connected to peer at {}", endpoint.get_remote_address());
+
+                for request in self.pending_requests.remove(&peer_id).unwrap_or_default() {
+                    self.send_request(peer_id, request).await;
                 }
             }
+            SwarmEvent::Dialing(peer_id) => {
+                tracing::debug!(peer = %peer_id, "Dialling peer");
+            }
+            SwarmEvent::ConnectionClosed {
+                peer_id,
+                endpoint,
+                num_established,
+                cause: Some(error),
+            } if num_established == 0 => {
+                tracing::warn!(peer = %peer_id, "Lost connection to peer at {}, cause: {}", endpoint.get_remote_address(), error);
+            }
+            SwarmEvent::ConnectionClosed {
+                peer_id,
+                num_established,
+                cause: None,
+                ..
+            } if num_established == 0 => {
+                // no error means the disconnection was requested
+                tracing::info!(peer = %peer_id, "Successfully closed connection to peer");
+            }
+            SwarmEvent::OutgoingConnectionError {
+                peer_id: Some(peer_id),
+                error,
+            } => {
+                tracing::warn!(peer = %peer_id, "Failed to dial peer: {}", error);
+
+                if let Some(duration) = self
+                    .swarm
+                    .behaviour_mut()
+                    .redial
+                    .until_next_redial(&peer_id)
+                {
+                    tracing::info!("Next redial attempt in {}s", duration.as_secs());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn register(&mut self, registration: Registration) {
+        let Registration {
+            swap_id,
+            seller,
+            seller_addresses,
+            transfer_proof,
+        } = registration;
+
+        let behaviour = self.swarm.behaviour_mut();
+        for address in seller_addresses {
+            behaviour.add_address(seller, address);
+        }
+        behaviour.redial.add_peer(seller);
+
+        self.swaps.insert(
+            swap_id,
+            SwapChannel {
+                seller,
+                transfer_proof,
+            },
+        );
+
+        self.dial(seller);
+    }
+
+    /// Sends the request to the given seller right away if we are connected,
+    /// otherwise it is buffered until the connection is established.
+    async fn send_request(&mut self, peer: PeerId, request: Request) {
+        if !self.swarm.is_connected(&peer) {
+            self.pending_requests.entry(peer).or_default().push(request);
+            self.dial(peer);
+
+            return;
+        }
+
+        match request {
+            Request::Quote(responder) => {
+                let id = self.swarm.behaviour_mut().quote.send_request(&peer, ());
+                self.inflight_quote_requests.insert(id, (peer, responder));
+            }
+            Request::SwapSetup(swap, responder) => {
+                let swap_id = swap.swap_id;
+
+                self.swarm
+                    .behaviour_mut()
+                    .swap_setup
+                    .start(peer, swap)
+                    .await;
+                self.inflight_swap_setups.insert(swap_id, (peer, responder));
+            }
+            Request::EncryptedSignature(request, responder) => {
+                let id = self
+                    .swarm
+                    .behaviour_mut()
+                    .encrypted_signature
+                    .send_request(&peer, request);
+                self.inflight_encrypted_signature_requests
+                    .insert(id, (peer, responder));
+            }
+        }
+    }
+
+    fn dial(&mut self, peer: PeerId) {
+        if self.swarm.is_connected(&peer) {
+            return;
+        }
+
+        let opts = DialOpts::peer_id(peer)
+            .condition(PeerCondition::NotDialing)
+            .build();
+
+        if let Err(e) = self.swarm.dial(opts) {
+            tracing::error!(%peer, "Failed to initiate dial to peer: {}", e);
         }
     }
 
-    fn is_connected_to_alice(&self) -> bool {
-        self.swarm.is_connected(&self.alice_peer_id)
+    /// Makes the requests sent to the given peer fail, the swaps can send
+    /// them again.
+    ///
+    /// The failure of a request does not tell which one failed, dropping the
+    /// responders of all of them makes sure none of the swaps waits forever.
+    fn fail_inflight_requests(&mut self, peer: PeerId) {
+        self.inflight_quote_requests
+            .retain(|_, (seller, _)| *seller != peer);
+        self.inflight_encrypted_signature_requests
+            .retain(|_, (seller, _)| *seller != peer);
+        self.inflight_swap_setups
+            .retain(|_, (seller, _)| *seller != peer);
+    }
+
+    /// Stops serving all swaps with the given seller once it cannot be
+    /// reached anymore.
+    ///
+    /// Dropping the channels makes the requests of these swaps fail.
+    fn remove_seller(&mut self, peer: PeerId) {
+        self.fail_inflight_requests(peer);
+        self.pending_requests.remove(&peer);
+        self.swaps.retain(|_, swap| swap.seller != peer);
+        self.swarm.behaviour_mut().redial.remove_peer(&peer);
+    }
+}
+
+/// Remembers the agent versions sellers identify themselves with until the
+/// event loop stops.
+///
+/// A change is logged because it may also mean that someone else took over the
+/// seller's address. The new version is saved, so that the change is only
+/// logged once.
+async fn remember_agent_versions(
+    db: Arc<dyn Database + Send + Sync>,
+    mut identified: mpsc::UnboundedReceiver<(PeerId, String)>,
+) {
+    while let Some((seller, agent_version)) = identified.recv().await {
+        match db.get_seller_agent_version(seller).await {
+            Ok(Some(known)) if known == agent_version => continue,
+            Ok(Some(known)) => {
+                tracing::warn!(peer = %seller, from = %known, to = %agent_version, "Seller changed its agent version");
+            }
+            Ok(None) => {
+                tracing::debug!(peer = %seller, %agent_version, "Seller identified itself");
+            }
+            Err(error) => {
+                tracing::warn!(peer = %seller, "Failed to load agent version of seller: {:#}", error);
+            }
+        }
+
+        if let Err(error) = db.insert_seller_agent_version(seller, agent_version).await {
+            tracing::warn!(peer = %seller, "Failed to save agent version of seller: {:#}", error);
+        }
+    }
+}
+
+/// Creates [`EventLoopHandle`]s for the swaps served by an [`EventLoop`].
+#[derive(Debug, Clone)]
+pub struct EventLoopController {
+    registrations: mpsc::UnboundedSender<Registration>,
+    swap_setup: bmrng::RequestSender<(PeerId, NewSwap), Result<State2>>,
+    encrypted_signature: bmrng::RequestSender<(PeerId, encrypted_signature::Request), ()>,
+    quote: bmrng::RequestSender<PeerId, BidQuote>,
+}
+
+impl EventLoopController {
+    /// Creates the handle through which the swap with the given id
+    /// communicates with `seller`, reachable at `seller_addresses`.
+    pub fn swap_handle(
+        &self,
+        swap_id: Uuid,
+        seller: PeerId,
+        seller_addresses: Vec<Multiaddr>,
+    ) -> Result<EventLoopHandle> {
+        let transfer_proof = bmrng::channel_with_timeout(1, Duration::from_secs(60));
+
+        self.registrations
+            .send(Registration {
+                swap_id,
+                seller,
+                seller_addresses,
+                transfer_proof: transfer_proof.0,
+            })
+            .map_err(|_| anyhow::anyhow!("Event loop is not running"))?;
+
+        Ok(EventLoopHandle {
+            swap_id,
+            seller,
+            swap_setup: self.swap_setup.clone(),
+            transfer_proof: transfer_proof.1,
+            encrypted_signature: self.encrypted_signature.clone(),
+            quote: self.quote.clone(),
+        })
     }
 }
 
 #[derive(Debug)]
 pub struct EventLoopHandle {
-    swap_setup: bmrng::RequestSender<NewSwap, Result<State2>>,
+    swap_id: Uuid,
+    seller: PeerId,
+    swap_setup: bmrng::RequestSender<(PeerId, NewSwap), Result<State2>>,
     transfer_proof: bmrng::RequestReceiver<monero::TransferProof, ()>,
-    encrypted_signature: bmrng::RequestSender<EncryptedSignature, ()>,
-    quote: bmrng::RequestSender<(), BidQuote>,
+    encrypted_signature: bmrng::RequestSender<(PeerId, encrypted_signature::Request), ()>,
+    quote: bmrng::RequestSender<PeerId, BidQuote>,
 }
 
 impl EventLoopHandle {
+    pub fn seller(&self) -> PeerId {
+        self.seller
+    }
+
     pub async fn setup_swap(&mut self, swap: NewSwap) -> Result<State2> {
-        self.swap_setup.send_receive(swap).await?
+        self.swap_setup.send_receive((self.seller, swap)).await?
     }
 
     pub async fn recv_transfer_proof(&mut self) -> Result<monero::TransferProof> {
@@ -241,15 +492,20 @@ impl EventLoopHandle {
     }
 
     pub async fn request_quote(&mut self) -> Result<BidQuote> {
-        Ok(self.quote.send_receive(()).await?)
+        Ok(self.quote.send_receive(self.seller).await?)
     }
 
     pub async fn send_encrypted_signature(
         &mut self,
         tx_redeem_encsig: EncryptedSignature,
-    ) -> Result<(), bmrng::error::RequestError<EncryptedSignature>> {
+    ) -> Result<(), bmrng::error::RequestError<(PeerId, encrypted_signature::Request)>> {
+        let request = encrypted_signature::Request {
+            swap_id: self.swap_id,
+            tx_redeem_encsig,
+        };
+
         self.encrypted_signature
-            .send_receive(tx_redeem_encsig)
+            .send_receive((self.seller, request))
             .await
     }
 }
@@ -0,0 +1,126 @@
+use crate::bitcoin::PartiallySignedTransaction;
+use crate::protocol::bob::ExternalSigner;
+use ::bitcoin::consensus::encode::{deserialize, serialize};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use base64::engine::general_purpose;
+use base64::Engine;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Has the user sign the lock transaction with their external wallet.
+#[derive(Debug, Clone, Default)]
+pub struct PsbtPrompt {
+    /// Where to also write the unsigned lock transaction.
+    pub lock_psbt_file: Option<PathBuf>,
+}
+
+#[async_trait]
+impl ExternalSigner for PsbtPrompt {
+    async fn sign(
+        &self,
+        swap_id: Uuid,
+        psbt: PartiallySignedTransaction,
+    ) -> Result<PartiallySignedTransaction> {
+        tracing::info!(%swap_id, "Bitcoin lock transaction is ready to be signed");
+
+        sign_externally(psbt, self.lock_psbt_file.as_deref()).await
+    }
+}
+
+/// Prints `prompt` and reads a PSBT from stdin.
+///
+/// The user can either paste the base64 encoded PSBT or enter the path of a
+/// file containing it, in binary or base64 encoding.
+pub async fn read_psbt(prompt: String) -> Result<PartiallySignedTransaction> {
+    let input = tokio::task::spawn_blocking(move || -> Result<String> {
+        println!("{}", prompt);
+
+        let mut line = String::new();
+        std::io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .context("Failed to read PSBT from stdin")?;
+
+        Ok(line.trim().to_owned())
+    })
+    .await??;
+
+    if input.is_empty() {
+        bail!("No PSBT provided")
+    }
+
+    let path = PathBuf::from(&input);
+    if path.is_file() {
+        let bytes = tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read PSBT from {}", path.display()))?;
+
+        return decode_psbt(&bytes);
+    }
+
+    decode_psbt(input.as_bytes())
+}
+
+/// Exports the unsigned lock transaction for signing by an external wallet and
+/// waits for the user to provide the signed PSBT.
+///
+/// The PSBT is always printed in base64 encoding. If `file` is given it is
+/// also written to that file in binary encoding.
+pub async fn sign_externally(
+    psbt: PartiallySignedTransaction,
+    file: Option<&Path>,
+) -> Result<PartiallySignedTransaction> {
+    let encoded = general_purpose::STANDARD.encode(serialize(&psbt));
+
+    if let Some(file) = file {
+        tokio::fs::write(file, serialize(&psbt))
+            .await
+            .with_context(|| format!("Failed to write PSBT to {}", file.display()))?;
+        tracing::info!(path = %file.display(), "Wrote unsigned Bitcoin lock transaction PSBT to file");
+    }
+
+    tracing::info!(psbt = %encoded, "Sign the Bitcoin lock transaction with your external wallet, without adding or removing any inputs or outputs");
+
+    read_psbt(String::from(
+        "Enter the signed PSBT (base64) or the path of the file containing it:",
+    ))
+    .await
+}
+
+fn decode_psbt(bytes: &[u8]) -> Result<PartiallySignedTransaction> {
+    if let Ok(psbt) = deserialize(bytes) {
+        return Ok(psbt);
+    }
+
+    let text = std::str::from_utf8(bytes).context("PSBT is neither binary nor base64 encoded")?;
+    let decoded = general_purpose::STANDARD
+        .decode(text.trim())
+        .context("PSBT is neither binary nor base64 encoded")?;
+
+    deserialize(&decoded).context("Failed to deserialize PSBT")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::{Amount, WalletBuilder};
+
+    #[tokio::test]
+    async fn decodes_binary_and_base64_psbts() {
+        let wallet = WalletBuilder::new(50_000).build();
+        let address = wallet.new_address().await.unwrap();
+        let psbt = wallet
+            .send_to_address(address, Amount::from_sat(10_000), None)
+            .await
+            .unwrap();
+
+        let binary = serialize(&psbt);
+        let encoded = general_purpose::STANDARD.encode(&binary);
+
+        assert_eq!(decode_psbt(&binary).unwrap(), psbt);
+        assert_eq!(decode_psbt(encoded.as_bytes()).unwrap(), psbt);
+        decode_psbt(b"not a psbt").expect_err("garbage to be rejected");
+    }
+}
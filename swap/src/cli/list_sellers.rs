@@ -1,6 +1,7 @@
 use crate::network::quote::BidQuote;
 use crate::network::rendezvous::XmrBtcNamespace;
 use crate::network::{quote, swarm};
+use crate::{bitcoin, monero};
 use anyhow::{Context, Result};
 use futures::StreamExt;
 use libp2p::multiaddr::Protocol;
@@ -59,6 +60,53 @@ pub async fn list_sellers(
     Ok(sellers)
 }
 
+/// Combines the quotes of all online sellers into a single quote with the best
+/// price and the widest quantity range.
+///
+/// Returns `None` if none of the sellers is online.
+pub fn combined_quote(sellers: &[Seller]) -> Option<BidQuote> {
+    sellers
+        .iter()
+        .filter_map(|seller| match seller.status {
+            Status::Online(quote) => Some(quote),
+            Status::Unreachable => None,
+        })
+        .reduce(|combined, quote| BidQuote {
+            price: combined.price.min(quote.price),
+            min_quantity: combined.min_quantity.min(quote.min_quantity),
+            max_quantity: combined.max_quantity.max(quote.max_quantity),
+        })
+}
+
+/// Returns the online sellers willing to buy `btc`, cheapest first.
+///
+/// If `xmr` is given, sellers whose price is too high to receive that amount
+/// for `btc` are left out as well.
+pub fn select_sellers(
+    sellers: Vec<Seller>,
+    btc: bitcoin::Amount,
+    xmr: Option<monero::Amount>,
+) -> Vec<(Multiaddr, BidQuote)> {
+    let mut candidates = sellers
+        .into_iter()
+        .filter_map(|seller| match seller.status {
+            Status::Online(quote) => Some((seller.multiaddr, quote)),
+            Status::Unreachable => None,
+        })
+        .filter(|(_, quote)| quote.min_quantity <= btc && btc <= quote.max_quantity)
+        .filter(|(_, quote)| match xmr {
+            Some(xmr) => xmr
+                .min_bitcoin_for_price(quote.price)
+                .map_or(false, |required| required <= btc),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+
+    candidates.sort_by_key(|(_, quote)| quote.price);
+
+    candidates
+}
+
 #[serde_as]
 #[derive(Debug, Serialize, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub struct Seller {
@@ -327,6 +375,79 @@ impl From<PingEvent> for OutEvent {
 mod tests {
     use super::*;
 
+    #[test]
+    fn selects_cheapest_sellers_that_accept_the_amount() {
+        let sellers = vec![
+            online("/ip4/127.0.0.1/tcp/1", 300, 0, 100_000),
+            online("/ip4/127.0.0.1/tcp/2", 100, 50_000, 100_000),
+            online("/ip4/127.0.0.1/tcp/3", 200, 0, 100_000),
+            online("/ip4/127.0.0.1/tcp/4", 50, 0, 10_000),
+            Seller {
+                multiaddr: "/ip4/127.0.0.1/tcp/5".parse().unwrap(),
+                status: Status::Unreachable,
+            },
+        ];
+
+        let selected = select_sellers(sellers, bitcoin::Amount::from_sat(20_000), None)
+            .into_iter()
+            .map(|(multiaddr, _)| multiaddr.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            selected,
+            vec!["/ip4/127.0.0.1/tcp/3", "/ip4/127.0.0.1/tcp/1"]
+        );
+    }
+
+    #[test]
+    fn drops_sellers_that_cannot_deliver_the_requested_xmr() {
+        let sellers = vec![
+            online("/ip4/127.0.0.1/tcp/1", 100_000, 0, 1_000_000),
+            online("/ip4/127.0.0.1/tcp/2", 110_000, 0, 1_000_000),
+        ];
+        let xmr = monero::Amount::parse_monero("1").unwrap();
+
+        let selected = select_sellers(sellers, bitcoin::Amount::from_sat(100_002), Some(xmr))
+            .into_iter()
+            .map(|(multiaddr, _)| multiaddr.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(selected, vec!["/ip4/127.0.0.1/tcp/1"]);
+    }
+
+    #[test]
+    fn combines_quotes_of_online_sellers() {
+        let sellers = vec![
+            online("/ip4/127.0.0.1/tcp/1", 300, 1_000, 100_000),
+            online("/ip4/127.0.0.1/tcp/2", 100, 50_000, 200_000),
+            Seller {
+                multiaddr: "/ip4/127.0.0.1/tcp/3".parse().unwrap(),
+                status: Status::Unreachable,
+            },
+        ];
+
+        assert_eq!(
+            combined_quote(&sellers),
+            Some(BidQuote {
+                price: bitcoin::Amount::from_sat(100),
+                min_quantity: bitcoin::Amount::from_sat(1_000),
+                max_quantity: bitcoin::Amount::from_sat(200_000),
+            })
+        );
+        assert_eq!(combined_quote(&[]), None);
+    }
+
+    fn online(multiaddr: &str, price: u64, min_quantity: u64, max_quantity: u64) -> Seller {
+        Seller {
+            multiaddr: multiaddr.parse().unwrap(),
+            status: Status::Online(BidQuote {
+                price: bitcoin::Amount::from_sat(price),
+                min_quantity: bitcoin::Amount::from_sat(min_quantity),
+                max_quantity: bitcoin::Amount::from_sat(max_quantity),
+            }),
+        }
+    }
+
     #[test]
     fn sellers_sort_with_unreachable_coming_last() {
         let mut list = vec![
@@ -0,0 +1,159 @@
+use crate::protocol::bob::LockSummary;
+use anyhow::{Context, Result};
+use comfy_table::Table;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::Confirm;
+use libp2p::PeerId;
+use std::io::BufRead;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+/// How the user confirms a swap after swap setup, before the Bitcoin is
+/// locked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockConfirmation {
+    pub seller: PeerId,
+    pub bitcoin_avg_block_time: Duration,
+    /// Print the summary as a JSON log line and read the answer as a plain
+    /// line from stdin.
+    pub json: bool,
+    /// Skip the question, e.g. for scripting.
+    pub assume_yes: bool,
+}
+
+/// Keeps the summaries and questions of swaps resumed at once from
+/// interleaving.
+static PROMPT: Mutex<()> = Mutex::new(());
+
+impl LockConfirmation {
+    /// Shows the summary and asks the user whether to lock the Bitcoin.
+    pub async fn confirm(&self, summary: LockSummary) -> Result<bool> {
+        let confirmation = *self;
+
+        tokio::task::spawn_blocking(move || {
+            let _prompt = PROMPT.lock().unwrap_or_else(PoisonError::into_inner);
+
+            confirmation.show(&summary);
+            if confirmation.assume_yes {
+                return Ok(true);
+            }
+
+            confirmation.ask()
+        })
+        .await?
+    }
+
+    fn show(&self, summary: &LockSummary) {
+        let cancel_blocks = u32::from(summary.cancel_timelock);
+        let punish_blocks = cancel_blocks + u32::from(summary.punish_timelock);
+        let cancel_estimate = estimate(cancel_blocks, self.bitcoin_avg_block_time);
+        let punish_estimate = estimate(punish_blocks, self.bitcoin_avg_block_time);
+        let tx_lock_fee = summary
+            .tx_lock_fee
+            .map_or_else(|| "unknown".to_owned(), |fee| fee.to_string());
+        let price = summary
+            .price()
+            .map_or_else(|| "unknown".to_owned(), |price| price.to_string());
+
+        if self.json {
+            tracing::info!(
+                btc_lock_amount = %summary.btc_lock_amount,
+                tx_lock_fee = %tx_lock_fee,
+                tx_cancel_fee = %summary.tx_cancel_fee,
+                tx_refund_fee = %summary.tx_refund_fee,
+                xmr = %summary.xmr,
+                price = %price,
+                seller = %self.seller,
+                cancel_timelock = %summary.cancel_timelock,
+                cancel_estimate = %cancel_estimate,
+                punish_timelock = %summary.punish_timelock,
+                punish_estimate = %punish_estimate,
+                "Swap setup completed, waiting for confirmation to lock the Bitcoin"
+            );
+        } else {
+            let mut table = Table::new();
+            table.set_header(vec!["", "SWAP"]);
+            table.add_row(vec![
+                "Bitcoin to lock".to_owned(),
+                summary.btc_lock_amount.to_string(),
+            ]);
+            table.add_row(vec!["Lock transaction fee".to_owned(), tx_lock_fee]);
+            table.add_row(vec![
+                "Cancel transaction fee".to_owned(),
+                summary.tx_cancel_fee.to_string(),
+            ]);
+            table.add_row(vec![
+                "Refund transaction fee".to_owned(),
+                summary.tx_refund_fee.to_string(),
+            ]);
+            table.add_row(vec![
+                "Monero to receive".to_owned(),
+                summary.xmr.to_string(),
+            ]);
+            table.add_row(vec!["Price per XMR".to_owned(), price]);
+            table.add_row(vec!["Seller".to_owned(), self.seller.to_string()]);
+            table.add_row(vec![
+                "Cancel possible after".to_owned(),
+                format!("{} of the lock transaction", cancel_estimate),
+            ]);
+            table.add_row(vec![
+                "Seller can punish after".to_owned(),
+                format!("{} of the lock transaction", punish_estimate),
+            ]);
+
+            println!("{}", table);
+        }
+    }
+
+    fn ask(&self) -> Result<bool> {
+        if self.json {
+            let mut line = String::new();
+            std::io::stdin()
+                .lock()
+                .read_line(&mut line)
+                .context("Failed to read confirmation from stdin")?;
+
+            return Ok(is_yes(&line));
+        }
+
+        Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Lock the Bitcoin and start the swap?")
+            .default(false)
+            .interact()
+            .context("Failed to read confirmation")
+    }
+}
+
+fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Formats a number of blocks with an estimate of the time they take.
+fn estimate(blocks: u32, avg_block_time: Duration) -> String {
+    let minutes = (avg_block_time * blocks).as_secs() / 60;
+
+    format!("{} blocks (~{}h {}m)", blocks, minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_wall_clock_time_of_timelocks() {
+        assert_eq!(
+            estimate(72, Duration::from_secs(600)),
+            "72 blocks (~12h 0m)"
+        );
+        assert_eq!(estimate(5, Duration::from_secs(150)), "5 blocks (~0h 12m)");
+    }
+
+    #[test]
+    fn only_explicit_yes_confirms() {
+        assert!(is_yes("y\n"));
+        assert!(is_yes("YES"));
+        assert!(!is_yes(""));
+        assert!(!is_yes("n"));
+        assert!(!is_yes("yep"));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::bitcoin;
+use crate::cli::{Seller, SellerStatus};
+use crate::kraken;
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::time::Duration;
+use url::Url;
+
+pub const DEFAULT_PRICE_TICKER_WS_URL: &str = "wss://ws.kraken.com";
+pub const DEFAULT_MAX_PREMIUM: Decimal = dec!(5);
+
+const MARKET_PRICE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An independent source for the market price of XMR that sellers' quotes are
+/// checked against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferencePrice {
+    /// Websocket endpoint following the kraken price ticker protocol.
+    pub price_ticker_ws_url: Url,
+    /// The highest premium over the market price, in percent, that is
+    /// accepted.
+    pub max_premium: Decimal,
+}
+
+impl ReferencePrice {
+    /// Fetches the current market price of one XMR in BTC, through Tor if
+    /// its SOCKS5 port is given.
+    pub async fn market_price(&self, tor_socks5_port: Option<u16>) -> Result<bitcoin::Amount> {
+        let mut price_updates =
+            kraken::connect_through_tor(self.price_ticker_ws_url.clone(), tor_socks5_port)?;
+
+        let update =
+            tokio::time::timeout(MARKET_PRICE_TIMEOUT, price_updates.wait_for_next_update())
+                .await
+                .with_context(|| {
+                    format!(
+                        "Did not receive a market price from {} within {}s",
+                        self.price_ticker_ws_url,
+                        MARKET_PRICE_TIMEOUT.as_secs()
+                    )
+                })??;
+        let price = update.context("Failed to fetch the market price")?.ask;
+
+        tracing::debug!(%price, "Fetched market price of XMR");
+
+        Ok(price)
+    }
+
+    /// Returns the premium of `price` over `market_price` or an error if it
+    /// exceeds the maximum premium.
+    pub fn check(
+        &self,
+        price: bitcoin::Amount,
+        market_price: bitcoin::Amount,
+    ) -> Result<Decimal, PremiumTooHigh> {
+        let premium = premium(price, market_price);
+
+        if premium > self.max_premium {
+            return Err(PremiumTooHigh {
+                price,
+                market_price,
+                premium,
+                max_premium: self.max_premium,
+            });
+        }
+
+        Ok(premium)
+    }
+
+    /// Removes the online sellers whose quote exceeds the maximum premium over
+    /// `market_price`. Unreachable sellers are kept.
+    ///
+    /// If `force` is set the sellers are kept nevertheless and only a warning
+    /// is logged. Fails if all online sellers were removed.
+    pub fn filter_sellers(
+        &self,
+        sellers: Vec<Seller>,
+        market_price: bitcoin::Amount,
+        force: bool,
+    ) -> Result<Vec<Seller>, PremiumTooHigh> {
+        let mut rejected = None;
+
+        let sellers = sellers
+            .into_iter()
+            .filter(|seller| {
+                let quote = match seller.status {
+                    SellerStatus::Online(quote) => quote,
+                    SellerStatus::Unreachable => return true,
+                };
+
+                match self.check(quote.price, market_price) {
+                    Ok(_) => true,
+                    Err(error) if force => {
+                        tracing::warn!(seller = %seller.multiaddr, "{}, proceeding anyway because of --force", error);
+                        true
+                    }
+                    Err(error) => {
+                        tracing::warn!(seller = %seller.multiaddr, "Ignoring seller: {}", error);
+                        rejected.get_or_insert(error);
+                        false
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let any_online = sellers
+            .iter()
+            .any(|seller| matches!(seller.status, SellerStatus::Online(_)));
+
+        match rejected {
+            Some(error) if !any_online => Err(error),
+            _ => Ok(sellers),
+        }
+    }
+}
+
+/// The premium of `price` over `market_price` in percent, rounded to two
+/// decimal places. Negative if `price` is below the market price.
+pub fn premium(price: bitcoin::Amount, market_price: bitcoin::Amount) -> Decimal {
+    if market_price == bitcoin::Amount::ZERO {
+        return Decimal::MAX;
+    }
+
+    let price = Decimal::from(price.to_sat());
+    let market_price = Decimal::from(market_price.to_sat());
+
+    ((price - market_price) * Decimal::ONE_HUNDRED / market_price).round_dp(2)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Seller's price of {price} per XMR is {premium}% above the market price of {market_price}, which exceeds the maximum premium of {max_premium}%")]
+pub struct PremiumTooHigh {
+    pub price: bitcoin::Amount,
+    pub market_price: bitcoin::Amount,
+    pub premium: Decimal,
+    pub max_premium: Decimal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::quote::BidQuote;
+
+    fn reference_price() -> ReferencePrice {
+        ReferencePrice {
+            price_ticker_ws_url: DEFAULT_PRICE_TICKER_WS_URL.parse().unwrap(),
+            max_premium: dec!(5),
+        }
+    }
+
+    fn seller(price: u64) -> Seller {
+        Seller {
+            multiaddr: format!("/ip4/127.0.0.1/tcp/{}", price).parse().unwrap(),
+            status: SellerStatus::Online(BidQuote {
+                price: bitcoin::Amount::from_sat(price),
+                min_quantity: bitcoin::Amount::ZERO,
+                max_quantity: bitcoin::Amount::from_sat(1_000_000),
+            }),
+        }
+    }
+
+    #[test]
+    fn calculates_premium_over_market_price() {
+        let market_price = bitcoin::Amount::from_sat(600_000);
+
+        assert_eq!(
+            premium(bitcoin::Amount::from_sat(630_000), market_price),
+            dec!(5)
+        );
+        assert_eq!(
+            premium(bitcoin::Amount::from_sat(582_000), market_price),
+            dec!(-3)
+        );
+        assert_eq!(
+            premium(bitcoin::Amount::from_sat(600_001), market_price),
+            dec!(0)
+        );
+    }
+
+    #[test]
+    fn rejects_price_above_max_premium() {
+        let market_price = bitcoin::Amount::from_sat(600_000);
+
+        assert_eq!(
+            reference_price().check(bitcoin::Amount::from_sat(630_000), market_price),
+            Ok(dec!(5))
+        );
+        assert_eq!(
+            reference_price().check(bitcoin::Amount::from_sat(636_000), market_price),
+            Err(PremiumTooHigh {
+                price: bitcoin::Amount::from_sat(636_000),
+                market_price,
+                premium: dec!(6),
+                max_premium: dec!(5),
+            })
+        );
+    }
+
+    #[test]
+    fn filters_sellers_above_max_premium_unless_forced() {
+        let market_price = bitcoin::Amount::from_sat(600_000);
+        let sellers = || vec![seller(610_000), seller(700_000)];
+
+        let filtered = reference_price()
+            .filter_sellers(sellers(), market_price, false)
+            .unwrap();
+        assert_eq!(filtered, vec![seller(610_000)]);
+
+        let filtered = reference_price()
+            .filter_sellers(sellers(), market_price, true)
+            .unwrap();
+        assert_eq!(filtered, sellers());
+
+        let error = reference_price()
+            .filter_sellers(vec![seller(700_000)], market_price, false)
+            .unwrap_err();
+        assert_eq!(error.premium, dec!(16.67));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::cli::config::PinnedSeller;
+use crate::protocol::Database;
+use anyhow::Result;
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+
+/// What we know about the peer behind a seller's address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Identity {
+    /// The peer is pinned to the address in the config file.
+    Pinned,
+    /// No peer was seen at the address before, it is trusted on first use.
+    New,
+    /// Only this peer was seen at the address before.
+    Known,
+    /// Other peers were seen at the address before.
+    Changed { previous: Vec<PeerId> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Seller address {endpoint} is pinned to peer {pinned}, but belongs to peer {actual}")]
+pub struct PinMismatch {
+    pub endpoint: Multiaddr,
+    pub pinned: PeerId,
+    pub actual: PeerId,
+}
+
+/// Checks the peer at the given address against the pinned sellers and the
+/// addresses of the peers we saw before.
+pub fn identify(
+    peer_id: PeerId,
+    address: &Multiaddr,
+    pinned_sellers: &[PinnedSeller],
+    known_addresses: &[(PeerId, Multiaddr)],
+) -> Result<Identity, PinMismatch> {
+    let endpoint = endpoint(address);
+
+    if let Some(pinned) = pinned_sellers
+        .iter()
+        .find(|pinned| self::endpoint(&pinned.address) == endpoint)
+    {
+        if pinned.peer_id != peer_id {
+            return Err(PinMismatch {
+                endpoint,
+                pinned: pinned.peer_id,
+                actual: peer_id,
+            });
+        }
+
+        return Ok(Identity::Pinned);
+    }
+
+    let mut previous = known_addresses
+        .iter()
+        .filter(|(_, known)| self::endpoint(known) == endpoint)
+        .map(|(known_peer_id, _)| *known_peer_id)
+        .collect::<Vec<_>>();
+    previous.sort();
+    previous.dedup();
+
+    Ok(match previous.as_slice() {
+        [] => Identity::New,
+        [known] if *known == peer_id => Identity::Known,
+        _ => {
+            previous.retain(|known| *known != peer_id);
+            Identity::Changed { previous }
+        }
+    })
+}
+
+/// Like [`identify`], but loads the known addresses from the database and
+/// warns if the address belonged to another peer before.
+pub async fn check_seller(
+    db: &(dyn Database + Send + Sync),
+    pinned_sellers: &[PinnedSeller],
+    peer_id: PeerId,
+    address: &Multiaddr,
+) -> Result<Identity> {
+    let known_addresses = db.get_all_addresses().await?;
+    let identity = identify(peer_id, address, pinned_sellers, &known_addresses)?;
+
+    match &identity {
+        Identity::New => {
+            tracing::info!(%peer_id, %address, "Seeing this seller for the first time");
+        }
+        Identity::Changed { previous } => {
+            tracing::warn!(
+                %peer_id,
+                %address,
+                ?previous,
+                "The seller's address belonged to a different peer before. Make sure you trust this seller, or pin the expected peer in the config file"
+            );
+        }
+        Identity::Pinned | Identity::Known => {}
+    }
+
+    Ok(identity)
+}
+
+/// The part of an address that identifies where a seller is reachable, i.e.
+/// the host name or onion address and the port, without the peer id.
+pub fn endpoint(address: &Multiaddr) -> Multiaddr {
+    address
+        .iter()
+        .filter(|protocol| !matches!(protocol, Protocol::P2p(_)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "/dns4/seller.example.com/tcp/9939";
+
+    fn address_of(peer_id: PeerId) -> Multiaddr {
+        format!("{}/p2p/{}", ADDRESS, peer_id).parse().unwrap()
+    }
+
+    #[test]
+    fn trusts_peers_on_first_use() {
+        let peer_id = PeerId::random();
+        let address = address_of(peer_id);
+
+        assert_eq!(identify(peer_id, &address, &[], &[]), Ok(Identity::New));
+        assert_eq!(
+            identify(peer_id, &address, &[], &[(peer_id, address.clone())]),
+            Ok(Identity::Known)
+        );
+    }
+
+    #[test]
+    fn detects_address_belonging_to_different_peer() {
+        let peer_id = PeerId::random();
+        let previous = PeerId::random();
+
+        assert_eq!(
+            identify(
+                peer_id,
+                &address_of(peer_id),
+                &[],
+                &[
+                    (previous, address_of(previous)),
+                    (peer_id, address_of(peer_id))
+                ]
+            ),
+            Ok(Identity::Changed {
+                previous: vec![previous]
+            })
+        );
+    }
+
+    #[test]
+    fn refuses_peer_not_matching_the_pin() {
+        let pinned = PeerId::random();
+        let peer_id = PeerId::random();
+        let pinned_sellers = [PinnedSeller {
+            address: ADDRESS.parse().unwrap(),
+            peer_id: pinned,
+        }];
+
+        assert_eq!(
+            identify(pinned, &address_of(pinned), &pinned_sellers, &[]),
+            Ok(Identity::Pinned)
+        );
+        assert_eq!(
+            identify(peer_id, &address_of(peer_id), &pinned_sellers, &[]),
+            Err(PinMismatch {
+                endpoint: ADDRESS.parse().unwrap(),
+                pinned,
+                actual: peer_id
+            })
+        );
+    }
+}
@@ -14,6 +14,12 @@ pub enum Bob {
         btc_amount: bitcoin::Amount,
         #[serde_as(as = "DisplayFromStr")]
         change_address: bitcoin::Address,
+        #[serde(default)]
+        external_funding: Option<crate::bitcoin::ExternalFunding>,
+        #[serde(default)]
+        xmr_amount: Option<crate::monero::Amount>,
+        #[serde(default, with = "::bitcoin::util::amount::serde::as_sat::opt")]
+        max_price: Option<bitcoin::Amount>,
     },
     ExecutionSetupDone {
         state2: bob::State2,
@@ -53,9 +59,15 @@ impl From<BobState> for Bob {
             BobState::Started {
                 btc_amount,
                 change_address,
+                external_funding,
+                xmr_amount,
+                max_price,
             } => Bob::Started {
                 btc_amount,
                 change_address,
+                external_funding,
+                xmr_amount,
+                max_price,
             },
             BobState::SwapSetupCompleted(state2) => Bob::ExecutionSetupDone { state2 },
             BobState::BtcLocked {
@@ -97,9 +109,15 @@ impl From<Bob> for BobState {
             Bob::Started {
                 btc_amount,
                 change_address,
+                external_funding,
+                xmr_amount,
+                max_price,
             } => BobState::Started {
                 btc_amount,
                 change_address,
+                external_funding,
+                xmr_amount,
+                max_price,
             },
             Bob::ExecutionSetupDone { state2 } => BobState::SwapSetupCompleted(state2),
             Bob::BtcLocked {
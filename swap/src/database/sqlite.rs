@@ -1,13 +1,15 @@
 use crate::database::Swap;
 use crate::monero::Address;
 use crate::protocol::{Database, State};
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 use libp2p::{Multiaddr, PeerId};
 use sqlx::sqlite::Sqlite;
 use sqlx::{Pool, SqlitePool};
 use std::path::Path;
 use std::str::FromStr;
+use time::format_description::FormatItem;
+use time::macros::format_description;
 use time::OffsetDateTime;
 use uuid::Uuid;
 
@@ -43,7 +45,7 @@ impl Database for SqliteDatabase {
 
         sqlx::query!(
             r#"
-        insert into peers (
+        insert or replace into peers (
             swap_id,
             peer_id
             ) values (?, ?);
@@ -249,6 +251,302 @@ impl Database for SqliteDatabase {
 
         result
     }
+
+    async fn archive(&self, swap_id: Uuid) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        let archived_at = OffsetDateTime::now_utc();
+
+        let swap_id = swap_id.to_string();
+        let archived_at = archived_at.to_string();
+
+        sqlx::query!(
+            r#"
+        insert or replace into archived_swaps (
+            swap_id,
+            archived_at
+            ) values (?, ?);
+        "#,
+            swap_id,
+            archived_at
+        )
+        .execute(&mut conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn unarchive(&self, swap_id: Uuid) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+
+        let swap_id_str = swap_id.to_string();
+
+        let result = sqlx::query!(
+            r#"
+        DELETE FROM archived_swaps
+        WHERE swap_id = ?
+        "#,
+            swap_id_str
+        )
+        .execute(&mut conn)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            bail!("Swap {} not found among the archived swaps", swap_id)
+        }
+
+        Ok(())
+    }
+
+    async fn archived(&self) -> Result<Vec<Uuid>> {
+        let mut conn = self.pool.acquire().await?;
+
+        let rows = sqlx::query!(
+            r#"
+        SELECT swap_id
+        FROM archived_swaps
+        "#
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        let swap_ids = rows
+            .iter()
+            .map(|row| Uuid::from_str(&row.swap_id))
+            .collect::<Result<Vec<Uuid>, _>>()?;
+
+        Ok(swap_ids)
+    }
+
+    async fn insert_seller(&self, peer_id: PeerId, name: Option<String>) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+
+        let peer_id = peer_id.to_string();
+
+        sqlx::query!(
+            r#"
+        insert into sellers (
+            peer_id,
+            name
+            ) values (?, ?)
+        on conflict(peer_id) do update set name = coalesce(excluded.name, sellers.name), removed = false;
+        "#,
+            peer_id,
+            name
+        )
+        .execute(&mut conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_seller_if_unknown(&self, peer_id: PeerId) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+
+        let peer_id = peer_id.to_string();
+
+        sqlx::query!(
+            r#"
+        insert into sellers (
+            peer_id
+            ) values (?)
+        on conflict(peer_id) do nothing;
+        "#,
+            peer_id
+        )
+        .execute(&mut conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn rename_seller(&self, peer_id: PeerId, name: String) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+
+        let peer_id_str = peer_id.to_string();
+
+        let result = sqlx::query!(
+            r#"
+        UPDATE sellers
+        SET name = ?
+        WHERE peer_id = ? AND NOT removed
+        "#,
+            name,
+            peer_id_str
+        )
+        .execute(&mut conn)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            bail!("Seller {} is not in the address book", peer_id)
+        }
+
+        Ok(())
+    }
+
+    async fn remove_seller(&self, peer_id: PeerId) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+
+        let peer_id_str = peer_id.to_string();
+
+        let result = sqlx::query!(
+            r#"
+        UPDATE sellers
+        SET removed = true
+        WHERE peer_id = ? AND NOT removed
+        "#,
+            peer_id_str
+        )
+        .execute(&mut conn)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            bail!("Seller {} is not in the address book", peer_id)
+        }
+
+        Ok(())
+    }
+
+    async fn get_sellers(&self) -> Result<Vec<(PeerId, Option<String>)>> {
+        let mut conn = self.pool.acquire().await?;
+
+        let rows = sqlx::query!(
+            r#"
+        SELECT peer_id, name
+        FROM sellers
+        WHERE NOT removed
+        "#
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        let sellers = rows
+            .into_iter()
+            .map(|row| {
+                let peer_id = PeerId::from_str(&row.peer_id)?;
+                Ok((peer_id, row.name))
+            })
+            .collect::<Result<Vec<_>>>();
+
+        sellers
+    }
+
+    async fn insert_seller_agent_version(
+        &self,
+        peer_id: PeerId,
+        agent_version: String,
+    ) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+
+        let peer_id = peer_id.to_string();
+
+        sqlx::query!(
+            r#"
+        UPDATE sellers
+        SET agent_version = ?
+        WHERE peer_id = ?
+        "#,
+            agent_version,
+            peer_id
+        )
+        .execute(&mut conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_seller_agent_version(&self, peer_id: PeerId) -> Result<Option<String>> {
+        let mut conn = self.pool.acquire().await?;
+
+        let peer_id = peer_id.to_string();
+
+        let row = sqlx::query!(
+            r#"
+        SELECT agent_version
+        FROM sellers
+        WHERE peer_id = ?
+        "#,
+            peer_id
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+
+        Ok(row.and_then(|row| row.agent_version))
+    }
+
+    async fn get_all_addresses(&self) -> Result<Vec<(PeerId, Multiaddr)>> {
+        let mut conn = self.pool.acquire().await?;
+
+        let rows = sqlx::query!(
+            r#"
+        SELECT peer_id, address
+        FROM peer_addresses
+        "#
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        let addresses = rows
+            .iter()
+            .map(|row| {
+                let peer_id = PeerId::from_str(&row.peer_id)?;
+                let address = Multiaddr::from_str(&row.address)?;
+                Ok((peer_id, address))
+            })
+            .collect::<Result<Vec<_>>>();
+
+        addresses
+    }
+
+    async fn get_seller_history(&self) -> Result<Vec<(PeerId, Uuid, OffsetDateTime, State)>> {
+        let mut conn = self.pool.acquire().await?;
+
+        let rows = sqlx::query!(
+            r#"
+        SELECT peers.peer_id, swap_states.swap_id, swap_states.entered_at, swap_states.state
+        FROM swap_states
+        JOIN peers ON swap_states.swap_id = peers.swap_id
+        ORDER BY swap_states.id
+        "#
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        // The history is only informational, a state we cannot read must not
+        // hide the rest of it.
+        let history = rows
+            .iter()
+            .filter_map(|row| {
+                let entry = || -> Result<_> {
+                    let peer_id = PeerId::from_str(&row.peer_id)?;
+                    let swap_id = Uuid::from_str(&row.swap_id)?;
+                    let entered_at = parse_timestamp(&row.entered_at)?;
+                    let state = State::from(serde_json::from_str::<Swap>(&row.state)?);
+                    Ok((peer_id, swap_id, entered_at, state))
+                };
+
+                match entry() {
+                    Ok(entry) => Some(entry),
+                    Err(error) => {
+                        tracing::warn!(swap_id = %row.swap_id, "Skipping swap state in seller history: {:#}", error);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Ok(history)
+    }
+}
+
+/// How `OffsetDateTime`'s `Display` formats timestamps, which is how they are
+/// stored in the database.
+const STORED_TIMESTAMP: &[FormatItem<'static>] = format_description!(
+    "[year]-[month]-[day] [hour padding:none]:[minute]:[second].[subsecond] [offset_hour sign:mandatory]:[offset_minute]:[offset_second]"
+);
+
+fn parse_timestamp(timestamp: &str) -> Result<OffsetDateTime> {
+    OffsetDateTime::parse(timestamp, STORED_TIMESTAMP)
+        .with_context(|| format!("Invalid timestamp {}", timestamp))
 }
 
 #[cfg(test)]
@@ -258,6 +556,7 @@ mod tests {
     use crate::protocol::bob::BobState;
     use std::fs::File;
     use tempfile::tempdir;
+    use time::{Date, Month, PrimitiveDateTime, Time, UtcOffset};
 
     #[tokio::test]
     async fn test_insert_and_load_state() {
@@ -370,6 +669,170 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_archive_and_unarchive_swap() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        let swap_id_1 = Uuid::new_v4();
+        let swap_id_2 = Uuid::new_v4();
+        db.insert_latest_state(swap_id_1, State::Bob(BobState::SafelyAborted))
+            .await?;
+        db.insert_latest_state(swap_id_2, State::Bob(BobState::SafelyAborted))
+            .await?;
+
+        db.archive(swap_id_1).await?;
+        db.archive(swap_id_1).await?;
+        assert_eq!(db.archived().await?, vec![swap_id_1]);
+
+        // archiving keeps the records of the swap
+        assert_eq!(db.all().await?.len(), 2);
+        assert_eq!(
+            db.get_state(swap_id_1).await?,
+            State::Bob(BobState::SafelyAborted)
+        );
+
+        db.unarchive(swap_id_1).await?;
+        assert!(db.archived().await?.is_empty());
+
+        assert!(db.unarchive(swap_id_1).await.is_err());
+        assert!(db.unarchive(Uuid::new_v4()).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_seller_address_book() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        let peer_id = PeerId::random();
+        db.insert_seller(peer_id, None).await?;
+        db.insert_seller(peer_id, Some("alice".to_owned())).await?;
+        db.insert_seller(peer_id, None).await?;
+        assert_eq!(
+            db.get_sellers().await?,
+            vec![(peer_id, Some("alice".to_owned()))]
+        );
+
+        db.rename_seller(peer_id, "carol".to_owned()).await?;
+        assert_eq!(
+            db.get_sellers().await?,
+            vec![(peer_id, Some("carol".to_owned()))]
+        );
+
+        db.remove_seller(peer_id).await?;
+        assert!(db.get_sellers().await?.is_empty());
+        assert!(db.remove_seller(peer_id).await.is_err());
+        assert!(db.rename_seller(peer_id, "dave".to_owned()).await.is_err());
+
+        db.insert_seller(peer_id, None).await?;
+        assert_eq!(
+            db.get_sellers().await?,
+            vec![(peer_id, Some("carol".to_owned()))]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_removed_seller_stays_removed_when_seen_again() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        let peer_id = PeerId::random();
+        db.insert_seller_if_unknown(peer_id).await?;
+        assert_eq!(db.get_sellers().await?, vec![(peer_id, None)]);
+
+        db.remove_seller(peer_id).await?;
+        db.insert_seller_if_unknown(peer_id).await?;
+        db.insert_seller_agent_version(peer_id, "asb/0.12.0 (xmr-btc-swap-mainnet)".to_owned())
+            .await?;
+        assert!(db.get_sellers().await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_seller_agent_version() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        let peer_id = PeerId::random();
+        assert_eq!(db.get_seller_agent_version(peer_id).await?, None);
+
+        db.insert_seller(peer_id, Some("alice".to_owned())).await?;
+        db.insert_seller_agent_version(peer_id, "asb/0.12.0 (xmr-btc-swap-mainnet)".to_owned())
+            .await?;
+
+        assert_eq!(
+            db.get_seller_agent_version(peer_id).await?,
+            Some("asb/0.12.0 (xmr-btc-swap-mainnet)".to_owned())
+        );
+        assert_eq!(
+            db.get_sellers().await?,
+            vec![(peer_id, Some("alice".to_owned()))]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_seller_history() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        let peer_id = PeerId::random();
+        let swap_id = Uuid::new_v4();
+        db.insert_peer_id(swap_id, peer_id).await?;
+        db.insert_latest_state(swap_id, State::Bob(BobState::SafelyAborted))
+            .await?;
+        db.insert_latest_state(Uuid::new_v4(), State::Bob(BobState::SafelyAborted))
+            .await?;
+
+        let history = db.get_seller_history().await?;
+
+        assert_eq!(history.len(), 1);
+        let (history_peer_id, history_swap_id, _, state) = &history[0];
+        assert_eq!(history_peer_id, &peer_id);
+        assert_eq!(history_swap_id, &swap_id);
+        assert_eq!(state, &State::Bob(BobState::SafelyAborted));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_seller_history_skips_unreadable_states() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        let peer_id = PeerId::random();
+        let swap_id = Uuid::new_v4();
+        db.insert_peer_id(swap_id, peer_id).await?;
+        db.insert_latest_state(swap_id, State::Bob(BobState::SafelyAborted))
+            .await?;
+        sqlx::query("insert into swap_states (swap_id, entered_at, state) values (?, ?, ?)")
+            .bind(swap_id.to_string())
+            .bind(OffsetDateTime::now_utc().to_string())
+            .bind("not a state")
+            .execute(&db.pool)
+            .await?;
+
+        let history = db.get_seller_history().await?;
+
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].3, State::Bob(BobState::SafelyAborted));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parses_stored_timestamps() {
+        let timestamp = OffsetDateTime::now_utc();
+        assert_eq!(parse_timestamp(&timestamp.to_string()).unwrap(), timestamp);
+
+        let timestamp = PrimitiveDateTime::new(
+            Date::from_calendar_date(2023, Month::February, 1).unwrap(),
+            Time::from_hms_milli(0, 5, 0, 120).unwrap(),
+        )
+        .assume_offset(UtcOffset::from_hms(-2, -30, 0).unwrap());
+        assert_eq!(parse_timestamp(&timestamp.to_string()).unwrap(), timestamp);
+    }
+
     async fn setup_test_db() -> Result<SqliteDatabase> {
         let temp_db = tempdir().unwrap().into_path().join("tempdb");
 
@@ -40,9 +40,50 @@ pub struct Mainnet;
 #[derive(Clone, Copy)]
 pub struct Testnet;
 
+#[derive(Clone, Copy)]
+pub struct Signet;
+
 #[derive(Clone, Copy)]
 pub struct Regtest;
 
+/// The networks to swap on, each pairing a Bitcoin network with a Monero
+/// network.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    /// Bitcoin testnet with Monero stagenet.
+    Testnet,
+    /// Bitcoin signet with Monero stagenet.
+    Signet,
+    /// Local Bitcoin and Monero regtest nodes, for development.
+    Regtest,
+}
+
+impl Network {
+    /// Picks the network from the `--testnet`, `--signet` and `--regtest`
+    /// flags.
+    pub fn from_flags(testnet: bool, signet: bool, regtest: bool) -> Self {
+        if regtest {
+            Network::Regtest
+        } else if signet {
+            Network::Signet
+        } else if testnet {
+            Network::Testnet
+        } else {
+            Network::Mainnet
+        }
+    }
+
+    pub fn get_config(self) -> Config {
+        match self {
+            Network::Mainnet => Mainnet::get_config(),
+            Network::Testnet => Testnet::get_config(),
+            Network::Signet => Signet::get_config(),
+            Network::Regtest => Regtest::get_config(),
+        }
+    }
+}
+
 impl GetConfig for Mainnet {
     fn get_config() -> Config {
         Config {
@@ -77,6 +118,15 @@ impl GetConfig for Testnet {
     }
 }
 
+impl GetConfig for Signet {
+    fn get_config() -> Config {
+        Config {
+            bitcoin_network: bitcoin::Network::Signet,
+            ..Testnet::get_config()
+        }
+    }
+}
+
 impl GetConfig for Regtest {
     fn get_config() -> Config {
         Config {
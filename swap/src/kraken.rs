@@ -15,6 +15,15 @@ use url::Url;
 /// price ticker protocol
 /// See: https://docs.kraken.com/websockets/
 pub fn connect(price_ticker_ws_url: Url) -> Result<PriceUpdates> {
+    connect_through_tor(price_ticker_ws_url, None)
+}
+
+/// Like [`connect`], but connects through the Tor SOCKS5 proxy at the given
+/// port, if any.
+pub fn connect_through_tor(
+    price_ticker_ws_url: Url,
+    tor_socks5_port: Option<u16>,
+) -> Result<PriceUpdates> {
     let (price_update, price_update_receiver) = watch::channel(Err(Error::NotYetAvailable));
     let price_update = Arc::new(price_update);
 
@@ -33,7 +42,7 @@ pub fn connect(price_ticker_ws_url: Url) -> Result<PriceUpdates> {
                 let price_update = price_update.clone();
                 let price_ticker_ws_url = price_ticker_ws_url.clone();
                 async move {
-                    let mut stream = connection::new(price_ticker_ws_url).await?;
+                    let mut stream = connection::new(price_ticker_ws_url, tor_socks5_port).await?;
 
                     while let Some(update) = stream.try_next().await.map_err(to_backoff)? {
                         let send_result = price_update.send(Ok(update));
@@ -127,12 +136,39 @@ mod connection {
     use super::*;
     use crate::kraken::wire;
     use futures::stream::BoxStream;
+    use std::net::Ipv4Addr;
+    use tokio_socks::tcp::Socks5Stream;
     use tokio_tungstenite::tungstenite;
 
-    pub async fn new(ws_url: Url) -> Result<BoxStream<'static, Result<wire::PriceUpdate, Error>>> {
-        let (mut rate_stream, _) = tokio_tungstenite::connect_async(ws_url)
-            .await
-            .context("Failed to connect to Kraken websocket API")?;
+    pub async fn new(
+        ws_url: Url,
+        tor_socks5_port: Option<u16>,
+    ) -> Result<BoxStream<'static, Result<wire::PriceUpdate, Error>>> {
+        let (mut rate_stream, _) = match tor_socks5_port {
+            None => tokio_tungstenite::connect_async(ws_url)
+                .await
+                .context("Failed to connect to Kraken websocket API")?,
+            Some(tor_socks5_port) => {
+                let host = ws_url
+                    .host_str()
+                    .context("Websocket URL has no host")?
+                    .to_owned();
+                let port = ws_url
+                    .port_or_known_default()
+                    .context("Websocket URL has no port")?;
+
+                let stream = Socks5Stream::connect(
+                    (Ipv4Addr::LOCALHOST, tor_socks5_port),
+                    (host.as_str(), port),
+                )
+                .await
+                .context("Failed to connect to Kraken websocket API through Tor")?;
+
+                tokio_tungstenite::client_async_tls(ws_url, stream.into_inner())
+                    .await
+                    .context("Failed to connect to Kraken websocket API")?
+            }
+        };
 
         rate_stream
             .send(SUBSCRIBE_XMR_BTC_TICKER_PAYLOAD.into())
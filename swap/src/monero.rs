@@ -121,6 +121,56 @@ impl Amount {
         Some(bitcoin::Amount::from_sat(satoshi))
     }
 
+    /// Calculate the minimum amount of Bitcoin that has to be sold at a given
+    /// asking price to receive at least this amount of Monero after paying the
+    /// median fee.
+    pub fn min_bitcoin_for_price(&self, ask_price: bitcoin::Amount) -> Option<bitcoin::Amount> {
+        let pico_plus_fee = self.as_piconero().checked_add(MONERO_FEE.as_piconero())?;
+
+        // safely convert the BTC/XMR rate to sat/pico
+        let ask_sats = Decimal::from(ask_price.to_sat());
+        let pico_per_xmr = Decimal::from(PICONERO_OFFSET);
+
+        let pico = Decimal::from(pico_plus_fee);
+        let min_sats = pico
+            .checked_mul(ask_sats)?
+            .checked_div(pico_per_xmr)?
+            .ceil();
+        let satoshi = min_sats.to_u64()?;
+
+        Some(bitcoin::Amount::from_sat(satoshi))
+    }
+
+    /// Calculate the price per XMR paid when buying this amount of Monero for
+    /// `btc`, rounded up to the next satoshi.
+    pub fn price_for_bitcoin(&self, btc: bitcoin::Amount) -> Option<bitcoin::Amount> {
+        let sats = Decimal::from(btc.to_sat());
+        let pico_per_xmr = Decimal::from(PICONERO_OFFSET);
+
+        let pico = Decimal::from(self.as_piconero());
+        let price_sats = sats.checked_mul(pico_per_xmr)?.checked_div(pico)?.ceil();
+        let satoshi = price_sats.to_u64()?;
+
+        Some(bitcoin::Amount::from_sat(satoshi))
+    }
+
+    /// Calculate the amount of Monero a seller asking `ask_price` per XMR
+    /// offers for `btc`. Fractions of a piconero are rounded down, like the
+    /// seller does.
+    pub fn for_bitcoin_at_price(btc: bitcoin::Amount, ask_price: bitcoin::Amount) -> Option<Self> {
+        let sats = Decimal::from(btc.to_sat());
+        let pico_per_xmr = Decimal::from(PICONERO_OFFSET);
+
+        let ask_sats = Decimal::from(ask_price.to_sat());
+        let pico = sats
+            .checked_mul(pico_per_xmr)?
+            .checked_div(ask_sats)?
+            .floor();
+        let piconero = pico.to_u64()?;
+
+        Some(Amount::from_piconero(piconero))
+    }
+
     pub fn from_monero(amount: f64) -> Result<Self> {
         let decimal = Decimal::try_from(amount)?;
         Self::from_decimal(decimal)
@@ -443,6 +493,86 @@ mod tests {
         assert_eq!(btc, bitcoin::Amount::from_sat(566_656));
     }
 
+    #[test]
+    fn min_bitcoin_to_trade() {
+        let ask = bitcoin::Amount::from_btc(1.0).unwrap();
+        let xmr = Amount::parse_monero("1.0").unwrap() - MONERO_FEE;
+        let btc = xmr.min_bitcoin_for_price(ask).unwrap();
+
+        assert_eq!(btc, bitcoin::Amount::from_btc(1.0).unwrap());
+
+        let ask = bitcoin::Amount::from_sat(382_900);
+        let xmr = Amount::parse_monero("10").unwrap() - MONERO_FEE;
+        let btc = xmr.min_bitcoin_for_price(ask).unwrap();
+
+        assert_eq!(btc, bitcoin::Amount::from_sat(3_829_000));
+
+        // fractions of a satoshi are rounded up so the seller's quote covers the
+        // requested amount
+        let ask = bitcoin::Amount::from_sat(685_800);
+        let xmr = Amount::parse_monero("0.826286435921").unwrap();
+        let btc = xmr.min_bitcoin_for_price(ask).unwrap();
+
+        assert_eq!(btc, bitcoin::Amount::from_sat(566_679));
+    }
+
+    #[test]
+    fn price_paid_for_bitcoin() {
+        let xmr = Amount::parse_monero("10").unwrap();
+        let price = xmr
+            .price_for_bitcoin(bitcoin::Amount::from_sat(3_829_000))
+            .unwrap();
+
+        assert_eq!(price, bitcoin::Amount::from_sat(382_900));
+
+        // fractions of a satoshi are rounded up so the price is never understated
+        let xmr = Amount::parse_monero("3").unwrap();
+        let price = xmr
+            .price_for_bitcoin(bitcoin::Amount::from_sat(1_000_000))
+            .unwrap();
+
+        assert_eq!(price, bitcoin::Amount::from_sat(333_334));
+
+        assert_eq!(
+            Amount::ZERO.price_for_bitcoin(bitcoin::Amount::from_sat(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn monero_for_bitcoin_at_price() {
+        let price = bitcoin::Amount::from_sat(685_800);
+        let btc = bitcoin::Amount::from_sat(1_000_000);
+
+        let xmr = Amount::for_bitcoin_at_price(btc, price).unwrap();
+
+        assert_eq!(xmr, Amount::from_piconero(1_458_151_064_450));
+        assert_eq!(
+            Amount::for_bitcoin_at_price(btc, bitcoin::Amount::ZERO),
+            None
+        );
+    }
+
+    #[test]
+    fn price_equal_to_the_quote_is_not_above_it() {
+        let price = bitcoin::Amount::from_sat(685_800);
+        let btc = bitcoin::Amount::from_sat(1_000_000);
+        let xmr = crate::asb::Rate::new(price, Decimal::ZERO)
+            .sell_quote(btc)
+            .unwrap();
+
+        // the seller rounds the amount of XMR down, which makes the implied
+        // price slightly higher than the quote
+        assert_eq!(
+            xmr.price_for_bitcoin(btc).unwrap(),
+            bitcoin::Amount::from_sat(685_801)
+        );
+        assert!(xmr >= Amount::for_bitcoin_at_price(btc, price).unwrap());
+        assert!(
+            xmr < Amount::for_bitcoin_at_price(btc, price - bitcoin::Amount::from_sat(1)).unwrap()
+        );
+    }
+
     #[test]
     fn max_bitcoin_to_trade_overflow() {
         let xmr = Amount::from_monero(30.0).unwrap();
@@ -70,18 +70,24 @@ impl Wallet {
     }
 
     /// Close the wallet and open (load) another wallet by generating it from
-    /// keys. The generated wallet will remain loaded.
-    pub async fn create_from_and_load(
+    /// keys, refresh it and sweep all funds to the given address. The
+    /// generated wallet will remain loaded.
+    ///
+    /// The wallet RPC can only have one wallet loaded, the lock is held for
+    /// the whole sequence so that concurrent swaps cannot load their wallet in
+    /// between and have their funds swept to the wrong address.
+    pub async fn sweep_from_keys(
         &self,
         file_name: String,
         private_spend_key: PrivateKey,
         private_view_key: PrivateViewKey,
         restore_height: BlockHeight,
-    ) -> Result<()> {
+        address: Address,
+    ) -> Result<Vec<TxHash>> {
         let public_spend_key = PublicKey::from_private_key(&private_spend_key);
         let public_view_key = PublicKey::from_private_key(&private_view_key.into());
 
-        let address = Address::standard(self.network, public_spend_key, public_view_key);
+        let generated_address = Address::standard(self.network, public_spend_key, public_view_key);
 
         let wallet = self.inner.lock().await;
 
@@ -92,10 +98,10 @@ impl Wallet {
             .await
             .context("Failed to close wallet")?;
 
-        let _ = wallet
+        if let Err(e) = wallet
             .generate_from_keys(
-                file_name,
-                address.to_string(),
+                file_name.clone(),
+                generated_address.to_string(),
                 private_spend_key.to_string(),
                 PrivateKey::from(private_view_key).to_string(),
                 restore_height.height,
@@ -103,9 +109,24 @@ impl Wallet {
                 true,
             )
             .await
-            .context("Failed to generate new wallet from keys")?;
+        {
+            // In case we failed to refresh/sweep, when resuming the wallet might already
+            // exist! This is a very unlikely scenario, but if we don't take care of it we
+            // might not be able to ever transfer the Monero.
+            tracing::warn!("Failed to generate monero wallet from keys: {:#}", e);
+            tracing::info!(wallet_file_name = %file_name,
+                "Falling back to trying to open the the wallet if it already exists",
+            );
+            wallet.open_wallet(file_name).await?;
+        }
 
-        Ok(())
+        // Ensure that the generated wallet is synced so we have a proper balance
+        wallet.refresh().await?;
+        // Sweep (transfer all funds) to the given address
+        let sweep_all = wallet.sweep_all(address.to_string()).await?;
+
+        let tx_hashes = sweep_all.tx_hash_list.into_iter().map(TxHash).collect();
+        Ok(tx_hashes)
     }
 
     /// Close the wallet and open (load) another wallet by generating it from
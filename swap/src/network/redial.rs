@@ -8,6 +8,7 @@ use libp2p::swarm::dial_opts::{DialOpts, PeerCondition};
 use libp2p::swarm::protocols_handler::DummyProtocolsHandler;
 use libp2p::swarm::{NetworkBehaviour, NetworkBehaviourAction, PollParameters};
 use libp2p::PeerId;
+use std::collections::HashMap;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::Duration;
@@ -19,11 +20,16 @@ pub enum OutEvent {
 }
 
 /// A [`NetworkBehaviour`] that tracks whether we are connected to the given
-/// peer and attempts to re-establish a connection with an exponential backoff
-/// if we lose the connection.
+/// peers and attempts to re-establish a connection with an exponential backoff
+/// if we lose the connection to one of them.
 pub struct Behaviour {
-    /// The peer we are interested in.
-    peer: PeerId,
+    /// The peers we are interested in.
+    peers: HashMap<PeerId, Redial>,
+    /// The initial interval between re-dials.
+    interval: Duration,
+}
+
+struct Redial {
     /// If present, tracks for how long we need to sleep until we dial again.
     sleep: Option<Pin<Box<Sleep>>>,
     /// Tracks the current backoff state.
@@ -31,9 +37,18 @@ pub struct Behaviour {
 }
 
 impl Behaviour {
-    pub fn new(peer: PeerId, interval: Duration) -> Self {
+    pub fn new(interval: Duration) -> Self {
         Self {
-            peer,
+            peers: HashMap::default(),
+            interval,
+        }
+    }
+
+    /// Starts tracking the connection to the given peer.
+    pub fn add_peer(&mut self, peer: PeerId) {
+        let interval = self.interval;
+
+        self.peers.entry(peer).or_insert_with(|| Redial {
             sleep: None,
             backoff: ExponentialBackoff {
                 initial_interval: interval,
@@ -42,11 +57,18 @@ impl Behaviour {
                 max_elapsed_time: Some(Duration::from_secs(5 * 60)),
                 ..ExponentialBackoff::default()
             },
-        }
+        });
+    }
+
+    /// Stops tracking the connection to the given peer.
+    pub fn remove_peer(&mut self, peer: &PeerId) {
+        self.peers.remove(peer);
     }
 
-    pub fn until_next_redial(&self) -> Option<Duration> {
+    pub fn until_next_redial(&self, peer: &PeerId) -> Option<Duration> {
         let until_next_redial = self
+            .peers
+            .get(peer)?
             .sleep
             .as_ref()?
             .deadline()
@@ -69,23 +91,27 @@ impl NetworkBehaviour for Behaviour {
     }
 
     fn inject_connected(&mut self, peer_id: &PeerId) {
-        if peer_id != &self.peer {
-            return;
-        }
+        let redial = match self.peers.get_mut(peer_id) {
+            Some(redial) => redial,
+            None => return,
+        };
 
         // established a connection to the desired peer, cancel any active re-dialling
-        self.sleep = None;
+        redial.sleep = None;
     }
 
     fn inject_disconnected(&mut self, peer_id: &PeerId) {
-        if peer_id != &self.peer {
-            return;
-        }
+        let redial = match self.peers.get_mut(peer_id) {
+            Some(redial) => redial,
+            None => return,
+        };
 
         // lost connection to the configured peer, trigger re-dialling with an
         // exponential backoff
-        self.backoff.reset();
-        self.sleep = Some(Box::pin(tokio::time::sleep(self.backoff.initial_interval)));
+        redial.backoff.reset();
+        redial.sleep = Some(Box::pin(tokio::time::sleep(
+            redial.backoff.initial_interval,
+        )));
     }
 
     fn inject_event(&mut self, _: PeerId, _: ConnectionId, _: Void) {}
@@ -95,30 +121,38 @@ impl NetworkBehaviour for Behaviour {
         cx: &mut Context<'_>,
         _: &mut impl PollParameters,
     ) -> Poll<NetworkBehaviourAction<Self::OutEvent, Self::ProtocolsHandler>> {
-        let sleep = match self.sleep.as_mut() {
-            None => return Poll::Pending, // early exit if we shouldn't be re-dialling
-            Some(future) => future,
-        };
-
-        futures::ready!(sleep.poll_unpin(cx));
-
-        let next_dial_in = match self.backoff.next_backoff() {
-            Some(next_dial_in) => next_dial_in,
-            None => {
-                return Poll::Ready(NetworkBehaviourAction::GenerateEvent(
-                    OutEvent::AllAttemptsExhausted { peer: self.peer },
-                ));
+        for (peer, redial) in self.peers.iter_mut() {
+            let sleep = match redial.sleep.as_mut() {
+                None => continue, // skip peers we shouldn't be re-dialling
+                Some(future) => future,
+            };
+
+            if sleep.poll_unpin(cx).is_pending() {
+                continue;
             }
-        };
 
-        self.sleep = Some(Box::pin(tokio::time::sleep(next_dial_in)));
+            let next_dial_in = match redial.backoff.next_backoff() {
+                Some(next_dial_in) => next_dial_in,
+                None => {
+                    redial.sleep = None;
+
+                    return Poll::Ready(NetworkBehaviourAction::GenerateEvent(
+                        OutEvent::AllAttemptsExhausted { peer: *peer },
+                    ));
+                }
+            };
+
+            redial.sleep = Some(Box::pin(tokio::time::sleep(next_dial_in)));
+
+            return Poll::Ready(NetworkBehaviourAction::Dial {
+                opts: DialOpts::peer_id(*peer)
+                    .condition(PeerCondition::Disconnected)
+                    .build(),
+                handler: Self::ProtocolsHandler::default(),
+            });
+        }
 
-        Poll::Ready(NetworkBehaviourAction::Dial {
-            opts: DialOpts::peer_id(self.peer)
-                .condition(PeerCondition::Disconnected)
-                .build(),
-            handler: Self::ProtocolsHandler::default(),
-        })
+        Poll::Pending
     }
 }
 
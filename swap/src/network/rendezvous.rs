@@ -1,3 +1,4 @@
+use crate::env;
 use libp2p::rendezvous::Namespace;
 use std::fmt;
 
@@ -5,16 +6,22 @@ use std::fmt;
 pub enum XmrBtcNamespace {
     Mainnet,
     Testnet,
+    Signet,
+    Regtest,
 }
 
 const MAINNET: &str = "xmr-btc-swap-mainnet";
 const TESTNET: &str = "xmr-btc-swap-testnet";
+const SIGNET: &str = "xmr-btc-swap-signet";
+const REGTEST: &str = "xmr-btc-swap-regtest";
 
 impl fmt::Display for XmrBtcNamespace {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             XmrBtcNamespace::Mainnet => write!(f, "{}", MAINNET),
             XmrBtcNamespace::Testnet => write!(f, "{}", TESTNET),
+            XmrBtcNamespace::Signet => write!(f, "{}", SIGNET),
+            XmrBtcNamespace::Regtest => write!(f, "{}", REGTEST),
         }
     }
 }
@@ -24,16 +31,19 @@ impl From<XmrBtcNamespace> for Namespace {
         match namespace {
             XmrBtcNamespace::Mainnet => Namespace::from_static(MAINNET),
             XmrBtcNamespace::Testnet => Namespace::from_static(TESTNET),
+            XmrBtcNamespace::Signet => Namespace::from_static(SIGNET),
+            XmrBtcNamespace::Regtest => Namespace::from_static(REGTEST),
         }
     }
 }
 
 impl XmrBtcNamespace {
-    pub fn from_is_testnet(testnet: bool) -> XmrBtcNamespace {
-        if testnet {
-            XmrBtcNamespace::Testnet
-        } else {
-            XmrBtcNamespace::Mainnet
+    pub fn from_network(network: env::Network) -> XmrBtcNamespace {
+        match network {
+            env::Network::Mainnet => XmrBtcNamespace::Mainnet,
+            env::Network::Testnet => XmrBtcNamespace::Testnet,
+            env::Network::Signet => XmrBtcNamespace::Signet,
+            env::Network::Regtest => XmrBtcNamespace::Regtest,
         }
     }
 }
@@ -71,10 +71,18 @@ impl WalletSnapshot {
             .unwrap_or(bitcoin_wallet.new_address().await?);
 
         let redeem_fee = bitcoin_wallet
-            .estimate_fee(bitcoin::TxRedeem::weight(), transfer_amount)
+            .estimate_fee(
+                bitcoin::TxKind::Redeem,
+                bitcoin::TxRedeem::weight(&redeem_address),
+                transfer_amount,
+            )
             .await?;
         let punish_fee = bitcoin_wallet
-            .estimate_fee(bitcoin::TxPunish::weight(), transfer_amount)
+            .estimate_fee(
+                bitcoin::TxKind::Punish,
+                bitcoin::TxPunish::weight(&punish_address),
+                transfer_amount,
+            )
             .await?;
 
         Ok(Self {
@@ -6,8 +6,9 @@ use crate::protocol::bob::{State0, State2};
 use crate::protocol::{Message1, Message3};
 use crate::{bitcoin, cli, env, monero};
 use anyhow::Result;
-use futures::future::{BoxFuture, OptionFuture};
-use futures::{AsyncWriteExt, FutureExt};
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
+use futures::{AsyncWriteExt, FutureExt, StreamExt};
 use libp2p::core::connection::ConnectionId;
 use libp2p::core::upgrade;
 use libp2p::swarm::{
@@ -48,7 +49,10 @@ impl Behaviour {
 
 impl From<Completed> for cli::OutEvent {
     fn from(completed: Completed) -> Self {
-        cli::OutEvent::SwapSetupCompleted(Box::new(completed.0))
+        cli::OutEvent::SwapSetupCompleted {
+            swap_id: completed.0,
+            result: Box::new(completed.1),
+        }
     }
 }
 
@@ -93,10 +97,10 @@ impl NetworkBehaviour for Behaviour {
     }
 }
 
-type OutboundStream = BoxFuture<'static, Result<State2>>;
+type OutboundStream = BoxFuture<'static, (Uuid, Result<State2>)>;
 
 pub struct Handler {
-    outbound_stream: OptionFuture<OutboundStream>,
+    outbound_streams: FuturesUnordered<OutboundStream>,
     env_config: env::Config,
     timeout: Duration,
     new_swaps: VecDeque<NewSwap>,
@@ -108,7 +112,7 @@ impl Handler {
     fn new(env_config: env::Config, bitcoin_wallet: Arc<bitcoin::Wallet>) -> Self {
         Self {
             env_config,
-            outbound_stream: OptionFuture::from(None),
+            outbound_streams: FuturesUnordered::new(),
             timeout: Duration::from_secs(120),
             new_swaps: VecDeque::default(),
             bitcoin_wallet,
@@ -124,10 +128,17 @@ pub struct NewSwap {
     pub tx_refund_fee: bitcoin::Amount,
     pub tx_cancel_fee: bitcoin::Amount,
     pub bitcoin_refund_address: bitcoin::Address,
+    pub external_funding: Option<bitcoin::ExternalFunding>,
+    /// The amount of Monero to receive after paying the fee of sweeping it to
+    /// the receive address, the seller has to offer this plus the median fee.
+    pub xmr_amount: Option<monero::Amount>,
+    /// The maximum price per XMR Bob is willing to pay.
+    pub max_price: Option<bitcoin::Amount>,
 }
 
+/// The result of the swap setup of the swap with the given id.
 #[derive(Debug)]
-pub struct Completed(Result<State2>);
+pub struct Completed(Uuid, Result<State2>);
 
 impl ProtocolsHandler for Handler {
     type InEvent = NewSwap;
@@ -153,6 +164,7 @@ impl ProtocolsHandler for Handler {
     ) {
         let bitcoin_wallet = self.bitcoin_wallet.clone();
         let env_config = self.env_config;
+        let swap_id = info.swap_id;
 
         let protocol = tokio::time::timeout(self.timeout, async move {
             write_cbor_message(
@@ -169,6 +181,33 @@ impl ProtocolsHandler for Handler {
 
             let xmr = Result::from(read_cbor_message::<SpotPriceResponse>(&mut substream).await?)?;
 
+            if let Some(requested) = info.xmr_amount {
+                if xmr < requested + monero::MONERO_FEE {
+                    return Err(Error::XmrAmountBelowRequested {
+                        requested,
+                        offered: xmr,
+                    }
+                    .into());
+                }
+            }
+
+            if let Some(max) = info.max_price {
+                // Compare against the XMR offered at the maximum price rather than the
+                // implied price, the seller rounds the amount of XMR down
+                match monero::Amount::for_bitcoin_at_price(info.btc, max) {
+                    Some(min_xmr) if xmr >= min_xmr => {}
+                    _ => {
+                        return Err(Error::PriceAboveMaximum {
+                            offered: xmr
+                                .price_for_bitcoin(info.btc)
+                                .unwrap_or(bitcoin::Amount::MAX),
+                            max,
+                        }
+                        .into());
+                    }
+                }
+            }
+
             let state0 = State0::new(
                 info.swap_id,
                 &mut rand::thread_rng(),
@@ -180,6 +219,7 @@ impl ProtocolsHandler for Handler {
                 env_config.monero_finality_confirmations,
                 info.tx_refund_fee,
                 info.tx_cancel_fee,
+                info.external_funding,
             );
 
             write_cbor_message(&mut substream, state0.next_message()).await?;
@@ -199,14 +239,20 @@ impl ProtocolsHandler for Handler {
         });
 
         let max_seconds = self.timeout.as_secs();
-        self.outbound_stream = OptionFuture::from(Some(
+        self.outbound_streams.push(
             async move {
-                protocol.await.map_err(|_| Error::Timeout {
-                    seconds: max_seconds,
-                })?
+                let result = match protocol.await {
+                    Ok(result) => result,
+                    Err(_) => Err(Error::Timeout {
+                        seconds: max_seconds,
+                    }
+                    .into()),
+                };
+
+                (swap_id, result)
             }
             .boxed(),
-        ));
+        );
     }
 
     fn inject_event(&mut self, new_swap: Self::InEvent) {
@@ -243,9 +289,8 @@ impl ProtocolsHandler for Handler {
             });
         }
 
-        if let Some(result) = futures::ready!(self.outbound_stream.poll_unpin(cx)) {
-            self.outbound_stream = OptionFuture::from(None);
-            return Poll::Ready(ProtocolsHandlerEvent::Custom(Completed(result)));
+        if let Poll::Ready(Some((swap_id, result))) = self.outbound_streams.poll_next_unpin(cx) {
+            return Poll::Ready(ProtocolsHandlerEvent::Custom(Completed(swap_id, result)));
         }
 
         Poll::Pending
@@ -284,6 +329,20 @@ pub enum Error {
         asb: BlockchainNetwork,
     },
 
+    #[error("Seller offered {offered} which does not cover the requested {requested} plus the Monero network fee")]
+    XmrAmountBelowRequested {
+        requested: monero::Amount,
+        offered: monero::Amount,
+    },
+
+    #[error(
+        "Seller offered a price of {offered} per XMR which is above the maximum price of {max}"
+    )]
+    PriceAboveMaximum {
+        offered: bitcoin::Amount,
+        max: bitcoin::Amount,
+    },
+
     #[error("Failed to complete swap setup within {seconds}s")]
     Timeout { seconds: u64 },
 
@@ -293,6 +352,31 @@ pub enum Error {
     Other,
 }
 
+impl Error {
+    /// Whether the seller declined the swap request, in which case another
+    /// seller might still accept it.
+    pub fn is_declined_by_seller(&self) -> bool {
+        matches!(
+            self,
+            Error::NoSwapsAccepted
+                | Error::AmountBelowMinimum { .. }
+                | Error::AmountAboveMaximum { .. }
+                | Error::BalanceTooLow { .. }
+                | Error::BlockchainNetworkMismatch { .. }
+                | Error::Other
+        )
+    }
+
+    /// Whether we rejected the seller's offer during swap setup, nothing has
+    /// been locked in that case.
+    pub fn is_offer_rejected(&self) -> bool {
+        matches!(
+            self,
+            Error::XmrAmountBelowRequested { .. } | Error::PriceAboveMaximum { .. }
+        )
+    }
+}
+
 impl From<SpotPriceError> for Error {
     fn from(error: SpotPriceError) -> Self {
         match error {
@@ -12,6 +12,7 @@ use sha2::Sha256;
 use sigma_fun::ext::dl_secp256k1_ed25519_eq::{CrossCurveDLEQ, CrossCurveDLEQProof};
 use sigma_fun::HashTranscript;
 use std::convert::TryInto;
+use time::OffsetDateTime;
 use uuid::Uuid;
 
 pub mod alice;
@@ -142,4 +143,29 @@ pub trait Database {
     async fn insert_latest_state(&self, swap_id: Uuid, state: State) -> Result<()>;
     async fn get_state(&self, swap_id: Uuid) -> Result<State>;
     async fn all(&self) -> Result<Vec<(Uuid, State)>>;
+    async fn archive(&self, swap_id: Uuid) -> Result<()>;
+    /// Fails if the swap is not archived.
+    async fn unarchive(&self, swap_id: Uuid) -> Result<()>;
+    async fn archived(&self) -> Result<Vec<Uuid>>;
+    /// Adds the seller to the address book, also if it was removed before.
+    async fn insert_seller(&self, peer_id: PeerId, name: Option<String>) -> Result<()>;
+    /// Adds the seller to the address book unless it is known already,
+    /// sellers that were removed stay removed.
+    async fn insert_seller_if_unknown(&self, peer_id: PeerId) -> Result<()>;
+    async fn rename_seller(&self, peer_id: PeerId, name: String) -> Result<()>;
+    async fn remove_seller(&self, peer_id: PeerId) -> Result<()>;
+    async fn get_sellers(&self) -> Result<Vec<(PeerId, Option<String>)>>;
+    /// Remembers the agent version the seller advertised over the identify
+    /// protocol, if the seller is known.
+    async fn insert_seller_agent_version(
+        &self,
+        peer_id: PeerId,
+        agent_version: String,
+    ) -> Result<()>;
+    async fn get_seller_agent_version(&self, peer_id: PeerId) -> Result<Option<String>>;
+    /// All addresses of all peers we know of.
+    async fn get_all_addresses(&self) -> Result<Vec<(PeerId, Multiaddr)>>;
+    /// All states of all swaps together with the seller of the swap and the
+    /// time the state was entered, in the order they were entered.
+    async fn get_seller_history(&self) -> Result<Vec<(PeerId, Uuid, OffsetDateTime, State)>>;
 }
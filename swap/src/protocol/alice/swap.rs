@@ -111,6 +111,18 @@ where
             }
         }
         AliceState::BtcLocked { state3 } => {
+            // Never lock Monero against a lock transaction that was reorganized out of the
+            // blockchain since we saw it being final.
+            let tx_lock_status = bitcoin_wallet.status_of_script(&state3.tx_lock).await?;
+            if !tx_lock_status.is_confirmed_with(env_config.bitcoin_finality_confirmations) {
+                tracing::warn!(
+                    txid = %state3.tx_lock.txid(),
+                    status = %tx_lock_status,
+                    "Bitcoin lock transaction is no longer final, waiting for it to be confirmed again"
+                );
+                return Ok(AliceState::BtcLockTransactionSeen { state3 });
+            }
+
             match state3.expired_timelocks(bitcoin_wallet).await? {
                 ExpiredTimelocks::None => {
                     // Record the current monero wallet block height so we don't have to scan from
@@ -285,6 +297,19 @@ where
             state3,
         } => {
             if state3.check_for_tx_cancel(bitcoin_wallet).await.is_err() {
+                if let ExpiredTimelocks::None = state3.expired_timelocks(bitcoin_wallet).await? {
+                    // A reorg moved the lock transaction into a later block since the cancel
+                    // timelock expired.
+                    tracing::warn!(
+                        "Cancel timelock is no longer expired, waiting for it to expire again"
+                    );
+                    bitcoin_wallet
+                        .subscribe_to(state3.tx_lock.clone())
+                        .await
+                        .wait_until_confirmed_with(state3.cancel_timelock)
+                        .await?;
+                }
+
                 // If Bob hasn't yet broadcasted the cancel transaction, Alice has to publish it
                 // to be able to eventually punish. Since the punish timelock is
                 // relative to the publication of the cancel transaction we have to ensure it
@@ -359,6 +384,23 @@ where
             transfer_proof,
             state3,
         } => {
+            if !matches!(
+                state3.expired_timelocks(bitcoin_wallet).await?,
+                ExpiredTimelocks::Punish
+            ) {
+                // A reorg moved the cancel transaction into a later block since the punish
+                // timelock expired. If the lock transaction was reorganized as well the node
+                // dropped the cancel transaction, which is published again if needed.
+                tracing::warn!(
+                    "Punish timelock is no longer expired, waiting for it to expire again"
+                );
+                return Ok(AliceState::CancelTimelockExpired {
+                    monero_wallet_restore_blockheight,
+                    transfer_proof,
+                    state3,
+                });
+            }
+
             let punish = state3.punish_btc(bitcoin_wallet).await;
 
             match punish {
@@ -1,8 +1,10 @@
 use std::sync::Arc;
 
 use anyhow::Result;
+use async_trait::async_trait;
 use uuid::Uuid;
 
+use crate::cli::lock_confirmation::LockConfirmation;
 use crate::protocol::Database;
 use crate::{bitcoin, cli, env, monero};
 
@@ -22,6 +24,11 @@ pub struct Swap {
     pub env_config: env::Config,
     pub id: Uuid,
     pub monero_receive_address: monero::Address,
+    /// Signs the lock transaction if it is funded by an external wallet.
+    pub external_signer: Option<Arc<dyn ExternalSigner>>,
+    /// Asks the user to confirm the swap before the Bitcoin is locked. If not
+    /// set, the Bitcoin is locked right after swap setup.
+    pub lock_confirmation: Option<LockConfirmation>,
 }
 
 impl Swap {
@@ -36,11 +43,16 @@ impl Swap {
         monero_receive_address: monero::Address,
         bitcoin_change_address: bitcoin::Address,
         btc_amount: bitcoin::Amount,
+        external_funding: Option<bitcoin::ExternalFunding>,
+        xmr_amount: Option<monero::Amount>,
     ) -> Self {
         Self {
             state: BobState::Started {
                 btc_amount,
                 change_address: bitcoin_change_address,
+                external_funding,
+                xmr_amount,
+                max_price: None,
             },
             event_loop_handle,
             db,
@@ -49,9 +61,30 @@ impl Swap {
             env_config,
             id,
             monero_receive_address,
+            external_signer: None,
+            lock_confirmation: None,
         }
     }
 
+    pub fn with_external_signer(mut self, external_signer: Arc<dyn ExternalSigner>) -> Self {
+        self.external_signer = Some(external_signer);
+        self
+    }
+
+    pub fn with_lock_confirmation(mut self, lock_confirmation: LockConfirmation) -> Self {
+        self.lock_confirmation = Some(lock_confirmation);
+        self
+    }
+
+    /// Aborts the swap during swap setup if the seller asks for more than
+    /// `max_price` per XMR.
+    pub fn with_max_price(mut self, limit: Option<bitcoin::Amount>) -> Self {
+        if let BobState::Started { max_price, .. } = &mut self.state {
+            *max_price = limit;
+        }
+        self
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub async fn from_db(
         db: Arc<dyn Database + Send + Sync>,
@@ -73,6 +106,18 @@ impl Swap {
             env_config,
             id,
             monero_receive_address,
+            external_signer: None,
+            lock_confirmation: None,
         })
     }
 }
+
+/// Signs the Bitcoin lock transaction of a swap funded by an external wallet.
+#[async_trait]
+pub trait ExternalSigner: Send + Sync {
+    async fn sign(
+        &self,
+        swap_id: Uuid,
+        psbt: bitcoin::PartiallySignedTransaction,
+    ) -> Result<bitcoin::PartiallySignedTransaction>;
+}
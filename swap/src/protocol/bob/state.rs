@@ -26,6 +26,9 @@ pub enum BobState {
     Started {
         btc_amount: bitcoin::Amount,
         change_address: bitcoin::Address,
+        external_funding: Option<bitcoin::ExternalFunding>,
+        xmr_amount: Option<monero::Amount>,
+        max_price: Option<bitcoin::Amount>,
     },
     SwapSetupCompleted(State2),
     BtcLocked {
@@ -91,6 +94,7 @@ pub struct State0 {
     min_monero_confirmations: u64,
     tx_refund_fee: bitcoin::Amount,
     tx_cancel_fee: bitcoin::Amount,
+    external_funding: Option<bitcoin::ExternalFunding>,
 }
 
 impl State0 {
@@ -106,6 +110,7 @@ impl State0 {
         min_monero_confirmations: u64,
         tx_refund_fee: bitcoin::Amount,
         tx_cancel_fee: bitcoin::Amount,
+        external_funding: Option<bitcoin::ExternalFunding>,
     ) -> Self {
         let b = bitcoin::SecretKey::new_random(rng);
 
@@ -132,6 +137,7 @@ impl State0 {
             min_monero_confirmations,
             tx_refund_fee,
             tx_cancel_fee,
+            external_funding,
         }
     }
 
@@ -173,14 +179,33 @@ impl State0 {
             bail!("Alice's dleq proof doesn't verify")
         }
 
-        let tx_lock = bitcoin::TxLock::new(
-            wallet,
-            self.btc,
-            msg.A,
-            self.b.public(),
-            self.refund_address.clone(),
-        )
-        .await?;
+        let externally_funded = self.external_funding.is_some();
+        let tx_lock = match self.external_funding {
+            Some(funding) => {
+                let tx_lock =
+                    bitcoin::TxLock::from_external_funding(funding, msg.A, self.b.public())?;
+
+                if tx_lock.lock_amount() != self.btc {
+                    bail!(
+                        "Externally funded lock transaction pays {} instead of {}",
+                        tx_lock.lock_amount(),
+                        self.btc
+                    )
+                }
+
+                tx_lock
+            }
+            None => {
+                bitcoin::TxLock::new(
+                    wallet,
+                    self.btc,
+                    msg.A,
+                    self.b.public(),
+                    self.refund_address.clone(),
+                )
+                .await?
+            }
+        };
         let v = msg.v_a + self.v_b;
 
         Ok(State1 {
@@ -202,6 +227,7 @@ impl State0 {
             tx_refund_fee: self.tx_refund_fee,
             tx_punish_fee: msg.tx_punish_fee,
             tx_cancel_fee: self.tx_cancel_fee,
+            externally_funded,
         })
     }
 }
@@ -226,6 +252,7 @@ pub struct State1 {
     tx_refund_fee: bitcoin::Amount,
     tx_punish_fee: bitcoin::Amount,
     tx_cancel_fee: bitcoin::Amount,
+    externally_funded: bool,
 }
 
 impl State1 {
@@ -275,10 +302,31 @@ impl State1 {
             tx_refund_fee: self.tx_refund_fee,
             tx_punish_fee: self.tx_punish_fee,
             tx_cancel_fee: self.tx_cancel_fee,
+            externally_funded: self.externally_funded,
         })
     }
 }
 
+/// What Bob is about to commit to by locking the Bitcoin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockSummary {
+    pub btc_lock_amount: bitcoin::Amount,
+    /// Unknown if the funding inputs of the lock transaction are not known.
+    pub tx_lock_fee: Option<bitcoin::Amount>,
+    pub tx_cancel_fee: bitcoin::Amount,
+    pub tx_refund_fee: bitcoin::Amount,
+    pub xmr: monero::Amount,
+    pub cancel_timelock: CancelTimelock,
+    pub punish_timelock: PunishTimelock,
+}
+
+impl LockSummary {
+    /// The price paid per XMR.
+    pub fn price(&self) -> Option<bitcoin::Amount> {
+        self.xmr.price_for_bitcoin(self.btc_lock_amount)
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct State2 {
     A: bitcoin::PublicKey,
@@ -305,6 +353,9 @@ pub struct State2 {
     tx_refund_fee: bitcoin::Amount,
     #[serde(with = "::bitcoin::util::amount::serde::as_sat")]
     tx_cancel_fee: bitcoin::Amount,
+    /// Whether the lock transaction has to be signed by an external wallet.
+    #[serde(default)]
+    externally_funded: bool,
 }
 
 impl State2 {
@@ -331,6 +382,26 @@ impl State2 {
         }
     }
 
+    pub fn is_externally_funded(&self) -> bool {
+        self.externally_funded
+    }
+
+    pub fn tx_lock(&self) -> &TxLock {
+        &self.tx_lock
+    }
+
+    pub fn lock_summary(&self) -> LockSummary {
+        LockSummary {
+            btc_lock_amount: self.tx_lock.lock_amount(),
+            tx_lock_fee: self.tx_lock.fee(),
+            tx_cancel_fee: self.tx_cancel_fee,
+            tx_refund_fee: self.tx_refund_fee,
+            xmr: self.xmr,
+            cancel_timelock: self.cancel_timelock,
+            punish_timelock: self.punish_timelock,
+        }
+    }
+
     pub async fn lock_btc(self) -> Result<(State3, TxLock)> {
         Ok((
             State3 {
@@ -439,6 +510,16 @@ impl State3 {
         self.tx_lock.txid()
     }
 
+    /// Verifies the lock transaction signed by an external wallet and returns
+    /// it ready for broadcasting.
+    pub fn signed_tx_lock(
+        &self,
+        signed: bitcoin::PartiallySignedTransaction,
+    ) -> Result<Transaction> {
+        self.tx_lock
+            .extract_externally_signed(signed, self.A, self.b.public())
+    }
+
     pub async fn current_epoch(
         &self,
         bitcoin_wallet: &bitcoin::Wallet,
@@ -624,6 +705,17 @@ impl State6 {
         ))
     }
 
+    pub async fn wait_for_cancel_timelock_to_expire(
+        &self,
+        bitcoin_wallet: &bitcoin::Wallet,
+    ) -> Result<()> {
+        bitcoin_wallet
+            .subscribe_to(self.tx_lock.clone())
+            .await
+            .wait_until_confirmed_with(self.cancel_timelock)
+            .await
+    }
+
     pub async fn check_for_tx_cancel(
         &self,
         bitcoin_wallet: &bitcoin::Wallet,
@@ -645,21 +737,19 @@ impl State6 {
         &self,
         bitcoin_wallet: &bitcoin::Wallet,
     ) -> Result<(Txid, Subscription)> {
-        let transaction = bitcoin::TxCancel::new(
-            &self.tx_lock,
-            self.cancel_timelock,
-            self.A,
-            self.b.public(),
-            self.tx_cancel_fee,
-        )
-        .complete_as_bob(self.A, self.b.clone(), self.tx_cancel_sig_a.clone())
-        .context("Failed to complete Bitcoin cancel transaction")?;
+        let transaction = self.signed_cancel_transaction()?;
 
         let (tx_id, subscription) = bitcoin_wallet.broadcast(transaction, "cancel").await?;
 
         Ok((tx_id, subscription))
     }
 
+    pub fn signed_cancel_transaction(&self) -> Result<Transaction> {
+        self.tx_cancel()
+            .complete_as_bob(self.A, self.b.clone(), self.tx_cancel_sig_a.clone())
+            .context("Failed to complete Bitcoin cancel transaction")
+    }
+
     pub async fn publish_refund_btc(&self, bitcoin_wallet: &bitcoin::Wallet) -> Result<()> {
         let signed_tx_refund = self.signed_refund_transaction()?;
         bitcoin_wallet.broadcast(signed_tx_refund, "refund").await?;
@@ -668,15 +758,7 @@ impl State6 {
     }
 
     pub fn signed_refund_transaction(&self) -> Result<Transaction> {
-        let tx_cancel = bitcoin::TxCancel::new(
-            &self.tx_lock,
-            self.cancel_timelock,
-            self.A,
-            self.b.public(),
-            self.tx_cancel_fee,
-        );
-        let tx_refund =
-            bitcoin::TxRefund::new(&tx_cancel, &self.refund_address, self.tx_refund_fee);
+        let tx_refund = self.tx_refund();
 
         let adaptor = Adaptor::<HashTranscript<Sha256>, Deterministic<Sha256>>::default();
 
@@ -692,4 +774,26 @@ impl State6 {
     pub fn tx_lock_id(&self) -> bitcoin::Txid {
         self.tx_lock.txid()
     }
+
+    pub fn tx_lock(&self) -> &bitcoin::TxLock {
+        &self.tx_lock
+    }
+
+    pub fn tx_cancel(&self) -> TxCancel {
+        TxCancel::new(
+            &self.tx_lock,
+            self.cancel_timelock,
+            self.A,
+            self.b.public(),
+            self.tx_cancel_fee,
+        )
+    }
+
+    pub fn tx_refund(&self) -> bitcoin::TxRefund {
+        bitcoin::TxRefund::new(&self.tx_cancel(), &self.refund_address, self.tx_refund_fee)
+    }
+
+    pub fn cancel_timelock(&self) -> CancelTimelock {
+        self.cancel_timelock
+    }
 }
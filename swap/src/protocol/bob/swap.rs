@@ -1,13 +1,21 @@
-use crate::bitcoin::{ExpiredTimelocks, TxCancel, TxRefund};
+use crate::bitcoin::{ExpiredTimelocks, TxCancel, TxKind, TxRefund};
+use crate::cli::lock_confirmation::LockConfirmation;
 use crate::cli::EventLoopHandle;
-use crate::network::swap_setup::bob::NewSwap;
+use crate::network::swap_setup::bob::{Error as SwapSetupError, NewSwap};
 use crate::protocol::bob;
 use crate::protocol::bob::state::*;
+use crate::protocol::bob::ExternalSigner;
 use crate::{bitcoin, monero};
 use anyhow::{bail, Context, Result};
 use tokio::select;
 use uuid::Uuid;
 
+fn is_offer_rejected(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<SwapSetupError>()
+        .map_or(false, SwapSetupError::is_offer_rejected)
+}
+
 pub fn is_complete(state: &BobState) -> bool {
     matches!(
         state,
@@ -30,15 +38,29 @@ pub async fn run_until(
     let mut current_state = swap.state;
 
     while !is_target_state(&current_state) {
-        current_state = next_state(
+        current_state = match next_state(
             swap.id,
             current_state.clone(),
             &mut swap.event_loop_handle,
             swap.bitcoin_wallet.as_ref(),
             swap.monero_wallet.as_ref(),
             swap.monero_receive_address,
+            swap.external_signer.as_deref(),
+            swap.lock_confirmation.as_ref(),
         )
-        .await?;
+        .await
+        {
+            Ok(state) => state,
+            Err(error) if is_offer_rejected(&error) => {
+                // Nothing has been locked yet, so it is safe to abort
+                swap.db
+                    .insert_latest_state(swap.id, BobState::SafelyAborted.into())
+                    .await?;
+
+                return Err(error);
+            }
+            Err(error) => return Err(error),
+        };
 
         swap.db
             .insert_latest_state(swap.id, current_state.clone().into())
@@ -48,6 +70,7 @@ pub async fn run_until(
     Ok(current_state)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn next_state(
     swap_id: Uuid,
     state: BobState,
@@ -55,6 +78,8 @@ async fn next_state(
     bitcoin_wallet: &bitcoin::Wallet,
     monero_wallet: &monero::Wallet,
     monero_receive_address: monero::Address,
+    external_signer: Option<&dyn ExternalSigner>,
+    lock_confirmation: Option<&LockConfirmation>,
 ) -> Result<BobState> {
     tracing::debug!(%state, "Advancing state");
 
@@ -62,12 +87,19 @@ async fn next_state(
         BobState::Started {
             btc_amount,
             change_address,
+            external_funding,
+            xmr_amount,
+            max_price,
         } => {
             let tx_refund_fee = bitcoin_wallet
-                .estimate_fee(TxRefund::weight(), btc_amount)
+                .estimate_fee(
+                    TxKind::Refund,
+                    TxRefund::weight(&change_address),
+                    btc_amount,
+                )
                 .await?;
             let tx_cancel_fee = bitcoin_wallet
-                .estimate_fee(TxCancel::weight(), btc_amount)
+                .estimate_fee(TxKind::Cancel, TxCancel::weight(), btc_amount)
                 .await?;
 
             let state2 = event_loop_handle
@@ -77,6 +109,9 @@ async fn next_state(
                     tx_refund_fee,
                     tx_cancel_fee,
                     bitcoin_refund_address: change_address,
+                    external_funding,
+                    xmr_amount,
+                    max_price,
                 })
                 .await?;
 
@@ -85,6 +120,13 @@ async fn next_state(
             BobState::SwapSetupCompleted(state2)
         }
         BobState::SwapSetupCompleted(state2) => {
+            if let Some(lock_confirmation) = lock_confirmation {
+                if !lock_confirmation.confirm(state2.lock_summary()).await? {
+                    tracing::info!(%swap_id, "Swap aborted by the user, no Bitcoin was locked");
+                    return Ok(BobState::SafelyAborted);
+                }
+            }
+
             // Record the current monero wallet block height so we don't have to scan from
             // block 0 once we create the redeem wallet.
             // This has to be done **before** the Bitcoin is locked in order to ensure that
@@ -97,11 +139,22 @@ async fn next_state(
             let monero_wallet_restore_blockheight = monero_wallet.block_height().await?;
 
             // Alice and Bob have exchanged info
+            let externally_funded = state2.is_externally_funded();
             let (state3, tx_lock) = state2.lock_btc().await?;
-            let signed_tx = bitcoin_wallet
-                .sign_and_finalize(tx_lock.clone().into())
-                .await
-                .context("Failed to sign Bitcoin lock transaction")?;
+            let signed_tx = if externally_funded {
+                let signed_psbt = external_signer
+                    .context("Swap is funded by an external wallet but there is no signer for it")?
+                    .sign(swap_id, tx_lock.into())
+                    .await
+                    .context("Failed to get signed Bitcoin lock transaction")?;
+
+                state3.signed_tx_lock(signed_psbt)?
+            } else {
+                bitcoin_wallet
+                    .sign_and_finalize(tx_lock.into())
+                    .await
+                    .context("Failed to sign Bitcoin lock transaction")?
+            };
             let (..) = bitcoin_wallet.broadcast(signed_tx, "lock").await?;
 
             BobState::BtcLocked {
@@ -226,30 +279,15 @@ async fn next_state(
         BobState::BtcRedeemed(state) => {
             let (spend_key, view_key) = state.xmr_keys();
 
-            let wallet_file_name = swap_id.to_string();
-            if let Err(e) = monero_wallet
-                .create_from_and_load(
-                    wallet_file_name.clone(),
+            let tx_hashes = monero_wallet
+                .sweep_from_keys(
+                    swap_id.to_string(),
                     spend_key,
                     view_key,
                     state.monero_wallet_restore_blockheight,
+                    monero_receive_address,
                 )
-                .await
-            {
-                // In case we failed to refresh/sweep, when resuming the wallet might already
-                // exist! This is a very unlikely scenario, but if we don't take care of it we
-                // might not be able to ever transfer the Monero.
-                tracing::warn!("Failed to generate monero wallet from keys: {:#}", e);
-                tracing::info!(%wallet_file_name,
-                    "Falling back to trying to open the the wallet if it already exists",
-                );
-                monero_wallet.open(wallet_file_name).await?;
-            }
-
-            // Ensure that the generated wallet is synced so we have a proper balance
-            monero_wallet.refresh().await?;
-            // Sweep (transfer all funds) to the given address
-            let tx_hashes = monero_wallet.sweep_all(monero_receive_address).await?;
+                .await?;
 
             for tx_hash in tx_hashes {
                 tracing::info!(%monero_receive_address, txid=%tx_hash.0, "Successfully transferred XMR to wallet");
@@ -261,6 +299,17 @@ async fn next_state(
         }
         BobState::CancelTimelockExpired(state4) => {
             if state4.check_for_tx_cancel(bitcoin_wallet).await.is_err() {
+                if let ExpiredTimelocks::None = state4.expired_timelock(bitcoin_wallet).await? {
+                    // A reorg moved the lock transaction into a later block since the cancel
+                    // timelock expired.
+                    tracing::warn!(
+                        "Cancel timelock is no longer expired, waiting for it to expire again"
+                    );
+                    state4
+                        .wait_for_cancel_timelock_to_expire(bitcoin_wallet)
+                        .await?;
+                }
+
                 state4.submit_tx_cancel(bitcoin_wallet).await?;
             }
 
@@ -270,9 +319,18 @@ async fn next_state(
             // Bob has cancelled the swap
             match state.expired_timelock(bitcoin_wallet).await? {
                 ExpiredTimelocks::None => {
-                    bail!(
-                        "Internal error: canceled state reached before cancel timelock was expired"
+                    // A reorg moved the lock transaction into a later block since the cancel
+                    // timelock expired.
+                    tracing::warn!(
+                        "Cancel timelock is no longer expired, waiting for it to expire again"
                     );
+                    state
+                        .wait_for_cancel_timelock_to_expire(bitcoin_wallet)
+                        .await?;
+
+                    // The node drops the cancel transaction once it is no longer valid, make sure
+                    // it is published again
+                    BobState::CancelTimelockExpired(state)
                 }
                 ExpiredTimelocks::Cancel => {
                     state.publish_refund_btc(bitcoin_wallet).await?;
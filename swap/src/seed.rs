@@ -4,18 +4,56 @@ use ::bitcoin::secp256k1::{self, SecretKey};
 use anyhow::{Context, Result};
 use bdk::bitcoin::util::bip32::ExtendedPrivKey;
 use bitcoin::hashes::{sha256, Hash, HashEngine};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::Password;
 use libp2p::identity;
 use pem::{encode, Pem};
 use rand::prelude::*;
 use std::ffi::OsStr;
 use std::fmt;
-use std::fs::{self, File};
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use torut::onion::TorSecretKeyV3;
+use zeroize::Zeroizing;
 
 pub const SEED_LENGTH: usize = 32;
 
+/// Environment variable from which the seed passphrase is read.
+pub const PASSPHRASE_ENV_VAR: &str = "SWAP_SEED_PASSPHRASE";
+/// Environment variable naming a file descriptor from which the seed
+/// passphrase is read. Useful for services that don't want the passphrase to
+/// show up in the process environment.
+pub const PASSPHRASE_FD_ENV_VAR: &str = "SWAP_SEED_PASSPHRASE_FD";
+/// Environment variable from which a new passphrase is read when encrypting a
+/// seed or changing its passphrase non-interactively.
+pub const NEW_PASSPHRASE_ENV_VAR: &str = "SWAP_SEED_NEW_PASSPHRASE";
+
+const SEED_FILE_NAME: &str = "seed.pem";
+const PEM_TAG: &str = "SEED";
+const ENCRYPTED_PEM_TAG: &str = "ENCRYPTED SEED";
+
+const ENCRYPTION_VERSION: u8 = 1;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+const KEY_LENGTH: usize = 32;
+// version || log_n || r || p || salt || nonce || ciphertext || tag
+const ENCRYPTED_LENGTH: usize =
+    1 + 1 + 4 + 4 + SALT_LENGTH + NONCE_LENGTH + SEED_LENGTH + TAG_LENGTH;
+
+// 2^15 * 8 * 128 bytes = 32 MiB of memory per key derivation
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+// Seed files with higher parameters are rejected instead of exhausting the
+// memory or CPU of whoever reads them, 2^20 * 8 * 128 bytes are 1 GiB.
+const MAX_SCRYPT_LOG_N: u8 = 20;
+const MAX_SCRYPT_R: u32 = 8;
+const MAX_SCRYPT_P: u32 = 16;
+
 #[derive(Eq, PartialEq)]
 pub struct Seed([u8; SEED_LENGTH]);
 
@@ -56,8 +94,14 @@ impl Seed {
         esk.to_bytes().into()
     }
 
+    /// Reads the seed from the data directory or generates a new one.
+    ///
+    /// Both plain and passphrase encrypted seed files are supported. The
+    /// passphrase for an encrypted seed is taken from the environment if
+    /// available, otherwise the user is prompted for it. A newly generated
+    /// seed is only encrypted if a passphrase is set in the environment.
     pub fn from_file_or_generate(data_dir: &Path) -> Result<Self, Error> {
-        let file_path_buf = data_dir.join("seed.pem");
+        let file_path_buf = data_dir.join(SEED_FILE_NAME);
         let file_path = Path::new(&file_path_buf);
 
         if file_path.exists() {
@@ -67,11 +111,49 @@ impl Seed {
         tracing::debug!("No seed file found, creating at {}", file_path.display());
 
         let random_seed = Seed::random()?;
-        random_seed.write_to(file_path.to_path_buf())?;
+
+        match Passphrase::from_env()? {
+            Some(passphrase) => {
+                random_seed.write_encrypted_to(file_path.to_path_buf(), &passphrase)?
+            }
+            None => random_seed.write_to(file_path.to_path_buf())?,
+        }
 
         Ok(random_seed)
     }
 
+    /// Returns whether the seed file in the data directory is encrypted.
+    pub fn is_encrypted(data_dir: &Path) -> Result<bool, Error> {
+        let contents = fs::read_to_string(data_dir.join(SEED_FILE_NAME))?;
+        let pem = pem::parse(contents)?;
+
+        Ok(pem.tag == ENCRYPTED_PEM_TAG)
+    }
+
+    /// Encrypts the seed with the given passphrase and replaces the seed file
+    /// in the data directory.
+    ///
+    /// This is used both for migrating a plain seed file to the encrypted
+    /// format and for changing the passphrase of an encrypted seed file.
+    pub fn encrypt_file(&self, data_dir: &Path, passphrase: &Passphrase) -> Result<(), Error> {
+        let file_path = data_dir.join(SEED_FILE_NAME);
+
+        // Write to a temporary file first so that the old seed file stays intact
+        // if anything goes wrong.
+        let tmp_path = data_dir.join(format!("{}.tmp", SEED_FILE_NAME));
+        self.write_encrypted_to(tmp_path.clone(), passphrase)?;
+
+        let rinsed = Self::from_encrypted_file(&tmp_path, passphrase)?;
+        if &rinsed != self {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(Error::Decryption);
+        }
+
+        fs::rename(&tmp_path, &file_path)?;
+
+        Ok(())
+    }
+
     /// Derive a new seed using the given scope.
     ///
     /// This function is purposely kept private because it is only a helper
@@ -102,9 +184,64 @@ impl Seed {
 
         tracing::debug!("Reading in seed from {}", file.display());
 
+        if pem.tag == ENCRYPTED_PEM_TAG {
+            let passphrase = match Passphrase::from_env()? {
+                Some(passphrase) => passphrase,
+                None => Passphrase::prompt("Enter the passphrase of your seed file")?,
+            };
+
+            return Self::from_encrypted_pem(pem, &passphrase);
+        }
+
         Self::from_pem(pem)
     }
 
+    fn from_encrypted_file(seed_file: &Path, passphrase: &Passphrase) -> Result<Self, Error> {
+        let contents = fs::read_to_string(seed_file)?;
+        let pem = pem::parse(contents)?;
+
+        Self::from_encrypted_pem(pem, passphrase)
+    }
+
+    fn from_encrypted_pem(pem: pem::Pem, passphrase: &Passphrase) -> Result<Self, Error> {
+        let contents = pem.contents;
+
+        if contents.len() != ENCRYPTED_LENGTH {
+            return Err(Error::IncorrectLength(contents.len()));
+        }
+
+        let (version, rest) = contents.split_at(1);
+        if version[0] != ENCRYPTION_VERSION {
+            return Err(Error::UnsupportedVersion(version[0]));
+        }
+
+        let (log_n, rest) = rest.split_at(1);
+        let (r, rest) = rest.split_at(4);
+        let (p, rest) = rest.split_at(4);
+        let (salt, rest) = rest.split_at(SALT_LENGTH);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+
+        let log_n = log_n[0];
+        let r = u32::from_be_bytes(r.try_into().expect("4 bytes"));
+        let p = u32::from_be_bytes(p.try_into().expect("4 bytes"));
+        if log_n > MAX_SCRYPT_LOG_N || r > MAX_SCRYPT_R || p > MAX_SCRYPT_P {
+            return Err(Error::KdfParamsTooHigh { log_n, r, p });
+        }
+
+        let params = scrypt::Params::new(log_n, r, p).map_err(|_| Error::Kdf)?;
+        let key = derive_encryption_key(passphrase, salt, &params)?;
+
+        let plaintext = ChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map(Zeroizing::new)
+            .map_err(|_| Error::Decryption)?;
+
+        let mut array = [0; SEED_LENGTH];
+        array.copy_from_slice(&plaintext);
+
+        Ok(Self::from(array))
+    }
+
     fn from_pem(pem: pem::Pem) -> Result<Self, Error> {
         if pem.contents.len() != SEED_LENGTH {
             Err(Error::IncorrectLength(pem.contents.len()))
@@ -123,7 +260,7 @@ impl Seed {
 
         let data = self.bytes();
         let pem = Pem {
-            tag: String::from("SEED"),
+            tag: String::from(PEM_TAG),
             contents: data.to_vec(),
         };
 
@@ -134,6 +271,154 @@ impl Seed {
 
         Ok(())
     }
+
+    fn write_encrypted_to(&self, seed_file: PathBuf, passphrase: &Passphrase) -> Result<(), Error> {
+        ensure_directory_exists(&seed_file)?;
+
+        let mut salt = [0u8; SALT_LENGTH];
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rand::thread_rng().try_fill_bytes(&mut salt)?;
+        rand::thread_rng().try_fill_bytes(&mut nonce)?;
+
+        let params =
+            scrypt::Params::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P).map_err(|_| Error::Kdf)?;
+        let key = derive_encryption_key(passphrase, &salt, &params)?;
+
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
+            .encrypt(Nonce::from_slice(&nonce), self.bytes().as_ref())
+            .map_err(|_| Error::Encryption)?;
+
+        let mut contents = Vec::with_capacity(ENCRYPTED_LENGTH);
+        contents.push(ENCRYPTION_VERSION);
+        contents.push(SCRYPT_LOG_N);
+        contents.extend_from_slice(&SCRYPT_R.to_be_bytes());
+        contents.extend_from_slice(&SCRYPT_P.to_be_bytes());
+        contents.extend_from_slice(&salt);
+        contents.extend_from_slice(&nonce);
+        contents.extend_from_slice(&ciphertext);
+
+        let pem = Pem {
+            tag: String::from(ENCRYPTED_PEM_TAG),
+            contents,
+        };
+
+        let pem_string = encode(&pem);
+
+        let mut file = private_file(&seed_file)?;
+        file.write_all(pem_string.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+fn derive_encryption_key(
+    passphrase: &Passphrase,
+    salt: &[u8],
+    params: &scrypt::Params,
+) -> Result<Zeroizing<[u8; KEY_LENGTH]>, Error> {
+    let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+    scrypt::scrypt(passphrase.0.as_bytes(), salt, params, key.as_mut_slice())
+        .map_err(|_| Error::Kdf)?;
+
+    Ok(key)
+}
+
+#[cfg(unix)]
+fn private_file(path: &Path) -> io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn private_file(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+}
+
+/// The passphrase used to encrypt the seed file at rest, it is wiped from
+/// memory when dropped.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Passphrase(Zeroizing<String>);
+
+impl Passphrase {
+    pub fn new(passphrase: impl Into<String>) -> Result<Self, Error> {
+        let passphrase = Zeroizing::new(passphrase.into());
+
+        if passphrase.is_empty() {
+            return Err(Error::EmptyPassphrase);
+        }
+
+        Ok(Self(passphrase))
+    }
+
+    /// Reads the passphrase from [`PASSPHRASE_ENV_VAR`] or from the file
+    /// descriptor given in [`PASSPHRASE_FD_ENV_VAR`].
+    pub fn from_env() -> Result<Option<Self>, Error> {
+        if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV_VAR) {
+            return Ok(Some(Self::new(passphrase)?));
+        }
+
+        if let Ok(fd) = std::env::var(PASSPHRASE_FD_ENV_VAR) {
+            let fd = fd
+                .parse::<u32>()
+                .map_err(|_| Error::InvalidFileDescriptor(fd))?;
+
+            return Ok(Some(Self::from_fd(fd)?));
+        }
+
+        Ok(None)
+    }
+
+    /// Reads a new passphrase from [`NEW_PASSPHRASE_ENV_VAR`] or asks the user
+    /// to enter it twice.
+    pub fn new_from_env_or_prompt() -> Result<Self, Error> {
+        if let Ok(passphrase) = std::env::var(NEW_PASSPHRASE_ENV_VAR) {
+            return Self::new(passphrase);
+        }
+
+        let passphrase = Password::with_theme(&ColorfulTheme::default())
+            .with_prompt("Enter the new passphrase for your seed file")
+            .with_confirmation("Repeat the passphrase", "The passphrases do not match")
+            .interact()?;
+
+        Self::new(passphrase)
+    }
+
+    fn prompt(prompt: &str) -> Result<Self, Error> {
+        let passphrase = Password::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .interact()?;
+
+        Self::new(passphrase)
+    }
+
+    #[cfg(unix)]
+    fn from_fd(fd: u32) -> Result<Self, Error> {
+        let contents = Zeroizing::new(fs::read_to_string(format!("/dev/fd/{}", fd))?);
+        let passphrase = contents.lines().next().unwrap_or_default();
+
+        Self::new(passphrase)
+    }
+
+    #[cfg(not(unix))]
+    fn from_fd(fd: u32) -> Result<Self, Error> {
+        Err(Error::InvalidFileDescriptor(fd.to_string()))
+    }
+}
+
+impl fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Passphrase([*****])")
+    }
 }
 
 impl fmt::Debug for Seed {
@@ -168,6 +453,20 @@ pub enum Error {
     Rand(#[from] rand::Error),
     #[error("no default path")]
     NoDefaultPath,
+    #[error("unsupported seed encryption version {0}")]
+    UnsupportedVersion(u8),
+    #[error("failed to derive encryption key from passphrase")]
+    Kdf,
+    #[error("scrypt parameters log_n={log_n}, r={r}, p={p} of the seed file exceed the supported limits")]
+    KdfParamsTooHigh { log_n: u8, r: u32, p: u32 },
+    #[error("failed to encrypt seed")]
+    Encryption,
+    #[error("failed to decrypt seed, the passphrase is wrong or the seed file is corrupted")]
+    Decryption,
+    #[error("the seed passphrase must not be empty")]
+    EmptyPassphrase,
+    #[error("invalid file descriptor for seed passphrase: {0}")]
+    InvalidFileDescriptor(String),
 }
 
 #[cfg(test)]
@@ -254,4 +553,75 @@ mbKANv2qKGmNVg1qtquj6Hx1pFPelpqOfE2JaJJAMEg1FlFhNRNlFlE=
         let rinsed = Seed::from_file(tmpfile).expect("Read from temp file");
         assert_eq!(seed.0, rinsed.0);
     }
+
+    #[test]
+    fn round_trip_through_encrypted_file_write_read() {
+        let tmpfile = temp_dir().join("encrypted_seed.pem");
+        let passphrase = Passphrase::new("correct horse battery staple").unwrap();
+
+        let seed = Seed::random().unwrap();
+        seed.write_encrypted_to(tmpfile.clone(), &passphrase)
+            .expect("Write encrypted seed to temp file");
+
+        let rinsed = Seed::from_encrypted_file(&tmpfile, &passphrase).expect("Read from temp file");
+        assert_eq!(seed.0, rinsed.0);
+    }
+
+    #[test]
+    fn encrypted_file_with_wrong_passphrase_fails() {
+        let tmpfile = temp_dir().join("encrypted_seed_wrong_passphrase.pem");
+        let passphrase = Passphrase::new("correct horse battery staple").unwrap();
+        let wrong_passphrase = Passphrase::new("incorrect horse battery staple").unwrap();
+
+        let seed = Seed::random().unwrap();
+        seed.write_encrypted_to(tmpfile.clone(), &passphrase)
+            .expect("Write encrypted seed to temp file");
+
+        match Seed::from_encrypted_file(&tmpfile, &wrong_passphrase) {
+            Err(Error::Decryption) => {} // pass
+            _ => panic!("should fail with Decryption error"),
+        }
+    }
+
+    #[test]
+    fn encrypt_legacy_seed_file_and_change_passphrase() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let first = Passphrase::new("first").unwrap();
+        let second = Passphrase::new("second").unwrap();
+
+        let seed = Seed::random().unwrap();
+        seed.write_to(data_dir.path().join(SEED_FILE_NAME)).unwrap();
+        assert!(!Seed::is_encrypted(data_dir.path()).unwrap());
+
+        seed.encrypt_file(data_dir.path(), &first).unwrap();
+        assert!(Seed::is_encrypted(data_dir.path()).unwrap());
+
+        seed.encrypt_file(data_dir.path(), &second).unwrap();
+        let file = data_dir.path().join(SEED_FILE_NAME);
+        assert!(Seed::from_encrypted_file(&file, &first).is_err());
+        assert_eq!(Seed::from_encrypted_file(&file, &second).unwrap(), seed);
+    }
+
+    #[test]
+    fn encrypted_seed_with_excessive_scrypt_parameters_is_rejected() {
+        let passphrase = Passphrase::new("correct horse battery staple").unwrap();
+        let mut contents = vec![ENCRYPTION_VERSION, 40];
+        contents.extend_from_slice(&SCRYPT_R.to_be_bytes());
+        contents.extend_from_slice(&SCRYPT_P.to_be_bytes());
+        contents.resize(ENCRYPTED_LENGTH, 0);
+        let pem = Pem {
+            tag: String::from(ENCRYPTED_PEM_TAG),
+            contents,
+        };
+
+        assert!(matches!(
+            Seed::from_encrypted_pem(pem, &passphrase),
+            Err(Error::KdfParamsTooHigh { log_n: 40, .. })
+        ));
+    }
+
+    #[test]
+    fn empty_passphrase_is_rejected() {
+        assert!(matches!(Passphrase::new(""), Err(Error::EmptyPassphrase)));
+    }
 }
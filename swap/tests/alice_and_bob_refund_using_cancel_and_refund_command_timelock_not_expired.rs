@@ -5,6 +5,7 @@ use harness::bob_run_until::is_btc_locked;
 use harness::SlowCancelConfig;
 use swap::asb::FixedRate;
 use swap::bitcoin::{parse_rpc_error_code, RpcErrorCode};
+use swap::cli::cancel_and_refund::{NotYetValid, Validity};
 use swap::protocol::alice::AliceState;
 use swap::protocol::bob::BobState;
 use swap::protocol::{alice, bob};
@@ -42,10 +43,10 @@ async fn given_alice_and_bob_manually_cancel_when_timelock_not_expired_errors()
         let error = cli::cancel(bob_swap.id, bob_swap.bitcoin_wallet, bob_swap.db)
             .await
             .unwrap_err();
-        assert_eq!(
-            parse_rpc_error_code(&error).unwrap(),
-            i64::from(RpcErrorCode::RpcVerifyRejected)
-        );
+        assert!(matches!(
+            error.downcast_ref::<NotYetValid>(),
+            Some(NotYetValid::Cancel(Validity::InBlocks(_)))
+        ));
 
         ctx.restart_alice().await;
         let alice_swap = ctx.alice_next_swap().await;
@@ -72,10 +73,10 @@ async fn given_alice_and_bob_manually_cancel_when_timelock_not_expired_errors()
         let error = cli::refund(bob_swap.id, bob_swap.bitcoin_wallet, bob_swap.db)
             .await
             .unwrap_err();
-        assert_eq!(
-            parse_rpc_error_code(&error).unwrap(),
-            i64::from(RpcErrorCode::RpcVerifyError)
-        );
+        assert!(matches!(
+            error.downcast_ref::<NotYetValid>(),
+            Some(NotYetValid::Refund(Validity::InBlocks(_)))
+        ));
 
         let (bob_swap, _) = ctx
             .stop_and_resume_bob_from_db(bob_join_handle, swap_id)
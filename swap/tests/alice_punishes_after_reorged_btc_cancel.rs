@@ -0,0 +1,82 @@
+pub mod harness;
+
+use harness::alice_run_until::{is_btc_cancelled, is_xmr_lock_transaction_sent};
+use harness::bob_run_until::is_btc_locked;
+use harness::{BitcoinBackend, FastPunishConfig};
+use swap::asb::FixedRate;
+use swap::protocol::alice::AliceState;
+use swap::protocol::bob::BobState;
+use swap::protocol::{alice, bob};
+
+/// Bob does not act after locking the Bitcoin. Alice cancels, the cancel
+/// transaction is reorganized out of the blockchain and Alice waits for it to
+/// confirm again before punishing.
+#[tokio::test]
+async fn given_btc_cancel_is_reorged_out_alice_waits_for_it_to_confirm_again_before_punishing() {
+    harness::setup_test_with_bitcoin_backend(
+        FastPunishConfig,
+        BitcoinBackend::Bitcoind,
+        |mut ctx| async move {
+            let (bob_swap, bob_join_handle) = ctx.bob_swap().await;
+            let bob_swap_id = bob_swap.id;
+            let bob_swap = tokio::spawn(bob::run_until(bob_swap, is_btc_locked));
+
+            let alice_swap = ctx.alice_next_swap().await;
+            let alice_bitcoin_wallet = alice_swap.bitcoin_wallet.clone();
+            let alice_swap = tokio::spawn(alice::run_until(
+                alice_swap,
+                is_xmr_lock_transaction_sent,
+                FixedRate::default(),
+            ));
+
+            let bob_state = bob_swap.await??;
+            assert!(matches!(bob_state, BobState::BtcLocked { .. }));
+
+            let alice_state = alice_swap.await??;
+            if let AliceState::XmrLockTransactionSent { state3, .. } = alice_state {
+                alice_bitcoin_wallet
+                    .subscribe_to(state3.tx_lock)
+                    .await
+                    .wait_until_confirmed_with(state3.cancel_timelock)
+                    .await?;
+            } else {
+                panic!("Alice in unexpected state {}", alice_state);
+            }
+
+            ctx.restart_alice().await;
+            let alice_swap = ctx.alice_next_swap().await;
+            let alice_state =
+                alice::run_until(alice_swap, is_btc_cancelled, FixedRate::default()).await?;
+            let tx_cancel = match &alice_state {
+                AliceState::BtcCancelled { state3, .. } => state3.tx_cancel(),
+                _ => panic!("Alice in unexpected state {}", alice_state),
+            };
+            alice_bitcoin_wallet
+                .subscribe_to(tx_cancel.clone())
+                .await
+                .wait_until_final()
+                .await?;
+
+            ctx.reorg_out(&tx_cancel).await?;
+
+            ctx.restart_alice().await;
+            let alice_swap = ctx.alice_next_swap().await;
+            assert!(matches!(alice_swap.state, AliceState::BtcCancelled { .. }));
+            let alice_swap = tokio::spawn(alice::run(alice_swap, FixedRate::default()));
+
+            ctx.allow_mining(tx_cancel.txid())?;
+
+            let alice_state = alice_swap.await??;
+            ctx.assert_alice_punished(alice_state).await;
+
+            let (bob_swap, _) = ctx
+                .stop_and_resume_bob_from_db(bob_join_handle, bob_swap_id)
+                .await;
+            let bob_state = bob::run(bob_swap).await?;
+            ctx.assert_bob_punished(bob_state).await;
+
+            Ok(())
+        },
+    )
+    .await;
+}
@@ -0,0 +1,58 @@
+pub mod harness;
+
+use harness::alice_run_until::{is_btc_lock_transaction_seen, is_btc_locked};
+use harness::SlowCancelConfig;
+use swap::asb::FixedRate;
+use swap::protocol::alice::AliceState;
+use swap::protocol::{alice, bob};
+
+#[tokio::test]
+async fn given_btc_lock_is_reorged_out_alice_waits_for_it_to_confirm_again() {
+    harness::setup_test(SlowCancelConfig, |mut ctx| async move {
+        let (bob_swap, _) = ctx.bob_swap().await;
+        let bob_swap = tokio::spawn(bob::run(bob_swap));
+
+        let alice_swap = ctx.alice_next_swap().await;
+        let alice_state = alice::run_until(alice_swap, is_btc_locked, FixedRate::default()).await?;
+
+        let tx_lock = match alice_state {
+            AliceState::BtcLocked { state3 } => state3.tx_lock.clone(),
+            _ => panic!("Alice should be in BtcLocked state"),
+        };
+
+        ctx.reorg_out(&tx_lock).await?;
+
+        ctx.restart_alice().await;
+        let alice_swap = ctx.alice_next_swap().await;
+        assert!(matches!(alice_swap.state, AliceState::BtcLocked { .. }));
+
+        let alice_state = alice::run_until(
+            alice_swap,
+            is_btc_lock_transaction_seen,
+            FixedRate::default(),
+        )
+        .await?;
+        assert!(matches!(
+            alice_state,
+            AliceState::BtcLockTransactionSeen { .. }
+        ));
+
+        ctx.allow_mining(tx_lock.txid())?;
+
+        ctx.restart_alice().await;
+        let alice_swap = ctx.alice_next_swap().await;
+        assert!(matches!(
+            alice_swap.state,
+            AliceState::BtcLockTransactionSeen { .. }
+        ));
+
+        let alice_state = alice::run(alice_swap, FixedRate::default()).await?;
+        ctx.assert_alice_redeemed(alice_state).await;
+
+        let bob_state = bob_swap.await??;
+        ctx.assert_bob_redeemed(bob_state).await;
+
+        Ok(())
+    })
+    .await;
+}
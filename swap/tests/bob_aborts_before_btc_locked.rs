@@ -0,0 +1,64 @@
+pub mod harness;
+
+use harness::bob_run_until::{is_btc_locked, is_swap_setup_completed};
+use harness::SlowCancelConfig;
+use swap::asb::FixedRate;
+use swap::cli;
+use swap::protocol::bob::BobState;
+use swap::protocol::{alice, bob};
+
+#[tokio::test]
+async fn given_bob_aborts_after_swap_setup_then_swap_is_safely_aborted() {
+    harness::setup_test(SlowCancelConfig, |mut ctx| async move {
+        let (bob_swap, bob_join_handle) = ctx.bob_swap().await;
+        let swap_id = bob_swap.id;
+        let bob_swap = tokio::spawn(bob::run_until(bob_swap, is_swap_setup_completed));
+
+        let alice_swap = ctx.alice_next_swap().await;
+        let _alice_swap = tokio::spawn(alice::run(alice_swap, FixedRate::default()));
+
+        let bob_state = bob_swap.await??;
+        assert!(matches!(bob_state, BobState::SwapSetupCompleted(..)));
+
+        let (bob_swap, bob_join_handle) = ctx
+            .stop_and_resume_bob_from_db(bob_join_handle, swap_id)
+            .await;
+        let bob_state = cli::abort(swap_id, bob_swap.bitcoin_wallet, bob_swap.db).await?;
+        assert!(matches!(bob_state, BobState::SafelyAborted));
+
+        let (bob_swap, _) = ctx
+            .stop_and_resume_bob_from_db(bob_join_handle, swap_id)
+            .await;
+        assert!(matches!(bob_swap.state, BobState::SafelyAborted));
+
+        let bob_state = bob::run(bob_swap).await?;
+        assert!(matches!(bob_state, BobState::SafelyAborted));
+
+        Ok(())
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn given_bob_locked_btc_then_swap_cannot_be_aborted() {
+    harness::setup_test(SlowCancelConfig, |mut ctx| async move {
+        let (bob_swap, bob_join_handle) = ctx.bob_swap().await;
+        let swap_id = bob_swap.id;
+        let bob_swap = tokio::spawn(bob::run_until(bob_swap, is_btc_locked));
+
+        let alice_swap = ctx.alice_next_swap().await;
+        let _alice_swap = tokio::spawn(alice::run(alice_swap, FixedRate::default()));
+
+        let bob_state = bob_swap.await??;
+        assert!(matches!(bob_state, BobState::BtcLocked { .. }));
+
+        let (bob_swap, _) = ctx
+            .stop_and_resume_bob_from_db(bob_join_handle, swap_id)
+            .await;
+        let result = cli::abort(swap_id, bob_swap.bitcoin_wallet, bob_swap.db).await;
+        assert!(result.is_err());
+
+        Ok(())
+    })
+    .await;
+}
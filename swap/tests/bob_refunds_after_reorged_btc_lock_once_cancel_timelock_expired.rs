@@ -0,0 +1,55 @@
+pub mod harness;
+
+use harness::alice_run_until::is_btc_locked;
+use harness::bob_run_until::is_cancel_timelock_expired;
+use harness::{BitcoinBackend, FastCancelConfig};
+use swap::asb::FixedRate;
+use swap::protocol::alice::AliceState;
+use swap::protocol::bob::BobState;
+use swap::protocol::{alice, bob};
+
+/// Alice does not lock the Monero. Once the cancel timelock expired the lock
+/// transaction is reorganized out of the blockchain, Bob waits for the cancel
+/// timelock to expire again before cancelling and refunding.
+#[tokio::test]
+async fn given_btc_lock_is_reorged_out_after_cancel_timelock_expired_bob_waits_before_cancelling() {
+    harness::setup_test_with_bitcoin_backend(
+        FastCancelConfig,
+        BitcoinBackend::Bitcoind,
+        |mut ctx| async move {
+            let (bob_swap, bob_join_handle) = ctx.bob_swap().await;
+            let bob_swap_id = bob_swap.id;
+            let bob_swap = tokio::spawn(bob::run_until(bob_swap, is_cancel_timelock_expired));
+
+            let alice_swap = ctx.alice_next_swap().await;
+            let alice_state =
+                alice::run_until(alice_swap, is_btc_locked, FixedRate::default()).await?;
+            assert!(matches!(alice_state, AliceState::BtcLocked { .. }));
+
+            let bob_state = bob_swap.await??;
+            let tx_lock = match &bob_state {
+                BobState::CancelTimelockExpired(state6) => state6.tx_lock().clone(),
+                _ => panic!("Bob in unexpected state {}", bob_state),
+            };
+
+            ctx.reorg_out(&tx_lock).await?;
+
+            let (bob_swap, _) = ctx
+                .stop_and_resume_bob_from_db(bob_join_handle, bob_swap_id)
+                .await;
+            assert!(matches!(
+                bob_swap.state,
+                BobState::CancelTimelockExpired(..)
+            ));
+            let bob_swap = tokio::spawn(bob::run(bob_swap));
+
+            ctx.allow_mining(tx_lock.txid())?;
+
+            let bob_state = bob_swap.await??;
+            ctx.assert_bob_refunded(bob_state).await;
+
+            Ok(())
+        },
+    )
+    .await;
+}
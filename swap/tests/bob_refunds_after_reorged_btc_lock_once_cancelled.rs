@@ -0,0 +1,60 @@
+pub mod harness;
+
+use harness::alice_run_until::is_btc_locked;
+use harness::bob_run_until::is_btc_cancelled;
+use harness::{BitcoinBackend, FastCancelConfig};
+use swap::asb::FixedRate;
+use swap::protocol::alice::AliceState;
+use swap::protocol::bob::BobState;
+use swap::protocol::{alice, bob};
+
+/// Alice does not lock the Monero and Bob cancels. The lock transaction, and
+/// with it the cancel transaction, is reorganized out of the blockchain. Bob
+/// waits for the cancel timelock to expire again, publishes the cancel
+/// transaction again and refunds.
+#[tokio::test]
+async fn given_btc_lock_is_reorged_out_after_cancel_bob_cancels_again_and_refunds() {
+    harness::setup_test_with_bitcoin_backend(
+        FastCancelConfig,
+        BitcoinBackend::Bitcoind,
+        |mut ctx| async move {
+            let (bob_swap, bob_join_handle) = ctx.bob_swap().await;
+            let bob_swap_id = bob_swap.id;
+            let bob_bitcoin_wallet = bob_swap.bitcoin_wallet.clone();
+            let bob_swap = tokio::spawn(bob::run_until(bob_swap, is_btc_cancelled));
+
+            let alice_swap = ctx.alice_next_swap().await;
+            let alice_state =
+                alice::run_until(alice_swap, is_btc_locked, FixedRate::default()).await?;
+            assert!(matches!(alice_state, AliceState::BtcLocked { .. }));
+
+            let bob_state = bob_swap.await??;
+            let (tx_lock, tx_cancel) = match &bob_state {
+                BobState::BtcCancelled(state6) => (state6.tx_lock().clone(), state6.tx_cancel()),
+                _ => panic!("Bob in unexpected state {}", bob_state),
+            };
+
+            bob_bitcoin_wallet
+                .subscribe_to(tx_cancel)
+                .await
+                .wait_until_final()
+                .await?;
+
+            ctx.reorg_out(&tx_lock).await?;
+
+            let (bob_swap, _) = ctx
+                .stop_and_resume_bob_from_db(bob_join_handle, bob_swap_id)
+                .await;
+            assert!(matches!(bob_swap.state, BobState::BtcCancelled(..)));
+            let bob_swap = tokio::spawn(bob::run(bob_swap));
+
+            ctx.allow_mining(tx_lock.txid())?;
+
+            let bob_state = bob_swap.await??;
+            ctx.assert_bob_refunded(bob_state).await;
+
+            Ok(())
+        },
+    )
+    .await;
+}
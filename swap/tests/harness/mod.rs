@@ -3,19 +3,24 @@ mod electrs;
 
 use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
+use bdk::bitcoincore_rpc::{self, Auth, RpcApi};
 use bitcoin_harness::{BitcoindRpcApi, Client};
 use futures::Future;
 use get_port::get_port;
 use libp2p::core::Multiaddr;
 use libp2p::PeerId;
 use monero_harness::{image, Monero};
+use serde_json::json;
 use std::cmp::Ordering;
 use std::fmt;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use swap::asb::FixedRate;
-use swap::bitcoin::{CancelTimelock, PunishTimelock, TxCancel, TxPunish, TxRedeem, TxRefund};
+use swap::bitcoin::wallet::Watchable;
+use swap::bitcoin::{
+    CancelTimelock, PunishTimelock, TxCancel, TxKind, TxPunish, TxRedeem, TxRefund,
+};
 use swap::database::SqliteDatabase;
 use swap::env::{Config, GetConfig};
 use swap::fs::ensure_directory_exists;
@@ -23,7 +28,7 @@ use swap::network::rendezvous::XmrBtcNamespace;
 use swap::network::swarm;
 use swap::protocol::alice::{AliceState, Swap};
 use swap::protocol::bob::BobState;
-use swap::protocol::{alice, bob};
+use swap::protocol::{alice, bob, Database};
 use swap::seed::Seed;
 use swap::{asb, bitcoin, cli, env, monero};
 use tempfile::{tempdir, NamedTempFile};
@@ -37,11 +42,30 @@ use tracing_subscriber::util::SubscriberInitExt;
 use url::Url;
 use uuid::Uuid;
 
-pub async fn setup_test<T, F, C>(_config: C, testfn: T)
+pub async fn setup_test<T, F, C>(config: C, testfn: T)
 where
     T: Fn(TestContext) -> F,
     F: Future<Output = Result<()>>,
     C: GetConfig,
+{
+    setup_test_with_bitcoin_backend(config, BitcoinBackend::Electrum, testfn).await
+}
+
+/// The chain backend used by the Bitcoin wallets of Alice and Bob.
+#[derive(Debug, Clone, Copy)]
+pub enum BitcoinBackend {
+    Electrum,
+    Bitcoind,
+}
+
+pub async fn setup_test_with_bitcoin_backend<T, F, C>(
+    _config: C,
+    bitcoin_backend: BitcoinBackend,
+    testfn: T,
+) where
+    T: Fn(TestContext) -> F,
+    F: Future<Output = Result<()>>,
+    C: GetConfig,
 {
     let cli = Cli::default();
 
@@ -65,6 +89,17 @@ where
         .electrs
         .get_host_port(electrs::RPC_PORT)
         .expect("Could not map electrs rpc port");
+    let bitcoin_backend = match bitcoin_backend {
+        BitcoinBackend::Electrum => bitcoin::Backend::Electrum(
+            Url::parse(&format!("tcp://@localhost:{}", electrs_rpc_port))
+                .unwrap()
+                .into(),
+        ),
+        BitcoinBackend::Bitcoind => bitcoin::Backend::Bitcoind(bitcoin::BitcoindRpc {
+            url: containers.bitcoind_url.clone(),
+            cookie_file: None,
+        }),
+    };
 
     let alice_seed = Seed::random().unwrap();
     let (alice_bitcoin_wallet, alice_monero_wallet) = init_test_wallets(
@@ -73,7 +108,7 @@ where
         &monero,
         alice_starting_balances.clone(),
         tempdir().unwrap().path(),
-        electrs_rpc_port,
+        bitcoin_backend.clone(),
         &alice_seed,
         env_config,
     )
@@ -100,11 +135,11 @@ where
 
     let (bob_bitcoin_wallet, bob_monero_wallet) = init_test_wallets(
         MONERO_WALLET_NAME_BOB,
-        containers.bitcoind_url,
+        containers.bitcoind_url.clone(),
         &monero,
         bob_starting_balances.clone(),
         tempdir().unwrap().path(),
-        electrs_rpc_port,
+        bitcoin_backend.clone(),
         &bob_seed,
         env_config,
     )
@@ -138,6 +173,7 @@ where
         bob_starting_balances,
         bob_bitcoin_wallet,
         bob_monero_wallet,
+        bitcoind_url: containers.bitcoind_url,
     };
 
     testfn(test).await.unwrap()
@@ -279,7 +315,7 @@ async fn init_test_wallets(
     monero: &Monero,
     starting_balances: StartingBalances,
     datadir: &Path,
-    electrum_rpc_port: u16,
+    bitcoin_backend: bitcoin::Backend,
     seed: &Seed,
     env_config: Config,
 ) -> (Arc<bitcoin::Wallet>, Arc<monero::Wallet>) {
@@ -303,18 +339,15 @@ async fn init_test_wallets(
     .await
     .unwrap();
 
-    let electrum_rpc_url = {
-        let input = format!("tcp://@localhost:{}", electrum_rpc_port);
-        Url::parse(&input).unwrap()
-    };
-
     let btc_wallet = swap::bitcoin::Wallet::new(
-        electrum_rpc_url,
+        bitcoin_backend,
         datadir,
         seed.derive_extended_private_key(env_config.bitcoin_network)
             .expect("Could not create extended private key from seed"),
         env_config,
         1,
+        bitcoin::FeePolicies::default(),
+        bitcoin::FeeEstimation::default(),
     )
     .await
     .expect("could not init btc wallet");
@@ -355,6 +388,8 @@ async fn init_test_wallets(
 const MONERO_WALLET_NAME_BOB: &str = "bob";
 const MONERO_WALLET_NAME_ALICE: &str = "alice";
 const BITCOIN_TEST_WALLET_NAME: &str = "testwallet";
+/// Large enough to keep a transaction out of any block the miner builds.
+const PRIORITY_DELTA_SATS: i64 = 100_000_000;
 
 #[derive(Debug, Clone)]
 pub struct StartingBalances {
@@ -414,8 +449,6 @@ struct BobParams {
 
 impl BobParams {
     pub async fn new_swap_from_db(&self, swap_id: Uuid) -> Result<(bob::Swap, cli::EventLoop)> {
-        let (event_loop, handle) = self.new_eventloop(swap_id).await?;
-
         if let Some(parent_dir) = self.db_path.parent() {
             ensure_directory_exists(parent_dir)?;
         }
@@ -423,6 +456,7 @@ impl BobParams {
             tokio::fs::File::create(&self.db_path).await?;
         }
         let db = Arc::new(SqliteDatabase::open(&self.db_path).await?);
+        let (event_loop, handle) = self.new_eventloop(swap_id, db.clone()).await?;
 
         let swap = bob::Swap::from_db(
             db,
@@ -444,8 +478,6 @@ impl BobParams {
     ) -> Result<(bob::Swap, cli::EventLoop)> {
         let swap_id = Uuid::new_v4();
 
-        let (event_loop, handle) = self.new_eventloop(swap_id).await?;
-
         if let Some(parent_dir) = self.db_path.parent() {
             ensure_directory_exists(parent_dir)?;
         }
@@ -453,6 +485,7 @@ impl BobParams {
             tokio::fs::File::create(&self.db_path).await?;
         }
         let db = Arc::new(SqliteDatabase::open(&self.db_path).await?);
+        let (event_loop, handle) = self.new_eventloop(swap_id, db.clone()).await?;
 
         let swap = bob::Swap::new(
             db,
@@ -464,6 +497,8 @@ impl BobParams {
             self.monero_wallet.get_main_address(),
             self.bitcoin_wallet.new_address().await?,
             btc_amount,
+            None,
+            None,
         );
 
         Ok((swap, event_loop))
@@ -472,23 +507,27 @@ impl BobParams {
     pub async fn new_eventloop(
         &self,
         swap_id: Uuid,
+        db: Arc<dyn Database + Send + Sync>,
     ) -> Result<(cli::EventLoop, cli::EventLoopHandle)> {
         let tor_socks5_port = get_port()
             .expect("We don't care about Tor in the tests so we get a free port to disable it.");
         let identity = self.seed.derive_libp2p_identity();
 
         let behaviour = cli::Behaviour::new(
-            self.alice_peer_id,
             self.env_config,
             self.bitcoin_wallet.clone(),
             (identity.clone(), XmrBtcNamespace::Testnet),
         );
-        let mut swarm = swarm::cli(identity.clone(), tor_socks5_port, behaviour).await?;
-        swarm
-            .behaviour_mut()
-            .add_address(self.alice_peer_id, self.alice_address.clone());
+        let swarm = swarm::cli(identity.clone(), tor_socks5_port, behaviour).await?;
 
-        cli::EventLoop::new(swap_id, swarm, self.alice_peer_id)
+        let (event_loop, controller) = cli::EventLoop::new(swarm, db)?;
+        let handle = controller.swap_handle(
+            swap_id,
+            self.alice_peer_id,
+            vec![self.alice_address.clone()],
+        )?;
+
+        Ok((event_loop, handle))
     }
 }
 
@@ -531,6 +570,8 @@ pub struct TestContext {
     bob_starting_balances: StartingBalances,
     bob_bitcoin_wallet: Arc<bitcoin::Wallet>,
     bob_monero_wallet: Arc<monero::Wallet>,
+
+    bitcoind_url: Url,
 }
 
 impl TestContext {
@@ -583,6 +624,75 @@ impl TestContext {
         (swap, BobApplicationHandle(join_handle))
     }
 
+    /// Invalidates the block that includes the given transaction and keeps
+    /// the transaction from being mined again until
+    /// [`TestContext::allow_mining`] is called.
+    ///
+    /// Returns once neither Alice's nor Bob's wallet sees the transaction as
+    /// confirmed anymore.
+    pub async fn reorg_out<T>(&self, tx: &T) -> Result<()>
+    where
+        T: Watchable,
+    {
+        let client = self.bitcoind_rpc_client()?;
+        let txid = tx.id();
+
+        let block_hash = client
+            .get_raw_transaction_info(&txid, None)?
+            .blockhash
+            .context("Bitcoin transaction is not included in a block")?;
+        client.call::<bool>(
+            "prioritisetransaction",
+            &[json!(txid), json!(0), json!(-PRIORITY_DELTA_SATS)],
+        )?;
+        client.invalidate_block(&block_hash)?;
+
+        timeout(Duration::from_secs(60), async {
+            while self
+                .alice_bitcoin_wallet
+                .status_of_script(tx)
+                .await?
+                .is_confirmed()
+                || self
+                    .bob_bitcoin_wallet
+                    .status_of_script(tx)
+                    .await?
+                    .is_confirmed()
+            {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+
+            Ok::<_, anyhow::Error>(())
+        })
+        .await
+        .context("The wallets did not notice the reorg within 60 seconds")??;
+
+        Ok(())
+    }
+
+    /// Lets the miner include a transaction passed to
+    /// [`TestContext::reorg_out`] again.
+    pub fn allow_mining(&self, txid: bitcoin::Txid) -> Result<()> {
+        self.bitcoind_rpc_client()?.call::<bool>(
+            "prioritisetransaction",
+            &[json!(txid), json!(0), json!(PRIORITY_DELTA_SATS)],
+        )?;
+
+        Ok(())
+    }
+
+    fn bitcoind_rpc_client(&self) -> Result<bitcoincore_rpc::Client> {
+        let mut url = self.bitcoind_url.clone();
+        let auth = Auth::UserPass(
+            url.username().to_owned(),
+            url.password().unwrap_or_default().to_owned(),
+        );
+        let _ = url.set_username("");
+        let _ = url.set_password(None);
+
+        Ok(bitcoincore_rpc::Client::new(url.as_str(), auth)?)
+    }
+
     pub async fn assert_alice_redeemed(&mut self, state: AliceState) {
         assert!(matches!(state, AliceState::BtcRedeemed));
 
@@ -683,12 +793,16 @@ impl TestContext {
 
         let cancel_fee = self
             .alice_bitcoin_wallet
-            .estimate_fee(TxCancel::weight(), self.btc_amount)
+            .estimate_fee(TxKind::Cancel, TxCancel::weight(), self.btc_amount)
             .await
             .expect("To estimate fee correctly");
         let refund_fee = self
             .alice_bitcoin_wallet
-            .estimate_fee(TxRefund::weight(), self.btc_amount)
+            .estimate_fee(
+                TxKind::Refund,
+                TxRefund::weight(&self.bob_bitcoin_wallet.new_address().await.unwrap()),
+                self.btc_amount,
+            )
             .await
             .expect("To estimate fee correctly");
 
@@ -731,7 +845,11 @@ impl TestContext {
     async fn alice_redeemed_btc_balance(&self) -> bitcoin::Amount {
         let fee = self
             .alice_bitcoin_wallet
-            .estimate_fee(TxRedeem::weight(), self.btc_amount)
+            .estimate_fee(
+                TxKind::Redeem,
+                TxRedeem::weight(&self.alice_bitcoin_wallet.new_address().await.unwrap()),
+                self.btc_amount,
+            )
             .await
             .expect("To estimate fee correctly");
         self.alice_starting_balances.btc + self.btc_amount - fee
@@ -774,12 +892,16 @@ impl TestContext {
     async fn alice_punished_btc_balance(&self) -> bitcoin::Amount {
         let cancel_fee = self
             .alice_bitcoin_wallet
-            .estimate_fee(TxCancel::weight(), self.btc_amount)
+            .estimate_fee(TxKind::Cancel, TxCancel::weight(), self.btc_amount)
             .await
             .expect("To estimate fee correctly");
         let punish_fee = self
             .alice_bitcoin_wallet
-            .estimate_fee(TxPunish::weight(), self.btc_amount)
+            .estimate_fee(
+                TxKind::Punish,
+                TxPunish::weight(&self.alice_bitcoin_wallet.new_address().await.unwrap()),
+                self.btc_amount,
+            )
             .await
             .expect("To estimate fee correctly");
         self.alice_starting_balances.btc + self.btc_amount - cancel_fee - punish_fee
@@ -964,6 +1086,14 @@ struct Containers<'a> {
 pub mod alice_run_until {
     use swap::protocol::alice::AliceState;
 
+    pub fn is_btc_lock_transaction_seen(state: &AliceState) -> bool {
+        matches!(state, AliceState::BtcLockTransactionSeen { .. })
+    }
+
+    pub fn is_btc_locked(state: &AliceState) -> bool {
+        matches!(state, AliceState::BtcLocked { .. })
+    }
+
     pub fn is_xmr_lock_transaction_sent(state: &AliceState) -> bool {
         matches!(state, AliceState::XmrLockTransactionSent { .. })
     }
@@ -971,11 +1101,23 @@ pub mod alice_run_until {
     pub fn is_encsig_learned(state: &AliceState) -> bool {
         matches!(state, AliceState::EncSigLearned { .. })
     }
+
+    pub fn is_cancel_timelock_expired(state: &AliceState) -> bool {
+        matches!(state, AliceState::CancelTimelockExpired { .. })
+    }
+
+    pub fn is_btc_cancelled(state: &AliceState) -> bool {
+        matches!(state, AliceState::BtcCancelled { .. })
+    }
 }
 
 pub mod bob_run_until {
     use swap::protocol::bob::BobState;
 
+    pub fn is_swap_setup_completed(state: &BobState) -> bool {
+        matches!(state, BobState::SwapSetupCompleted(..))
+    }
+
     pub fn is_btc_locked(state: &BobState) -> bool {
         matches!(state, BobState::BtcLocked { .. })
     }
@@ -991,6 +1133,14 @@ pub mod bob_run_until {
     pub fn is_encsig_sent(state: &BobState) -> bool {
         matches!(state, BobState::EncSigSent(..))
     }
+
+    pub fn is_cancel_timelock_expired(state: &BobState) -> bool {
+        matches!(state, BobState::CancelTimelockExpired(..))
+    }
+
+    pub fn is_btc_cancelled(state: &BobState) -> bool {
+        matches!(state, BobState::BtcCancelled(..))
+    }
 }
 
 pub struct SlowCancelConfig;